@@ -208,7 +208,7 @@ fn wgsl_to_wgpu_generation(resolved_shaders: &HashMap<OsString, String>, binding
         "diff_display.fragment",
         "binary_distance_approx.compute",
         "binary_distance_approx.fragment",
-        // "max_norm.compute",
+        "max_norm.compute",
     ]
     .map(OsString::from);
 
@@ -2,13 +2,88 @@
 #import "binary_distance_approx.buffer.wgsl";
 #import "binary_distance_approx.wgsl";
 
+// The estimate is binned onto the grid, padded by `kde_options.padding` cells to each side,
+// then the kernel is applied to the rows and after that to the columns.
+// The bins are kept between computations, so only the records added since get binned.
+// See `kde::binned_density` for the reference.
+// Every entry is its own dispatch, WebGPU makes the storage writes of one visible to the next.
+
 @group(1) @binding(40)
 var<storage, read_write> compute_output: array<f32>;
 
+// How long the chain stayed in each cell of the padded grid.
+// These are integers, so adding them up atomically is exact, unlike with floats.
+@group(1) @binding(44)
+var<storage, read_write> bins: array<atomic<u32>>;
+
+// The bins with the kernel applied to the rows, only for the columns of the grid and as many rows as the kernel reaches.
+@group(1) @binding(45)
+var<storage, read_write> rows_convolved: array<f32>;
+
+const BIN_WORKGROUP_SIZE = 64u;
+
+fn grid_size() -> vec2<u32> {
+    return vec2(u32(resolution_info.resolution.x), u32(resolution_info.resolution.y));
+}
+
+fn padded_grid_size() -> vec2<u32> {
+    return grid_size() + vec2(2u * kde_options.padding);
+}
+
+// The one dimensional kernel `offset` cells from its center, scaled to the bandwidth.
+fn tap(offset: i32) -> f32 {
+    let canvas_res = resolution_info.resolution;
+    let cell_size = 2.0 * resolution_info.view_half_extent / min(canvas_res.x, canvas_res.y);
+    let bandwidth = kde_options.bandwidth;
+    return kernel_factor(f32(offset) * cell_size / bandwidth) / bandwidth;
+}
+
+@compute
+@workgroup_size(BIN_WORKGROUP_SIZE)
+fn bin(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+    let padded = padded_grid_size();
+    // The dispatch size is limited, so an invocation might bin several points.
+    // Starting at 1, as the first record is either the dummy one (see `binary_distance_approx`) or was binned before.
+    for (var i = global_id.x + 1u; i < arrayLength(&accepted); i += num_workgroups.x * BIN_WORKGROUP_SIZE) {
+        let el = accepted[i];
+        let cell = round(ndc_to_canvas_coord(el.position)) + f32(kde_options.padding);
+        if (all(cell >= vec2(0.0)) && all(cell < vec2<f32>(padded))) {
+            atomicAdd(&bins[u32(cell.y) * padded.x + u32(cell.x)], el.remain_count + 1u);
+        }
+    }
+}
+
+@compute
+@workgroup_size(16, 16, 1)
+fn convolve_rows(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = grid_size();
+    let padded = padded_grid_size();
+    // Also the rows the kernel reaches beyond the grid, as the columns still need them.
+    if (global_id.x >= size.x || global_id.y >= size.y + 2u * kde_options.margin) {
+        return;
+    }
+    let margin = i32(kde_options.margin);
+    let unused_padding = kde_options.padding - kde_options.margin;
+    let row_start = (global_id.y + unused_padding) * padded.x + global_id.x + unused_padding;
+    var sum = 0.0;
+    for (var offset = -margin; offset <= margin; offset += 1) {
+        sum += tap(offset) * f32(atomicLoad(&bins[row_start + u32(offset + margin)]));
+    }
+    rows_convolved[global_id.y * size.x + global_id.x] = sum;
+}
+
 @compute
-@workgroup_size(1)
-fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let ndc = canvas_coord_to_ndc_int(global_id.xy);
-    let index = to_buffer_idx(global_id.xy);
-    compute_output[index] = binary_distance_approx(ndc);
+@workgroup_size(16, 16, 1)
+fn convolve_columns(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = grid_size();
+    if (any(global_id.xy >= size)) {
+        return;
+    }
+    let margin = i32(kde_options.margin);
+    var sum = 0.0;
+    for (var offset = -margin; offset <= margin; offset += 1) {
+        sum += tap(offset) * rows_convolved[(global_id.y + u32(offset + margin)) * size.x + global_id.x];
+    }
+    let total_point_count = f32(max(count_info.total_point_count, 1u));
+    compute_output[to_buffer_idx(global_id.xy)] = sum / total_point_count;
 }
@@ -2,29 +2,40 @@
 #import "fullscreen_quad.vertex.wgsl";
 #import "binary_distance_approx.buffer.wgsl";
 #import "multimodal_gaussian.wgsl";
-#import "helpers.wgsl";
+#import "color_map.wgsl";
 
 @group(1) @binding(40)
 var<storage, read> compute_output: array<f32>;
 
+// The grid `compute_output` was computed on, independent of the canvas, see `ComputeGrid` on the CPU side.
+@group(1) @binding(41)
+var<uniform> compute_grid: ResolutionInfo;
+
+fn compute_output_at(cell: vec2<i32>) -> f32 {
+    let size = vec2<i32>(compute_grid.resolution);
+    if (any(cell < vec2(0)) || any(cell >= size)) {
+        return 0.0;
+    }
+    return compute_output[cell.y * size.x + cell.x];
+}
+
+// Bilinear interpolation between the four cells around `ndc_coord`.
+fn sample_compute_output(ndc_coord: vec2<f32>) -> f32 {
+    let cell = ndc_to_canvas_coord_of(compute_grid, ndc_coord);
+    let lower = floor(cell);
+    let weight = cell - lower;
+    let base = vec2<i32>(lower);
+    let top = mix(compute_output_at(base), compute_output_at(base + vec2(1, 0)), weight.x);
+    let bottom = mix(compute_output_at(base + vec2(0, 1)), compute_output_at(base + vec2(1, 1)), weight.x);
+    return mix(top, bottom, weight.y);
+}
+
 @fragment
 fn fs_main(@builtin(position) canvas_coords: vec4<f32>) -> @location(0) vec4<f32> {
-    let index = to_buffer_idx(vec2(u32(canvas_coords.x), u32(canvas_coords.y)));
-    let approx_density = compute_output[index];
     let ndc_coord = canvas_coord_to_ndc(canvas_coords.xy);
+    let approx_density = sample_compute_output(ndc_coord);
     let target_density = calc_gaussian_density(ndc_coord);
     let diff = target_density - approx_density;
 
-    let diff_paint = percentage_logscaled(abs(diff));
-
-    var color = vec3(0.0);
-
-    if sign(diff) == 1 {
-        color[1] = diff_paint;
-    } else {
-        color[2] = sqrt(diff_paint);
-        color[0] = sqrt(diff_paint);
-    }
-
-    return vec4(color, 1.0);
+    return diff_color(diff);
 }
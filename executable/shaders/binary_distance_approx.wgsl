@@ -1,20 +1,54 @@
 #import "rwmh_info.wgsl";
 
+const PI_KDE = radians(180.0);
+
 struct BinaryDistanceApproxOptions {
-    window_radius: f32,
+    // The standard deviation of each coordinate, for all kernels.
+    bandwidth: f32,
+    // See `Kernel::shader_id` on the CPU side.
+    kernel: u32,
+    // Cells the kernel reaches for the binned estimate, see `Grid::margin` on the CPU side.
+    margin: u32,
+    // Cells the bins are padded with, at least `margin`.
+    // Fixed, so that the bins don't depend on the bandwidth and can be added to.
+    padding: u32,
 }
 
-// if reintroduced, increase bindgroup id on others again.
-// @group(2) @binding(0)
-// var<uniform> diff_display_options: BinaryDistanceApproxOptions;
+@group(2) @binding(0)
+var<uniform> kde_options: BinaryDistanceApproxOptions;
 
-fn binary_distance_approx(ndc_coord: vec2<f32>) -> f32 {
-    let total_point_count = count_info.total_point_count;
+const KERNEL_TOP_HAT = 0u;
+const KERNEL_EPANECHNIKOV = 1u;
+
+// Value of the one dimensional kernel at `offset`, for a bandwidth of 1.
+// Each integrates to 1, see `Kernel::factor` for the reference.
+fn kernel_factor(offset: f32) -> f32 {
+    switch kde_options.kernel {
+        case KERNEL_TOP_HAT: {
+            let radius = sqrt(3.0);
+            return select(0.0, 1.0 / (2.0 * radius), abs(offset) <= radius);
+        }
+        case KERNEL_EPANECHNIKOV: {
+            let radius = sqrt(5.0);
+            let relative = offset / radius;
+            return select(0.0, 3.0 / (4.0 * radius) * (1.0 - relative * relative), abs(relative) <= 1.0);
+        }
+        default: {
+            return exp(-0.5 * offset * offset) / sqrt(2.0 * PI_KDE);
+        }
+    }
+}
+
+// The kernels are products of the one dimensional one in each coordinate.
+fn kernel_value(offset: vec2<f32>) -> f32 {
+    return kernel_factor(offset.x) * kernel_factor(offset.y);
+}
 
-    let window_radius = 0.1;
-    // let window_radius = diff_display_options.window_radius;
+// The kernel density estimate of the accepted points, see `kde::density` for the reference.
+fn binary_distance_approx(ndc_coord: vec2<f32>) -> f32 {
+    let bandwidth = kde_options.bandwidth;
 
-    var prob_unnorm: u32 = 0;
+    var weighted = 0.0;
 
     // REALLY ugly fix, but I need to start at 1 so that I never submit
     // a zero sized buffer, which otherwise causes WebGPU to refuse the draw call.
@@ -26,10 +60,8 @@ fn binary_distance_approx(ndc_coord: vec2<f32>) -> f32 {
         let position = el.position;
         let remain_count = el.remain_count;
 
-        if distance(ndc_coord, position) <= window_radius {
-            prob_unnorm += remain_count + 1;
-        }
+        weighted += f32(remain_count + 1) * kernel_value((ndc_coord - position) / bandwidth);
     }
-    // count_info.max_remain_count
-    return f32(prob_unnorm) / f32(count_info.max_remain_count * 30);
+    let total_point_count = f32(max(count_info.total_point_count, 1u));
+    return weighted / (total_point_count * bandwidth * bandwidth);
 }
@@ -1,7 +1,11 @@
 struct ResolutionInfo {
     resolution: vec2<f32>,
+    // The shown part of the sample space, see `View` on the CPU side.
+    view_center: vec2<f32>,
+    view_half_extent: f32,
     // See corresponding bindinggroup for reason
-    _pad: vec2<f32>,
+    _pad: f32,
+    _pad_2: vec2<f32>,
 }
 
 @group(0) @binding(0)
@@ -11,9 +15,23 @@ fn canvas_coord_to_ndc(canvas_coord: vec2<f32>) -> vec2<f32> {
     let canvas_res = resolution_info.resolution;
     let min_res = min(canvas_res.x, canvas_res.y);
     let center_offset = (canvas_res - vec2(min_res)) / 2.0;
-    return ((canvas_coord - center_offset) / min_res) * 2.0 - 1.0;
+    let in_view = ((canvas_coord - center_offset) / min_res) * 2.0 - 1.0;
+    return resolution_info.view_center + in_view * resolution_info.view_half_extent;
 }
 
 fn canvas_coord_to_ndc_int(canvas_coord: vec2<u32>) -> vec2<f32> {
     return canvas_coord_to_ndc(vec2(f32(canvas_coord.x), f32(canvas_coord.y)));
 }
+
+fn ndc_to_canvas_coord(ndc: vec2<f32>) -> vec2<f32> {
+    return ndc_to_canvas_coord_of(resolution_info, ndc);
+}
+
+// For other grids than the canvas, described in the same terms.
+fn ndc_to_canvas_coord_of(info: ResolutionInfo, ndc: vec2<f32>) -> vec2<f32> {
+    let canvas_res = info.resolution;
+    let min_res = min(canvas_res.x, canvas_res.y);
+    let center_offset = (canvas_res - vec2(min_res)) / 2.0;
+    let in_view = (ndc - info.view_center) / info.view_half_extent;
+    return (in_view + 1.0) / 2.0 * min_res + center_offset;
+}
@@ -0,0 +1,58 @@
+// Enough for the longest map, see `ColorMap::MAX_STOPS` on the CPU side.
+const MAX_COLOR_STOPS = 11u;
+
+struct ColorMapOptions {
+    // sRGB colors evenly spaced along the map, the alpha is unused.
+    stops: array<vec4<f32>, MAX_COLOR_STOPS>,
+    stop_count: u32,
+    // See `Scaling::shader_id` on the CPU side.
+    scaling: u32,
+    // The logarithmic scaling shows this many decades below 1.
+    log_decades: f32,
+    // See corresponding bindinggroup for reason
+    _pad: f32,
+}
+
+@group(0) @binding(1)
+var<uniform> color_map: ColorMapOptions;
+
+const SCALING_LINEAR = 0u;
+const SCALING_LOG = 1u;
+const SCALING_SQRT = 2u;
+
+// Position of `value` along the map, for values in [0, 1].
+// See `Scaling::position` for the reference.
+fn color_map_position(value: f32) -> f32 {
+    let clamped = clamp(value, 0.0, 1.0);
+    switch color_map.scaling {
+        case SCALING_LOG: {
+            if (clamped <= 0.0) {
+                return 0.0;
+            }
+            return max(1.0 + log(clamped) / (color_map.log_decades * log(10.0)), 0.0);
+        }
+        case SCALING_SQRT: {
+            return sqrt(clamped);
+        }
+        default: {
+            return clamped;
+        }
+    }
+}
+
+// Linear interpolation between the stops around `position`.
+fn color_map_at(position: f32) -> vec3<f32> {
+    let last = color_map.stop_count - 1u;
+    let along = clamp(position, 0.0, 1.0) * f32(last);
+    let lower = min(u32(along), last - 1u);
+    return mix(color_map.stops[lower].rgb, color_map.stops[lower + 1u].rgb, along - f32(lower));
+}
+
+fn density_color(density: f32) -> vec4<f32> {
+    return vec4(color_map_at(color_map_position(density)), 1.0);
+}
+
+// Zero is in the middle of the map, the magnitudes are scaled the same way to either side.
+fn diff_color(diff: f32) -> vec4<f32> {
+    return vec4(color_map_at(0.5 + 0.5 * sign(diff) * color_map_position(abs(diff))), 1.0);
+}
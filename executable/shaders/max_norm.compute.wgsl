@@ -1,42 +1,86 @@
-#import "binary_distance_approx.buffer.wgsl";
+// Normalizes `compute_output` by its maximum.
+//
+// Each workgroup reduces its chunk of the values to their maximum, pass after pass until one value is left,
+// then every value gets divided by that.
+// Every pass is its own dispatch, as nothing synchronizes workgroups within one
+// (`storageBarrier` only does within a workgroup, see https://github.com/gpuweb/gpuweb/issues/3774).
+// See `Plan::new` on the CPU side for how the passes are laid out.
 
-// This is not used for now.
-// Perhaps I'll figure out the synchronization at some point, but as it stands, this is probably not correctly synchronized,
-// And doing this on the CPU is so much easier ;-P.
+struct ReductionPass {
+    // Whether the pass reads `compute_output`, the later passes read `maxima`.
+    from_output: u32,
+    // The values the pass reads from its source.
+    input_offset: u32,
+    input_len: u32,
+    // Where in `maxima` the pass writes, for the normalization where the maximum is.
+    output_offset: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> reduction_pass: ReductionPass;
+
+@group(1) @binding(40)
+var<storage, read_write> compute_output: array<f32>;
+
+// The maxima of the workgroups of all passes, one after the other.
+@group(1) @binding(46)
+var<storage, read_write> maxima: array<f32>;
 
-@group(1) @binding(41)
-var<storage, read> unnormalized: array<u32>;
+const REDUCTION_WORKGROUP_SIZE = 256u;
 
-@group(1) @binding(42)
-var<storage, read_write> normalized: array<f32>;
+var<workgroup> workgroup_maxima: array<f32, REDUCTION_WORKGROUP_SIZE>;
 
-// apparently initialized to 0:
-// https://github.com/gpuweb/cts/issues/1487
-@group(1) @binding(43)
-// this ought to be legal according to spec:
-// > Atomic types may only be instantiated by variables in the workgroup address space or by storage buffer variables with a read_write access mode.
-// But this either errors on "missing binding for a storage buffer" or on "unsupported type for binding fields"
-var<storage, read_write> maximum: atomic<u32>;
-var<workgroup> workgroup_maximum: atomic<u32>;
+// There might be more workgroups than fit into one dimension of a dispatch.
+fn flat_workgroup_idx(workgroup_id: vec3<u32>, num_workgroups: vec3<u32>) -> u32 {
+    return workgroup_id.y * num_workgroups.x + workgroup_id.x;
+}
+
+fn reduction_input(idx: u32) -> f32 {
+    // the densities are never negative.
+    if (idx >= reduction_pass.input_len) {
+        return 0.0;
+    }
+    if (reduction_pass.from_output != 0u) {
+        return compute_output[reduction_pass.input_offset + idx];
+    }
+    return maxima[reduction_pass.input_offset + idx];
+}
 
 @compute
-// sqrt(256) = 16
-// 256 is default limit for total workgroup size
-@workgroup_size(16, 16, 1)
-fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(local_invocation_id) local_id: vec3<u32>) {
-    let is_leader = all(local_id == vec3(0));
-    let idx = to_buffer_idx(global_id.xy);
-    atomicMax(&workgroup_maximum, unnormalized[idx]);
+@workgroup_size(REDUCTION_WORKGROUP_SIZE)
+fn reduce_max(
+    @builtin(local_invocation_index) local_idx: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let workgroup_idx = flat_workgroup_idx(workgroup_id, num_workgroups);
+    workgroup_maxima[local_idx] = reduction_input(workgroup_idx * REDUCTION_WORKGROUP_SIZE + local_idx);
     workgroupBarrier();
-    if (is_leader) {
-        atomicMax(&maximum, atomicLoad(&workgroup_maximum));
+    for (var stride = REDUCTION_WORKGROUP_SIZE / 2u; stride > 0u; stride /= 2u) {
+        if (local_idx < stride) {
+            workgroup_maxima[local_idx] = max(workgroup_maxima[local_idx], workgroup_maxima[local_idx + stride]);
+        }
+        workgroupBarrier();
+    }
+    // the workgroups beyond the input would overwrite the next pass.
+    let output_len = (reduction_pass.input_len + REDUCTION_WORKGROUP_SIZE - 1u) / REDUCTION_WORKGROUP_SIZE;
+    if (local_idx == 0u && workgroup_idx < output_len) {
+        maxima[reduction_pass.output_offset + workgroup_idx] = workgroup_maxima[0];
+    }
+}
+
+@compute
+@workgroup_size(REDUCTION_WORKGROUP_SIZE)
+fn normalize(
+    @builtin(local_invocation_index) local_idx: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let idx = flat_workgroup_idx(workgroup_id, num_workgroups) * REDUCTION_WORKGROUP_SIZE + local_idx;
+    if (idx >= reduction_pass.input_len) {
+        return;
     }
-    // TODO: huh, this looks suspiciously like a global barrier.
-    // I must be missing something?
-    // Yeah. Its highly misleading IMO.
-    // https://github.com/gpuweb/gpuweb/issues/3774
-    // Should not work.
-    storageBarrier();
-    let maximum = f32(atomicLoad(&maximum));
-    normalized[idx] = f32(unnormalized[idx]) / maximum;
+    let maximum = maxima[reduction_pass.output_offset];
+    // Nothing to show without any density, instead of dividing by zero.
+    compute_output[reduction_pass.input_offset + idx] = select(compute_output[reduction_pass.input_offset + idx] / maximum, 0.0, maximum <= 0.0);
 }
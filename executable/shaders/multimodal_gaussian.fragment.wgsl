@@ -1,5 +1,5 @@
 #import "multimodal_gaussian.wgsl";
-#import "helpers.wgsl";
+#import "color_map.wgsl";
 #import "canvas_ndc_conversion.wgsl";
 #import "fullscreen_quad.vertex.wgsl";
 
@@ -9,5 +9,5 @@ fn fs_main(@builtin(position) canvas_coords: vec4<f32>) -> @location(0) vec4<f32
 
     let combined_prob_density = calc_gaussian_density(normalized_device_coords);
 
-    return vec4(vec3(0, percentage_logscaled(combined_prob_density), 0), 1.0);
+    return density_color(combined_prob_density);
 }
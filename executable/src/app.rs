@@ -1,4 +1,4 @@
-use egui::{self, ProgressBar, Shadow, Vec2};
+use egui::{self, ProgressBar, Shadow};
 use macros::cfg_persistence_derive;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::Notify;
@@ -6,20 +6,33 @@ use type_map::TypeMap;
 
 use crate::{
     cfg_sleep,
+    continuous_run::{self, ContinuousRun, StepFn},
+    edit_history::{EditHistory, EditState, EditableParams},
+    export::{self, Chain},
     helpers::{
-        BackgroundTaskManager, BgTaskHandle, GpuTaskSenders, TaskProgress, get_compute_queue,
-        get_gpu_channels, gpu_scheduler, task_spawn,
+        BackgroundTaskManager, BgTaskHandle, GpuTaskSenders, TaskProgress, get_gpu_channels,
+        gpu_scheduler, task_spawn,
     },
-    simulation::random_walk_metropolis_hastings::{ProgressMode, Rwmh},
-    target_distr,
+    metrics::Discrepancy,
+    scenario::Scenario,
+    simulation::random_walk_metropolis_hastings::{
+        AlgoParams, OutOfSupport, ProgressMode, Rwmh, StopReason,
+    },
+    target_distr::{self, CpuTarget},
     visualizations::{
-        BDADiffState, BackgroundDisplay, BackgroundDisplayDiscr, BdaComputeState, DistrEdit,
-        ElementSettings, MMGState, SamplePointVisualizer,
+        BDADiffState, BackgroundDisplay, BackgroundDisplayDiscr, BdaComputeState, CanvasPainter,
+        ChainExport, ColorSettings, ContourLines, ContourSettings, CsvLoader, DensityImage,
+        DistrEdit, ElementSettings, HighDimEdit, HistoryScrubber, MMGState, PosteriorEdit,
+        RngSettingsUi, SampleImport, SamplePointVisualizer, ScenarioLoader, StepAnimation,
+        SupportEdit, View,
     },
 };
+#[cfg(feature = "persistence")]
+use crate::{checkpoint::Checkpoint, visualizations::CheckpointFile};
+use crate::{share_link, simulation::WrappedRngDiscriminants};
 
+/// Persisted through [`crate::persisted_state`], which has to be updated when fields change.
 #[cfg_persistence_derive]
-#[serde(default)]
 pub struct McmcDemo {
     // TODO: to make things more modular, switch to a composite struct for the simulation.
     // That struct will hold the algo, the data, the rngs and maybe the display (or an vector of displays, pointdisplay, targetdistr display, diff display).
@@ -28,7 +41,18 @@ pub struct McmcDemo {
     algo: Arc<Rwmh>,
     point_display: Option<SamplePointVisualizer>,
     target_distr: target_distr::Gaussian,
+    /// If present, this is sampled instead of [`Self::target_distr`].
+    cpu_target: Option<target_distr::CpuTarget>,
     background_display: BackgroundDisplay,
+    /// Shown next to [`Self::algo`] for comparison.
+    imported: Option<ImportedChain>,
+    #[serde(default)]
+    view: View,
+    #[serde(default)]
+    colors: ColorSettings,
+    /// Drawn over the background display.
+    #[serde(default)]
+    contours: ContourSettings,
     /// This holds resource managers for the main thread.
     ///
     /// If you want to hold copyable temporary ui state, use [`TempStateExtDelegatedToDataMethods`] instead.
@@ -42,7 +66,12 @@ impl Default for McmcDemo {
             algo: Default::default(),
             point_display: Some(Default::default()),
             target_distr: Default::default(),
+            cpu_target: None,
             background_display: Default::default(),
+            imported: None,
+            view: Default::default(),
+            colors: Default::default(),
+            contours: Default::default(),
             local_resources: TypeMap::new(),
         }
     }
@@ -51,12 +80,100 @@ impl Default for McmcDemo {
 // struct ComputeProfiler(Arc<wgpu_profiler::GpuProfiler>);
 // struct GUIProfiler(Rc<wgpu_profiler::GpuProfiler>);
 
+struct BatchJob(BgTaskHandle<Arc<Rwmh>>);
+
+/// Samples from elsewhere, shown like the sampled chain but never stepped.
+#[cfg_persistence_derive]
+struct ImportedChain {
+    name: String,
+    algo: Arc<Rwmh>,
+    display: SamplePointVisualizer,
+    /// Whether the background display approximates this chain instead of the sampled one.
+    in_background: bool,
+}
+
+impl ImportedChain {
+    fn new(name: String, algo: Rwmh) -> Self {
+        Self {
+            name,
+            algo: Arc::new(algo),
+            display: SamplePointVisualizer {
+                accepted_point_color: egui::Color32::LIGHT_BLUE,
+                ..Default::default()
+            },
+            in_background: false,
+        }
+    }
+}
+
+/// Scrolling by this many points zooms by a factor of e.
+const WHEEL_ZOOM_SPEED: f32 = 200.0;
+
+/// How far "fit to target" reaches around the elements of a Gaussian mixture.
+const FIT_STANDARD_DEVIATIONS: f32 = 3.0;
+
+/// Why the last continuous run ended by itself.
+struct RunStopped(StopReason);
+
+/// Starts the next [`StepAnimation`] once one is done.
+struct AutoStep;
+
+/// Starts the URL fragment of share links, to tell them apart from other fragments.
+#[cfg(target_arch = "wasm32")]
+const SHARE_LINK_PREFIX: &str = "run=";
+
+/// Outcome of the last copied or opened share link.
+#[cfg(target_arch = "wasm32")]
+struct ShareLinkStatus(Result<String, String>);
+
+/// Shown until dismissed, if the saved state couldn't be restored completely.
+#[cfg(feature = "persistence")]
+struct LoadProblem(String);
+
+/// Cells per axis of the grid the error metrics are evaluated on.
+const METRICS_RESOLUTION: usize = 32;
+
+/// The batches of a loaded [`Scenario`] that still have to be started.
+struct ScenarioRun {
+    remaining_batches: usize,
+}
+
+/// How the chain was stepped since it was fresh, share links only reproduce batches of a single size.
+///
+/// Missing if that's unknown, e.g. for chains restored from the saved state.
+#[derive(Clone, Copy)]
+enum SamplingRecord {
+    Fresh,
+    Batches(usize),
+    Other,
+}
+
 macro_rules! assert_none {
     ($expr:expr) => {
         assert!($expr.is_none());
     };
 }
 
+/// Steps towards the current target in the background, with its own copy of the target.
+fn step_fn(target_distr: &target_distr::Gaussian, cpu_target: Option<&CpuTarget>) -> StepFn {
+    match cpu_target {
+        Some(&CpuTarget::Posterior(ref posterior)) => {
+            let posterior = posterior.clone();
+            Box::new(move |algo: &mut Rwmh| algo.step(&posterior))
+        }
+        Some(&CpuTarget::HighDimensional(ref target)) => {
+            let target = target.clone();
+            Box::new(move |algo: &mut Rwmh| {
+                algo.step_high_dimensional(&target);
+            })
+        }
+        None => {
+            let target_distr = target_distr.clone();
+            Box::new(move |algo: &mut Rwmh| algo.step(&target_distr))
+        }
+    }
+}
+
 impl McmcDemo {
     /// Called once before the first frame.
     #[expect(clippy::missing_panics_doc, reason = "only used once")]
@@ -69,13 +186,15 @@ impl McmcDemo {
 
         let (GpuTaskSenders { bda_compute }, gpu_rx) = get_gpu_channels();
 
-        task_spawn(async move {
-            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-                ..Default::default()
-            });
-            let adapter = instance.request_adapter(&Default::default()).await.unwrap();
-            let (compute_device, compute_queue) = get_compute_queue(&adapter).await;
+        let render_state = cc
+            .wgpu_render_state
+            .as_ref()
+            .expect("Compiling with WGPU enabled");
 
+        // The compute shares the device with the rendering, so that its results are drawn without leaving the GPU.
+        let compute_device = render_state.device.clone();
+        let compute_queue = render_state.queue.clone();
+        task_spawn(async move {
             // I might end up creating a profiler for every workload.
             // Reason is that many relevant APIs require mutable access, making sharing annoying.
             // And, calling end_frame does not actually need to be called at top level, from my current understanding.
@@ -137,11 +256,6 @@ impl McmcDemo {
         //     .insert(ComputeProfiler(cfg_profiler_compute));
         // state.local_resources.insert(GUIProfiler(cfg_profiler_gui));
 
-        let render_state = cc
-            .wgpu_render_state
-            .as_ref()
-            .expect("Compiling with WGPU enabled");
-
         let refresh_token = Arc::new(Notify::new());
 
         let refresh_on_finished = {
@@ -202,7 +316,439 @@ impl McmcDemo {
         state
     }
 
-    pub fn get_state(
+    /// Throws away all samples, but keeps the parameters.
+    ///
+    /// The RNGs start over from their seeds, so the chain can be reproduced from them.
+    fn reset_simulation(&mut self) {
+        self.local_resources.remove::<BatchJob>();
+        self.local_resources.remove::<ScenarioRun>();
+        self.local_resources.remove::<ContinuousRun>();
+        self.local_resources.remove::<RunStopped>();
+        self.local_resources.remove::<StepAnimation>();
+        self.local_resources.remove::<AutoStep>();
+        let mut params = self.algo.params.clone();
+        // both, even if the first one's seed is unknown.
+        let proposal_rewound = params.proposal.rng.rewind();
+        let accept_rewound = params.accept.rewind();
+        self.local_resources
+            .insert(if proposal_rewound && accept_rewound {
+                SamplingRecord::Fresh
+            } else {
+                SamplingRecord::Other
+            });
+        *Arc::make_mut(&mut self.algo) = Rwmh::new(params);
+    }
+
+    fn edit_state(&self) -> EditState {
+        EditState {
+            target_distr: self.target_distr.clone(),
+            params: EditableParams::of(&self.algo.params),
+        }
+    }
+
+    fn restore_edit_state(&mut self, state: EditState) {
+        let EditState {
+            target_distr,
+            params,
+        } = state;
+        self.target_distr = target_distr;
+        if EditableParams::of(&self.algo.params) != params {
+            params.apply_to(&mut Arc::make_mut(&mut self.algo).params);
+        }
+    }
+
+    /// Makes the current state the one further edits are compared against, for changes that aren't edits.
+    fn rebase_edit_history(&mut self) {
+        let current = self.edit_state();
+        self.local_resources
+            .entry::<EditHistory>()
+            .or_insert_with(Default::default)
+            .rebase(&current);
+    }
+
+    /// Replaces target and sampler, and starts the batches of the run plan.
+    ///
+    /// `params` are usually [`Scenario::params`].
+    fn apply_scenario(&mut self, scenario: &Scenario, params: AlgoParams) {
+        self.set_cpu_target(None);
+        self.target_distr = scenario.target.clone();
+        self.algo = Arc::new(Rwmh::new(params));
+        self.reset_simulation();
+        if let Some(remaining_batches) = scenario.batches.checked_sub(1) {
+            self.start_batch(scenario.batch_size);
+            self.local_resources
+                .insert(ScenarioRun { remaining_batches });
+        }
+        self.rebase_edit_history();
+    }
+
+    fn start_batch(&mut self, size: usize) {
+        let step = step_fn(&self.target_distr, self.cpu_target.as_ref());
+        let mut algo = self.algo.clone();
+        let existing = self.local_resources.insert(BatchJob(BgTaskHandle::new(
+            move |mut communicate: BackgroundTaskManager| {
+                let algo_ref = Arc::make_mut(&mut algo);
+                for curr_step in 0..size {
+                    step(algo_ref);
+                    if communicate.update_progress_and_check_abort(curr_step) {
+                        break;
+                    }
+                }
+                algo
+            },
+            size,
+        )));
+        assert!(
+            existing.is_none(),
+            "ought to be prevented from overriding this by UI logic"
+        );
+    }
+
+    /// Takes over the chain of the finished [`BatchJob`], and starts the next batch of a [`ScenarioRun`].
+    fn finish_batch(&mut self, size: usize) {
+        let params = self.algo.params.clone();
+        let mut thread_result = self
+            .local_resources
+            .remove::<BatchJob>()
+            .unwrap()
+            .0
+            .get_value();
+        Arc::make_mut(&mut thread_result).params = params;
+        self.algo = thread_result;
+        let record = match self.local_resources.get::<SamplingRecord>() {
+            Some(&SamplingRecord::Fresh) => SamplingRecord::Batches(size),
+            Some(&SamplingRecord::Batches(previous)) if previous == size => {
+                SamplingRecord::Batches(size)
+            }
+            _ => SamplingRecord::Other,
+        };
+        self.local_resources.insert(record);
+        if let Some(run) = self.local_resources.get_mut::<ScenarioRun>() {
+            if let Some(remaining) = run.remaining_batches.checked_sub(1) {
+                run.remaining_batches = remaining;
+                self.start_batch(size);
+            } else {
+                self.local_resources.remove::<ScenarioRun>();
+            }
+        }
+    }
+
+    /// Replaces target and chain, the run continues where the checkpoint was written.
+    #[cfg(feature = "persistence")]
+    fn apply_checkpoint(&mut self, checkpoint: Checkpoint) {
+        let Checkpoint {
+            algo,
+            target_distr,
+            cpu_target,
+        } = checkpoint;
+        self.reset_simulation();
+        self.cpu_target = cpu_target;
+        self.target_distr = target_distr;
+        self.algo = Arc::new(algo);
+        // it was sampled elsewhere.
+        self.local_resources.remove::<SamplingRecord>();
+        self.rebase_edit_history();
+    }
+
+    /// Pans by dragging the canvas, and zooms with the mouse wheel or by pinching.
+    #[expect(
+        clippy::float_cmp,
+        reason = "the input is exactly neutral if not zooming"
+    )]
+    fn navigate(&mut self, ui: &egui::Ui, response: &egui::Response, rect: egui::Rect) {
+        if response.dragged_by(egui::PointerButton::Primary) {
+            self.view.pan(response.drag_delta(), rect.size());
+        }
+        if let Some(anchor) = response.hover_pos() {
+            let (scroll, zoom) =
+                ui.input(|input| (input.smooth_scroll_delta.y, input.zoom_delta()));
+            if scroll != 0.0 || zoom != 1.0 {
+                let factor = zoom * (scroll / WHEEL_ZOOM_SPEED).exp();
+                self.view.zoom(factor, anchor, rect.size());
+            }
+        }
+    }
+
+    /// Shows where the target has most of its mass.
+    fn fit_to_target(&mut self) {
+        self.view = self.target_extent();
+    }
+
+    /// Where the target has most of its mass, as a view fitted to it.
+    fn target_extent(&self) -> View {
+        match self.cpu_target {
+            Some(ref target) => self
+                .view
+                .fitted_to_density(|position| target.canvas_log_density(position)),
+            None => self
+                .view
+                .fitted(self.target_distr.gaussians.iter().flat_map(
+                    |&target_distr::NormalDistribution {
+                         position: [x, y],
+                         variance,
+                         ..
+                     }| {
+                        let reach = FIT_STANDARD_DEVIATIONS * variance.sqrt();
+                        [[x - reach, y - reach], [x + reach, y + reach]]
+                    },
+                )),
+        }
+    }
+
+    fn fit_to_samples(&mut self) {
+        let shown = self.shown_algo();
+        self.view = self.view.fitted(
+            shown
+                .history
+                .iter()
+                .skip(1)
+                .map(|record| record.position)
+                .chain([shown.current_loc.position]),
+        );
+    }
+
+    fn view_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Drag the canvas to pan, scroll to zoom.");
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("fit to samples").clicked() {
+                self.fit_to_samples();
+            }
+            if ui.button("fit to target").clicked() {
+                self.fit_to_target();
+            }
+            if ui.button("reset").clicked() {
+                self.view = View::default();
+            }
+        });
+        let View {
+            center: [ref mut x, ref mut y],
+            ref mut half_extent,
+        } = self.view;
+        ui.horizontal(|ui| {
+            ui.label("center");
+            let speed = *half_extent * 0.01;
+            ui.add(egui::DragValue::new(x).speed(speed));
+            ui.add(egui::DragValue::new(y).speed(speed));
+        });
+        ui.add(
+            egui::Slider::new(half_extent, 1e-3..=1e3)
+                .logarithmic(true)
+                .text("half width"),
+        );
+    }
+
+    /// Whether the background shows a difference rather than a density, which decides its color scale.
+    const fn shows_diff(&self) -> bool {
+        self.cpu_target.is_none() && self.background_display.shows_diff()
+    }
+
+    /// The chain the background display approximates.
+    fn background_algo(&mut self) -> Arc<Rwmh> {
+        match self.imported {
+            Some(ref imported) if imported.in_background => imported.algo.clone(),
+            _ => self.shown_algo(),
+        }
+    }
+
+    /// The chain as shown and measured, limited by the [`HistoryScrubber`] if present.
+    fn shown_algo(&mut self) -> Arc<Rwmh> {
+        match self.local_resources.get_mut::<HistoryScrubber>() {
+            Some(scrubber) => scrubber.shown(&self.algo),
+            None => self.algo.clone(),
+        }
+    }
+
+    /// Whether the chain is stepped in the background, it's replaced once that's done.
+    fn is_sampling(&self) -> bool {
+        self.local_resources.contains::<BatchJob>()
+            || self.local_resources.contains::<StepAnimation>()
+            || self
+                .local_resources
+                .get::<ContinuousRun>()
+                .is_some_and(ContinuousRun::is_running)
+    }
+
+    /// Takes over the snapshots of a continuous run, and ends it if it's over.
+    fn poll_continuous_run(&mut self, ctx: &egui::Context) {
+        if !matches!(
+            self.algo.params.progress_mode,
+            ProgressMode::Continuous { .. }
+        ) {
+            // left by loading or undoing while running, dropping the run ends its thread.
+            self.local_resources.remove::<ContinuousRun>();
+            return;
+        }
+        let Some(run) = self.local_resources.get_mut::<ContinuousRun>() else {
+            return;
+        };
+        let update = run.poll(ctx.input(|input| input.time));
+        if run.is_running() {
+            ctx.request_repaint_after(Duration::from_millis(16));
+        }
+        if let Some(algo) = update.algo {
+            self.algo = algo;
+            self.local_resources.insert(SamplingRecord::Other);
+            self.rebase_edit_history();
+        }
+        if update.ended {
+            self.local_resources.remove::<ContinuousRun>();
+            if let Some(reason) = update.reason {
+                self.local_resources.insert(RunStopped(reason));
+            }
+        }
+    }
+
+    fn continuous_controls(&mut self, ui: &mut egui::Ui) {
+        let ProgressMode::Continuous {
+            snapshot_interval,
+            ref stop,
+        } = self.algo.params.progress_mode
+        else {
+            return;
+        };
+        let stop = stop.clone();
+        let now = ui.input(|input| input.time);
+        if let Some(run) = self.local_resources.get_mut::<ContinuousRun>() {
+            run.status_ui(ui, &self.algo);
+            ui.horizontal(|ui| {
+                if !run.is_paused() {
+                    if ui.button("pause").clicked() {
+                        run.pause();
+                    }
+                } else if ui.button("resume").clicked() {
+                    let step = step_fn(&self.target_distr, self.cpu_target.as_ref());
+                    run.resume(self.algo.clone(), step, snapshot_interval, stop, now);
+                }
+                if ui.button("stop").clicked() {
+                    run.stop();
+                }
+            });
+            return;
+        }
+        if ui.button("start").clicked() {
+            self.local_resources.remove::<RunStopped>();
+            let step = step_fn(&self.target_distr, self.cpu_target.as_ref());
+            self.local_resources.insert(ContinuousRun::start(
+                self.algo.clone(),
+                step,
+                snapshot_interval,
+                stop,
+                now,
+            ));
+        } else if let Some(&RunStopped(reason)) = self.local_resources.get::<RunStopped>() {
+            ui.label(format!("stopped, {}", reason.display_name()));
+        }
+    }
+
+    fn start_step_animation(&mut self, now: f64) {
+        let animation = StepAnimation::new(
+            &self.algo,
+            |algo: &mut Rwmh| match self.cpu_target {
+                Some(CpuTarget::Posterior(ref posterior)) => algo.step_traced(posterior),
+                Some(CpuTarget::HighDimensional(ref target)) => {
+                    algo.step_high_dimensional_traced(target)
+                }
+                None => algo.step_traced(&self.target_distr),
+            },
+            now,
+        );
+        self.local_resources.insert(animation);
+    }
+
+    /// Takes over the stepped chain once its animation is done, and starts the next one when playing.
+    fn poll_step_animation(&mut self, ctx: &egui::Context) {
+        let ProgressMode::Animated { step_duration } = self.algo.params.progress_mode else {
+            // otherwise an unfinished step would count as sampling forever.
+            self.local_resources.remove::<StepAnimation>();
+            self.local_resources.remove::<AutoStep>();
+            return;
+        };
+        let now = ctx.input(|input| input.time);
+        if let Some(animation) = self.local_resources.get::<StepAnimation>() {
+            if animation.progress(now, step_duration) < 1.0 {
+                ctx.request_repaint();
+                return;
+            }
+            if let Some(StepAnimation { mut next, .. }) = self.local_resources.remove() {
+                // the speed may have been changed meanwhile.
+                Arc::make_mut(&mut next).params.progress_mode =
+                    self.algo.params.progress_mode.clone();
+                self.algo = next;
+                self.local_resources.insert(SamplingRecord::Other);
+                self.rebase_edit_history();
+            }
+        }
+        if self.local_resources.contains::<AutoStep>() {
+            self.start_step_animation(now);
+            ctx.request_repaint();
+        }
+    }
+
+    fn animation_controls(&mut self, ui: &mut egui::Ui) {
+        let now = ui.input(|input| input.time);
+        ui.horizontal(|ui| {
+            let animating = self.local_resources.contains::<StepAnimation>();
+            if ui
+                .add_enabled(!animating, egui::Button::new("step"))
+                .clicked()
+            {
+                self.start_step_animation(now);
+            }
+            if self.local_resources.contains::<AutoStep>() {
+                if ui.button("pause").clicked() {
+                    // the current step is still finished.
+                    self.local_resources.remove::<AutoStep>();
+                }
+            } else if ui.button("play").clicked() {
+                self.local_resources.insert(AutoStep);
+                if !animating {
+                    self.start_step_animation(now);
+                }
+            }
+        });
+    }
+
+    /// Samples of high dimensional targets don't fit any other target, so this resets the simulation in that case.
+    fn set_cpu_target(&mut self, cpu_target: Option<CpuTarget>) {
+        let is_high_dimensional =
+            |target: &Option<CpuTarget>| matches!(*target, Some(CpuTarget::HighDimensional(_)));
+        if is_high_dimensional(&self.cpu_target) || is_high_dimensional(&cpu_target) {
+            self.reset_simulation();
+        }
+        self.cpu_target = cpu_target;
+    }
+
+    pub fn get_state(cc: &eframe::CreationContext<'_>) -> Self {
+        #[cfg_attr(
+            not(target_arch = "wasm32"),
+            expect(unused_mut, reason = "share links only exist on the web")
+        )]
+        let mut state = Self::load_stored(cc);
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(encoded) = crate::helpers::html_bindings::url_fragment()
+            .as_deref()
+            .and_then(|fragment| fragment.strip_prefix(SHARE_LINK_PREFIX))
+        {
+            // otherwise reloading the page would throw away everything done since opening the link.
+            crate::helpers::html_bindings::clear_url_fragment();
+            let status = match share_link::decode(encoded) {
+                Ok(run) => {
+                    state.apply_scenario(&run.scenario, run.params());
+                    Ok("restored the shared run".to_owned())
+                }
+                Err(err) => {
+                    tracing::warn!("Ignoring share link: {err}");
+                    Err(err.to_string())
+                }
+            };
+            state.local_resources.insert(ShareLinkStatus(status));
+        }
+
+        state
+    }
+
+    fn load_stored(
         #[allow(
             clippy::allow_attributes,
             reason = "This seems cleanest way to do this."
@@ -216,12 +762,65 @@ impl McmcDemo {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         #[cfg(feature = "persistence")]
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        if let Some(loaded) = cc.storage.and_then(crate::persisted_state::load) {
+            let crate::persisted_state::Loaded { mut state, problem } = loaded;
+            if let Some(problem) = problem {
+                tracing::warn!("{problem}");
+                state.local_resources.insert(LoadProblem(problem));
+            }
+            return state;
         }
 
         Default::default()
     }
+
+    /// What reproduces the current chain when applied.
+    ///
+    /// The RNG state is restored after every batch, so the chain is only reproduced
+    /// if it was sampled in batches of a single size since the last reset.
+    ///
+    /// # Errors
+    /// If the chain was sampled otherwise, or the seed of an RNG is unknown.
+    #[cfg_attr(
+        all(not(target_arch = "wasm32"), not(test)),
+        expect(dead_code, reason = "share links only exist on the web")
+    )]
+    fn shared_run(&self) -> Result<share_link::SharedRun, String> {
+        let params = &self.algo.params;
+        let (Some(seed), Some(accept_seed)) = (params.proposal.rng.seed, params.accept.seed) else {
+            return Err(
+                "The seeds of the RNGs are unknown, set them to share the chain.".to_owned(),
+            );
+        };
+        let steps = self.algo.step_count() as usize;
+        let (batch_size, batches) = match self.local_resources.get::<SamplingRecord>() {
+            // there's nothing to reproduce, a batch size of zero is invalid though.
+            _ if steps == 0 => (params.progress_mode.batch_size().unwrap_or(1), 0),
+            Some(&SamplingRecord::Batches(size)) if steps.is_multiple_of(size) => {
+                (size, steps / size)
+            }
+            _ => {
+                return Err(
+                    "Only chains sampled in batches of a single size since the last reset can be shared."
+                        .to_owned(),
+                );
+            }
+        };
+        Ok(share_link::SharedRun {
+            scenario: Scenario {
+                target: self.target_distr.clone(),
+                sigma: params.proposal.sigma,
+                out_of_support: params.out_of_support,
+                start: params.start,
+                rng: WrappedRngDiscriminants::from(&params.proposal.rng.rng),
+                seed,
+                batch_size,
+                batches,
+            },
+            accept_rng: WrappedRngDiscriminants::from(&params.accept.rng),
+            accept_seed,
+        })
+    }
 }
 
 impl eframe::App for McmcDemo {
@@ -233,7 +832,7 @@ impl eframe::App for McmcDemo {
         // We can't do blocking join on wasm main thread though, but the browser window will continue running.
         // #[cfg(not(target_arch = "wasm32"))]
         // self.background_thread.take().map(thread::JoinHandle::join);
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        crate::persisted_state::save(storage, self);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -262,6 +861,8 @@ impl eframe::App for McmcDemo {
                         local_resources: std::mem::take(&mut self.local_resources),
                         ..Default::default()
                     };
+                    // what's running or recorded refers to the old chain.
+                    self.reset_simulation();
                     ui.data_mut(|type_map| type_map.clear());
                 }
                 #[cfg(feature = "backend_panel")]
@@ -304,94 +905,306 @@ impl eframe::App for McmcDemo {
             backend.end_of_frame(ctx);
         }
 
+        // the chain taken over once sampling is done would undo the restore.
+        let restored = if self.is_sampling() {
+            None
+        } else {
+            self.local_resources
+                .entry::<EditHistory>()
+                .or_insert_with(Default::default)
+                .handle_shortcuts(ctx)
+        };
+        if let Some(state) = restored {
+            self.restore_edit_state(state);
+        }
+
+        #[cfg(feature = "persistence")]
+        if let Some(&LoadProblem(ref problem)) = self.local_resources.get::<LoadProblem>() {
+            let mut open = true;
+            egui::Window::new("saved state")
+                .open(&mut open)
+                .show(ctx, |ui| ui.label(problem));
+            if !open {
+                self.local_resources.remove::<LoadProblem>();
+            }
+        }
+
         egui::Window::new("Simulation").show(
             ctx,
             #[expect(clippy::shadow_unrelated, reason = "false positive, is related.")]
             |ui| {
-                let ProgressMode::Batched { ref mut size } =
-                    Arc::make_mut(&mut self.algo).params.progress_mode;
-                ui.add(
-                    // Safety: the slider begins at 1.
-                    unsafe {
-                        egui::Slider::new(
-                            size.get_inner_mut(),
-                            // TODO: use default webgpu maximum size here to determine slider maximum, by determining how much space is left, roughly.
-                            1..=100_000,
-                        )
-                    }
-                    .logarithmic(true)
-                    .text("batch size"),
-                );
-                let size = size.get_inner();
-                struct BatchJob(BgTaskHandle<Arc<Rwmh>>);
-
-                let bg_task = self.local_resources.get::<BatchJob>();
-                if let Some(&BatchJob(ref bg_task)) = bg_task {
-                    ui.add(
-                        ProgressBar::new(match bg_task.get_progress() {
-                            TaskProgress::Pending(progress) => progress,
-                            TaskProgress::Finished => {
-                                let params = self.algo.params.clone();
-                                let mut thread_result = self
-                                    .local_resources
-                                    .remove::<BatchJob>()
-                                    .unwrap()
-                                    .0
-                                    .get_value();
-                                Arc::make_mut(&mut thread_result).params = params;
-                                self.algo = thread_result;
-                                // process is finished, but because of the control flow I can't show the button for the next batchstep yet.
-                                // So this will have to do.
-                                // Alternative would be moving the batch step UI put of this gigantic function and using this here,
-                                // moving the ProgressBar rendering back into the Pending branch.
-                                // But thats too much work for something still in the flow.
-                                1.0
+                let sampling = self.is_sampling();
+                let mut switched = false;
+                ui.add_enabled_ui(!sampling, |ui| {
+                    ui.horizontal(|ui| {
+                        let progress_mode = &mut Arc::make_mut(&mut self.algo).params.progress_mode;
+                        let current = std::mem::discriminant(&*progress_mode);
+                        for (name, explanation, mode) in [
+                            ("batches", "Steps a fixed number of times per click.", ProgressMode::default()),
+                            ("continuous", "Steps until stopped, the displays update while it runs.", ProgressMode::continuous()),
+                            ("animated", "Shows every step, with its proposal, acceptance test and move.", ProgressMode::animated()),
+                        ] {
+                            let selected = std::mem::discriminant(&mode) == current;
+                            if ui
+                                .selectable_label(selected, name)
+                                .on_hover_text(explanation)
+                                .clicked()
+                                && !selected
+                            {
+                                *progress_mode = mode;
+                                switched = true;
                             }
-                        })
-                        // this "fixes" the layout when displaying the progress bar.
-                        // Without adding this, it will take up more horizontal space then the settings element took up originally,
-                        // which looks very glitchy.
-                        // There is probably a less hacky way that also works on other aspect ratios etc, but for now it'll have to do.
-                        .desired_width(200.0),
-                    );
-                    ctx.request_repaint_after(Duration::from_millis(16));
-                } else if ui.button("batch step").clicked() {
-                    let existing = self.local_resources.insert(BatchJob({
-                        let mut algo = self.algo.clone();
-                        let target_distr = self.target_distr.clone();
-                        BgTaskHandle::new(
-                            move |mut communicate: BackgroundTaskManager| {
-                                let algo_ref = Arc::make_mut(&mut algo);
-                                for curr_step in 0..size {
-                                    algo_ref.step(&target_distr);
-                                    if communicate.update_progress_and_check_abort(curr_step) {
-                                        break;
-                                    }
+                        }
+                    });
+                });
+                if switched {
+                    self.local_resources.remove::<ContinuousRun>();
+                    self.local_resources.remove::<RunStopped>();
+                    self.local_resources.remove::<AutoStep>();
+                }
+                let batch_size = match Arc::make_mut(&mut self.algo).params.progress_mode {
+                    ProgressMode::Batched { ref mut size } => {
+                        ui.add(
+                            // Safety: the slider begins at 1.
+                            unsafe {
+                                egui::Slider::new(
+                                    size.get_inner_mut(),
+                                    // TODO: use default webgpu maximum size here to determine slider maximum, by determining how much space is left, roughly.
+                                    1..=100_000,
+                                )
+                            }
+                            .logarithmic(true)
+                            .text("batch size"),
+                        );
+                        Some(size.get_inner())
+                    }
+                    ProgressMode::Continuous {
+                        ref mut snapshot_interval,
+                        ref mut stop,
+                    } => {
+                        ui.add_enabled_ui(!sampling, |ui| {
+                            continuous_run::settings_ui(ui, snapshot_interval, stop);
+                        });
+                        None
+                    }
+                    ProgressMode::Animated {
+                        ref mut step_duration,
+                    } => {
+                        ui.add(
+                            egui::Slider::new(step_duration, 0.2..=10.0)
+                                .logarithmic(true)
+                                .suffix(" s")
+                                .text("per step"),
+                        );
+                        None
+                    }
+                };
+                if let Some(size) = batch_size {
+                    let bg_task = self.local_resources.get::<BatchJob>();
+                    if let Some(&BatchJob(ref bg_task)) = bg_task {
+                        ui.add(
+                            ProgressBar::new(match bg_task.get_progress() {
+                                TaskProgress::Pending(progress) => progress,
+                                TaskProgress::Finished => {
+                                    self.finish_batch(size);
+                                    // process is finished, but because of the control flow I can't show the button for the next batchstep yet.
+                                    // So this will have to do.
+                                    // Alternative would be moving the batch step UI put of this gigantic function and using this here,
+                                    // moving the ProgressBar rendering back into the Pending branch.
+                                    // But thats too much work for something still in the flow.
+                                    1.0
                                 }
-                                algo
-                            },
-                            size,
-                        )
-                    }));
-                    assert!(
-                        existing.is_none(),
-                        "ought to be prevented from overriding this by UI logic"
-                    );
+                            })
+                            // this "fixes" the layout when displaying the progress bar.
+                            // Without adding this, it will take up more horizontal space then the settings element took up originally,
+                            // which looks very glitchy.
+                            // There is probably a less hacky way that also works on other aspect ratios etc, but for now it'll have to do.
+                            .desired_width(200.0),
+                        );
+                        ctx.request_repaint_after(Duration::from_millis(16));
+                    } else if ui.button("batch step").clicked() {
+                        self.start_batch(size);
+                    }
+                } else if let ProgressMode::Continuous { .. } = self.algo.params.progress_mode {
+                    self.continuous_controls(ui);
+                } else {
+                    self.animation_controls(ui);
+                }
+                if let Some(&ScenarioRun { remaining_batches }) =
+                    self.local_resources.get::<ScenarioRun>()
+                {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("scenario: {remaining_batches} more batches"));
+                        if ui.button("stop").clicked() {
+                            self.local_resources.remove::<ScenarioRun>();
+                        }
+                    });
                 }
                 if ui.button("reset simulation").clicked() {
-                    self.local_resources.remove::<BatchJob>();
-                    let params = self.algo.params.clone();
-                    *Arc::make_mut(&mut self.algo) = Rwmh {
-                        params,
-                        ..Default::default()
-                    };
+                    self.reset_simulation();
                 }
+                ui.collapsing("export samples", |ui| {
+                    let requested = self
+                        .local_resources
+                        .entry::<ChainExport>()
+                        .or_insert_with(Default::default)
+                        .ui(ui);
+                    if let Some((format, layout)) = requested {
+                        let chain = Chain::new(&self.algo, self.cpu_target.as_ref());
+                        let target =
+                            export::describe_target(self.cpu_target.as_ref(), &self.target_distr);
+                        let files =
+                            export::export(&chain, &target, &self.algo.params, format, layout);
+                        self.local_resources
+                            .entry::<ChainExport>()
+                            .or_insert_with(Default::default)
+                            .save(files);
+                    }
+                });
+                ui.collapsing("imported samples", |ui| {
+                    if let Some(ref mut imported) = self.imported {
+                        let draws = imported.algo.total_point_count
+                            + imported.algo.current_loc.remain_count
+                            + 1;
+                        ui.label(format!("{}: {draws} draws", imported.name));
+                        ui.checkbox(&mut imported.in_background, "show in background display");
+                        let mut accept_color_fullspace =
+                            egui::Rgba::from(imported.display.accepted_point_color)
+                                .to_rgba_unmultiplied();
+                        ui.label("set point color");
+                        ui.color_edit_button_rgba_unmultiplied(&mut accept_color_fullspace);
+                        let [r, g, b, a] = accept_color_fullspace;
+                        imported.display.accepted_point_color =
+                            egui::Rgba::from_rgba_unmultiplied(r, g, b, a).into();
+                        if ui.button("remove imported samples").clicked() {
+                            self.imported = None;
+                        }
+                        ui.separator();
+                    }
+                    let imported = self
+                        .local_resources
+                        .entry::<SampleImport>()
+                        .or_insert_with(Default::default)
+                        .ui(ui);
+                    if let Some((name, algo)) = imported {
+                        self.imported = Some(ImportedChain::new(name, algo));
+                    }
+                });
+                ui.collapsing("replay history", |ui| {
+                    let mut replaying = self.local_resources.contains::<HistoryScrubber>();
+                    ui.checkbox(&mut replaying, "limit displays to the first iterations");
+                    if !replaying {
+                        self.local_resources.remove::<HistoryScrubber>();
+                    } else if let Some(scrubber) =
+                        self.local_resources.get_mut::<HistoryScrubber>()
+                    {
+                        scrubber.ui(ui);
+                    } else {
+                        self.local_resources
+                            .insert(HistoryScrubber::new(&self.algo));
+                    }
+                });
+                ui.collapsing("error metrics", |ui| {
+                    if self.cpu_target.is_some() {
+                        ui.label("Only available for the Gaussian mixture.");
+                        return;
+                    }
+                    let shown = self.shown_algo();
+                    let mut chains = vec![("sampled", &shown)];
+                    if let Some(ref imported) = self.imported {
+                        chains.push(("imported", &imported.algo));
+                    }
+                    egui::Grid::new("error metrics")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("chain");
+                            ui.label("draws");
+                            ui.label("total variation").on_hover_text(format!(
+                            "On a {METRICS_RESOLUTION}x{METRICS_RESOLUTION} grid over the square the view is centered on."
+                        ));
+                            ui.label("mean error");
+                            ui.label("outside grid");
+                            ui.end_row();
+                            for (name, algo) in chains {
+                                let discrepancy = Discrepancy::new(
+                                    algo,
+                                    &self.target_distr,
+                                    self.view.center,
+                                    self.view.half_extent,
+                                    METRICS_RESOLUTION,
+                                );
+                                ui.label(name);
+                                ui.label(discrepancy.draws.to_string());
+                                ui.label(format!("{:.4}", discrepancy.total_variation));
+                                ui.label(format!("{:.4}", discrepancy.mean_error));
+                                ui.label(format!("{:.1}%", discrepancy.outside * 100.0));
+                                ui.end_row();
+                            }
+                        });
+                });
+                ui.collapsing("edit history", |ui| {
+                    let sampling = self.is_sampling();
+                    let restored = ui
+                        .add_enabled_ui(!sampling, |ui| {
+                            self.local_resources
+                                .entry::<EditHistory>()
+                                .or_insert_with(Default::default)
+                                .ui(ui)
+                        })
+                        .inner;
+                    if let Some(state) = restored {
+                        self.restore_edit_state(state);
+                    }
+                });
+                ui.collapsing("view", |ui| {
+                    self.view_ui(ui);
+                });
                 ui.collapsing("background display", |ui| {
+                    if self.cpu_target.is_some() {
+                        ui.label("Only the target distribution can be displayed for this target.");
+                        return;
+                    }
                     let prev_bg = BackgroundDisplayDiscr::from(&self.background_display);
                     let new_bg = prev_bg.selection_ui(ui);
                     if new_bg != prev_bg {
+                        let kde = self.background_display.kde_mut().map(std::mem::take);
                         self.background_display = new_bg.into();
+                        // the estimate stays the same when switching between the immediate and compute diff.
+                        if let (Some(kde), Some(slot)) = (kde, self.background_display.kde_mut()) {
+                            *slot = kde;
+                        }
                     };
+                    let background_algo = self.background_algo();
+                    if let Some(kde) = self.background_display.kde_mut() {
+                        ui.separator();
+                        kde.ui(ui, &background_algo);
+                    }
+                    if let BackgroundDisplay::BDAComputeDiff(ref mut display) =
+                        self.background_display
+                    {
+                        display.grid.ui(ui);
+                    }
+                });
+                ui.collapsing("colors", |ui| {
+                    let diff = self.shows_diff();
+                    self.colors.ui(ui, diff);
+                });
+                ui.collapsing("contour overlay", |ui| {
+                    let contours = &mut self.contours;
+                    if !contours.shown {
+                        if ui.button("show contours").clicked() {
+                            contours.shown = true;
+                        }
+                    } else if ui.button("hide contours").clicked() {
+                        contours.shown = false;
+                        self.local_resources.remove::<ContourLines>();
+                    } else {
+                        ui.label("levels of the highest density regions")
+                            .on_hover_text(
+                                "Each line encloses the smallest region holding this much of the target's mass.",
+                            );
+                        contours.ui(ui);
+                    }
                 });
                 ui.collapsing("approximation point-display", |ui| {
                     if let Some(ref mut point_display) = self.point_display {
@@ -439,23 +1252,214 @@ impl eframe::App for McmcDemo {
                 egui::CollapsingHeader::new("target distribution")
                     .default_open(true)
                     .show(ui, |ui| {
-                        DistrEdit::settings_ui(&mut self.target_distr.gaussians, ui);
+                        let sampling = self.is_sampling();
+                        let mut new_target = None;
+                        if self.cpu_target.is_some()
+                            && ui.button("back to Gaussian mixture").clicked()
+                        {
+                            new_target = Some(None);
+                        }
+                        match self.cpu_target {
+                            Some(CpuTarget::Posterior(ref mut posterior)) => {
+                                PosteriorEdit::settings_ui(posterior, ui);
+                            }
+                            Some(CpuTarget::HighDimensional(ref mut target)) => {
+                                let previous = target.clone();
+                                // the running chain would be taken over with the old dimension or projection.
+                                ui.add_enabled_ui(!sampling, |ui| {
+                                    HighDimEdit::settings_ui(target, ui);
+                                });
+                                if target.dim() != previous.dim() {
+                                    self.reset_simulation();
+                                } else if target.projection != previous.projection {
+                                    Arc::make_mut(&mut self.algo).reproject(target);
+                                }
+                            }
+                            None => {
+                                DistrEdit::settings_ui(&mut self.target_distr.gaussians, ui);
+                                ui.separator();
+                                SupportEdit::settings_ui(&mut self.target_distr.support, ui);
+                                if ui.button("high dimensional target").clicked() {
+                                    new_target =
+                                        Some(Some(CpuTarget::HighDimensional(Default::default())));
+                                }
+                            }
+                        }
+                        ui.collapsing("posterior from data", |ui| {
+                            let loaded = self
+                                .local_resources
+                                .entry::<CsvLoader>()
+                                .or_insert_with(Default::default)
+                                .ui(ui);
+                            if let Some(data) = loaded {
+                                new_target = Some(Some(CpuTarget::Posterior(
+                                    target_distr::Posterior::new(data),
+                                )));
+                            }
+                        });
+                        if let Some(new_target) = new_target {
+                            self.set_cpu_target(new_target);
+                        }
                     });
-                ui.collapsing("proposal probability", |ui| {
-                    let prop = &mut Arc::make_mut(&mut self.algo).params.proposal;
-                    ui.add(egui::Slider::new(&mut prop.sigma, 0.0..=1.0).text("Proposal sigma"));
-                    prop.rng.rng.settings_ui(ui, ui.id());
+                ui.collapsing("scenario file", |ui| {
+                    let loaded = self
+                        .local_resources
+                        .entry::<ScenarioLoader>()
+                        .or_insert_with(Default::default)
+                        .ui(ui);
+                    if let Some(scenario) = loaded {
+                        self.apply_scenario(&scenario, scenario.params());
+                    }
                 });
-                ui.collapsing("acceptance probability", |ui| {
-                    Arc::make_mut(&mut self.algo)
-                        .params
-                        .accept
-                        .rng
-                        .settings_ui(ui, ui.id());
+                #[cfg(feature = "persistence")]
+                ui.collapsing("checkpoint", |ui| {
+                    let can_load = !self.is_sampling();
+                    let loaded = self
+                        .local_resources
+                        .entry::<CheckpointFile>()
+                        .or_insert_with(Default::default)
+                        .ui(
+                            ui,
+                            &self.algo,
+                            &self.target_distr,
+                            self.cpu_target.as_ref(),
+                            can_load,
+                        );
+                    if let Some(checkpoint) = loaded {
+                        self.apply_checkpoint(checkpoint);
+                    }
+                });
+                #[cfg(target_arch = "wasm32")]
+                ui.collapsing("share link", |ui| {
+                    ui.label("Copies a link that restores the target, the sampler settings and the sampled steps.");
+                    let copy = ui.add_enabled(
+                        self.cpu_target.is_none(),
+                        egui::Button::new("copy share link"),
+                    );
+                    if copy
+                        .on_disabled_hover_text("Only the Gaussian mixture can be shared.")
+                        .clicked()
+                    {
+                        let status = self.shared_run().and_then(|run| {
+                            let fragment =
+                                format!("{SHARE_LINK_PREFIX}{}", share_link::encode(&run));
+                            crate::helpers::html_bindings::url_with_fragment(&fragment)
+                                .map(|link| {
+                                    ui.ctx().copy_text(link);
+                                    "copied to the clipboard".to_owned()
+                                })
+                                .ok_or_else(|| "can't determine the page URL".to_owned())
+                        });
+                        self.local_resources.insert(ShareLinkStatus(status));
+                    }
+                    match self.local_resources.get::<ShareLinkStatus>() {
+                        Some(&ShareLinkStatus(Ok(ref message))) => {
+                            ui.label(message);
+                        }
+                        Some(&ShareLinkStatus(Err(ref error))) => {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                        None => {}
+                    }
+                });
+                // the running chain would overwrite changes, they can be made while paused.
+                let sampling = self.is_sampling();
+                ui.add_enabled_ui(!sampling, |ui| {
+                    ui.collapsing("start point", |ui| {
+                        let [ref mut x, ref mut y] = Arc::make_mut(&mut self.algo).params.start;
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(x).speed(0.01).prefix("x: "));
+                            ui.add(egui::DragValue::new(y).speed(0.01).prefix("y: "));
+                        });
+                        ui.label("Takes effect when the simulation is reset.");
+                    });
+                    ui.collapsing("proposal probability", |ui| {
+                        let prop = &mut Arc::make_mut(&mut self.algo).params.proposal;
+                        ui.add(egui::Slider::new(&mut prop.sigma, 0.0..=1.0).text("Proposal sigma"));
+                        if let Some(seed) = prop.rng.rng.settings_ui(ui, ui.id()) {
+                            prop.rng.seed = Some(seed);
+                        }
+                    });
+                    ui.collapsing("outside of target support", |ui| {
+                        let out_of_support = &mut Arc::make_mut(&mut self.algo).params.out_of_support;
+                        for ele in OutOfSupport::VARIANTS.iter() {
+                            ui.selectable_value(out_of_support, *ele, ele.display_name())
+                                .on_hover_text(ele.explanation());
+                        }
+                        ui.label(out_of_support.explanation());
+                    });
+                    ui.collapsing("acceptance probability", |ui| {
+                        let accept = &mut Arc::make_mut(&mut self.algo).params.accept;
+                        if let Some(seed) = accept.rng.settings_ui(ui, ui.id()) {
+                            accept.seed = Some(seed);
+                        }
+                    });
                 });
             },
         );
 
+        let dropped_scenario = self
+            .local_resources
+            .entry::<ScenarioLoader>()
+            .or_insert_with(Default::default)
+            .dropped(ctx);
+        if let Some(scenario) = dropped_scenario {
+            self.apply_scenario(&scenario, scenario.params());
+        }
+
+        self.poll_continuous_run(ctx);
+        self.poll_step_animation(ctx);
+
+        #[cfg(feature = "persistence")]
+        {
+            let can_load = !self.is_sampling();
+            let dropped_checkpoint = self
+                .local_resources
+                .entry::<CheckpointFile>()
+                .or_insert_with(Default::default)
+                .dropped(ctx, can_load);
+            if let Some(checkpoint) = dropped_checkpoint {
+                self.apply_checkpoint(checkpoint);
+            }
+        }
+
+        let dropped = self
+            .local_resources
+            .entry::<CsvLoader>()
+            .or_insert_with(Default::default)
+            .dropped(ctx);
+        if let Some(data) = dropped {
+            let import = self
+                .local_resources
+                .entry::<SampleImport>()
+                .or_insert_with(Default::default);
+            if import.takes_drops {
+                if let Some((name, algo)) = import.import(&data) {
+                    self.imported = Some(ImportedChain::new(name, algo));
+                }
+            } else {
+                self.set_cpu_target(Some(CpuTarget::Posterior(target_distr::Posterior::new(
+                    data,
+                ))));
+            }
+        }
+
+        let shown = self.shown_algo();
+        match self.cpu_target {
+            Some(CpuTarget::Posterior(ref posterior)) => {
+                egui::SidePanel::right("data fit").show(ctx, |ui| {
+                    ui.heading("data fit of the current draw");
+                    PosteriorEdit::data_fit_ui(posterior, shown.current_loc.position.into(), ui);
+                });
+            }
+            Some(CpuTarget::HighDimensional(ref target)) => {
+                egui::SidePanel::right("full chain state").show(ctx, |ui| {
+                    HighDimEdit::state_ui(target, &shown, ui);
+                });
+            }
+            None => {}
+        }
+
         egui::CentralPanel::default()
             // remove margins
             .frame(Default::default())
@@ -467,71 +1471,172 @@ impl eframe::App for McmcDemo {
                     .show(ui, |ui| {
                         let px_size = ui.available_size();
                         let (rect, response) =
-                            ui.allocate_exact_size(px_size, egui::Sense::hover());
+                            ui.allocate_exact_size(px_size, egui::Sense::click_and_drag());
+                        self.navigate(ui, &response, rect);
                         // last painted element wins.
                         let painter = ui.painter();
-                        self.background_display.paint(
-                            painter,
-                            rect * ctx.pixels_per_point(),
-                            self.algo.clone(),
-                            &self.target_distr,
-                        );
+                        if let Some(ref cpu_target) = self.cpu_target {
+                            let is_current = self
+                                .local_resources
+                                .get::<DensityImage>()
+                                .is_some_and(|image| {
+                                    image.is_current(
+                                        cpu_target,
+                                        rect,
+                                        &self.view,
+                                        &self.colors.density,
+                                    )
+                                });
+                            if !is_current {
+                                self.local_resources.insert(DensityImage::new(
+                                    ctx,
+                                    cpu_target,
+                                    rect,
+                                    &self.view,
+                                    &self.colors.density,
+                                ));
+                            }
+                            if let Some(image) = self.local_resources.get::<DensityImage>() {
+                                image.paint(painter, rect);
+                            }
+                        } else {
+                            let background_algo = self.background_algo();
+                            self.background_display.paint(
+                                painter,
+                                rect * ctx.pixels_per_point(),
+                                background_algo,
+                                &self.target_distr,
+                                &self.view,
+                                self.colors.scale(self.shows_diff()),
+                            );
+                        }
+                        if self.contours.shown {
+                            let contours = &self.contours;
+                            let cpu_target = self.cpu_target.as_ref();
+                            let is_current = self
+                                .local_resources
+                                .get::<ContourLines>()
+                                .is_some_and(|lines| {
+                                    lines.is_current(
+                                        &self.target_distr,
+                                        cpu_target,
+                                        &contours.masses,
+                                    )
+                                });
+                            if !is_current {
+                                self.local_resources.insert(ContourLines::new(
+                                    &self.target_distr,
+                                    cpu_target,
+                                    &self.target_extent(),
+                                    &contours.masses,
+                                ));
+                            }
+                            if let Some(lines) = self.local_resources.get::<ContourLines>() {
+                                lines.paint(painter, rect, &self.view, contours);
+                            }
+                        }
 
                         if let Some(ref point_display) = self.point_display {
-                            point_display.paint(painter, rect, &self.algo);
+                            point_display.paint(painter, rect, &shown, &self.view);
+                        }
+                        if let ProgressMode::Animated { step_duration } =
+                            self.algo.params.progress_mode
+                            && let Some(animation) = self.local_resources.get::<StepAnimation>()
+                        {
+                            let now = ctx.input(|input| input.time);
+                            animation.paint(
+                                painter,
+                                rect,
+                                &self.view,
+                                animation.progress(now, step_duration),
+                            );
+                        }
+                        if let Some(ref imported) = self.imported {
+                            imported
+                                .display
+                                .paint(painter, rect, &imported.algo, &self.view);
                         }
+                        self.colors.paint_legend(painter, rect, self.shows_diff());
+
+                        if self.cpu_target.is_some() {
+                            return;
+                        }
+
+                        SupportEdit::paint(&self.target_distr.support, painter, rect, &self.view);
+                        SupportEdit::show_if_open(
+                            &mut self.target_distr.support,
+                            ui,
+                            &response,
+                            rect,
+                            &self.view,
+                        );
 
                         let gaussians = &mut self.target_distr.gaussians;
 
-                        DistrEdit::show_if_open(gaussians, ui, &response, rect, painter);
+                        DistrEdit::show_if_open(
+                            gaussians, ui, &response, rect, &self.view, painter,
+                        );
 
-                        ElementSettings::show_if_open(gaussians, ui, rect, ctx);
+                        ElementSettings::show_if_open(gaussians, ui, rect, &self.view, ctx);
                     });
             });
+
+        // waiting for the end of interactions, so that a drag is recorded as a single edit.
+        if !ctx.input(|input| input.pointer.any_down()) {
+            let current = self.edit_state();
+            self.local_resources
+                .entry::<EditHistory>()
+                .or_insert_with(Default::default)
+                .record(&current);
+        }
         // let ComputeProfiler(_compute_profiler) = self.local_resources.get().expect("blah");
         // let GUIProfiler(_gui_profiler) = self.local_resources.get().expect("blah");
     }
 }
 
-pub fn ndc_to_canvas_coord(ndc: egui::Pos2, canvas_size: egui::Vec2) -> egui::Pos2 {
-    let center = (canvas_size - egui::Vec2::splat(canvas_size.min_elem())) / 2.0;
-    ((ndc.to_vec2() + egui::Vec2::splat(1.0)) / 2.0 * canvas_size.min_elem() + center).to_pos2()
-}
-
-pub fn canvas_coord_to_ndc(canvas_coord: egui::Pos2, canvas_size: egui::Vec2) -> egui::Pos2 {
-    let center = (canvas_size - egui::Vec2::splat(canvas_size.min_elem())) / 2.0;
-    ((canvas_coord - center) / canvas_size.min_elem()) * 2.0 - Vec2::splat(1.0)
-}
-
 #[cfg(test)]
 mod test {
-    use egui::Pos2;
+    use std::{sync::Arc, thread, time::Duration};
 
-    use super::{canvas_coord_to_ndc, ndc_to_canvas_coord};
+    use super::{BatchJob, McmcDemo, SamplingRecord};
+    use crate::helpers::TaskProgress;
 
-    fn close_enough(Pos2 { x: x_1, y: y_1 }: Pos2, Pos2 { x: x_2, y: y_2 }: Pos2) -> bool {
-        ((x_1 - x_2).abs() < f32::EPSILON) && ((y_1 - y_2).abs() < f32::EPSILON)
+    /// Waits for each batch to finish, like the UI does, until no further one is started.
+    fn finish_batches(app: &mut McmcDemo, size: usize) {
+        while let Some(&BatchJob(ref task)) = app.local_resources.get::<BatchJob>() {
+            if matches!(task.get_progress(), TaskProgress::Finished) {
+                app.finish_batch(size);
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
     }
 
     #[test]
-    fn invert_each_other() {
-        let rect = egui::Rect {
-            min: [0.0, 0.0].into(),
-            max: [1920.0, 1080.0].into(),
-        };
-
-        let size = rect.size();
+    fn shared_runs_reproduce_the_chain_after_a_reset() {
+        let mut app = McmcDemo::default();
+        // continuous runs and animated steps leave the RNGs where they are.
+        for _ in 0..10 {
+            Arc::make_mut(&mut app.algo).step(&app.target_distr);
+        }
+        app.local_resources.insert(SamplingRecord::Other);
+        app.reset_simulation();
+        app.start_batch(100);
+        finish_batches(&mut app, 100);
 
-        let start_canvas_coord = egui::Pos2::from([450.0, 670.0]);
-        let start_ndc = [-0.634, 0.232].into();
+        let run = app.shared_run().expect("sampled in a single batch");
+        let mut replayed = McmcDemo::default();
+        replayed.apply_scenario(&run.scenario, run.params());
+        finish_batches(&mut replayed, run.scenario.batch_size);
+        assert_eq!(replayed.algo.history, app.algo.history);
+        assert_eq!(replayed.algo.current_loc, app.algo.current_loc);
+    }
 
-        assert_eq!(
-            ndc_to_canvas_coord(canvas_coord_to_ndc(start_canvas_coord, size), size),
-            start_canvas_coord
-        );
-        assert!(close_enough(
-            canvas_coord_to_ndc(ndc_to_canvas_coord(start_ndc, size), size),
-            start_ndc
-        ));
+    #[test]
+    fn unknown_seeds_are_not_shared() {
+        let mut app = McmcDemo::default();
+        Arc::make_mut(&mut app.algo).params.accept.seed = None;
+        app.reset_simulation();
+        assert!(app.shared_run().is_err());
     }
 }
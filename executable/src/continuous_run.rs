@@ -0,0 +1,431 @@
+//! Runs in [`ProgressMode::Continuous`], which keep stepping in the background and publish snapshots of the chain.
+//!
+//! Pausing ends the background thread, and resuming starts a new one on the chain it returned,
+//! so the RNGs continue where they stopped and a paused run is the same as an uninterrupted one.
+//!
+//! [`ProgressMode::Continuous`]: crate::simulation::random_walk_metropolis_hastings::ProgressMode::Continuous
+
+use std::sync::{
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicBool, Ordering},
+};
+
+use egui::Ui;
+
+use crate::{
+    helpers::{BackgroundTaskManager, BgTaskHandle, TaskProgress},
+    metrics::effective_sample_size,
+    simulation::random_walk_metropolis_hastings::{Rwmh, StopConditions, StopReason},
+};
+
+pub type StepFn = Box<dyn Fn(&mut Rwmh) + Send>;
+
+/// Exchanged with the background thread.
+#[derive(Default)]
+struct Shared {
+    /// Set by the main thread once [`Snapshot`]s are due, the background thread publishes one at its next step.
+    snapshot_wanted: AtomicBool,
+    snapshot: Mutex<Option<Snapshot>>,
+}
+
+struct Snapshot {
+    algo: Arc<Rwmh>,
+    /// Only computed if there is a target for it.
+    ess: Option<f64>,
+}
+
+struct Worker {
+    handle: BgTaskHandle<(Arc<Rwmh>, Option<StopReason>)>,
+    shared: Arc<Shared>,
+}
+
+impl Worker {
+    fn spawn(mut algo: Arc<Rwmh>, step: StepFn, stop: &StopConditions, start_steps: u32) -> Self {
+        // the time limit is checked by the main thread.
+        let StopConditions {
+            max_steps,
+            target_ess,
+            ..
+        } = *stop;
+        let shared = Arc::new(Shared::default());
+        let handle = BgTaskHandle::new(
+            {
+                let shared = shared.clone();
+                move |communicate: BackgroundTaskManager| {
+                    let algo_ref = Arc::make_mut(&mut algo);
+                    let reason = loop {
+                        if communicate.is_abort_requested() {
+                            break None;
+                        }
+                        step(algo_ref);
+                        if max_steps.is_some_and(|max| algo_ref.step_count() - start_steps >= max) {
+                            break Some(StopReason::MaxSteps);
+                        }
+                        if shared.snapshot_wanted.swap(false, Ordering::AcqRel) {
+                            let ess = target_ess.map(|_| effective_sample_size(algo_ref));
+                            *shared
+                                .snapshot
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner) = Some(Snapshot {
+                                algo: Arc::new(algo_ref.clone()),
+                                ess,
+                            });
+                            if let (Some(ess), Some(target)) = (ess, target_ess)
+                                && ess >= target
+                            {
+                                break Some(StopReason::TargetEss);
+                            }
+                        }
+                    };
+                    (algo, reason)
+                }
+            },
+            // progress is shown as steps and time instead.
+            1,
+        );
+        Self { handle, shared }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.handle.get_progress(), TaskProgress::Finished)
+    }
+}
+
+enum State {
+    Running {
+        worker: Worker,
+        /// `egui` input time of the last frame.
+        last_frame: f64,
+        last_snapshot: f64,
+    },
+    /// Asked the worker to end, waiting for the final chain.
+    Ending {
+        worker: Worker,
+        then: Then,
+    },
+    Paused,
+    /// Stopped while paused, reported by the next [`ContinuousRun::poll`].
+    Stopped,
+}
+
+enum Then {
+    Pause,
+    Stop(Option<StopReason>),
+}
+
+/// What changed since the last frame.
+#[derive(Default)]
+pub struct Update {
+    /// Newest state of the chain, replaces the shown one.
+    pub algo: Option<Arc<Rwmh>>,
+    /// Whether the run is over, it has to be dropped then.
+    pub ended: bool,
+    /// Set if the run ended because a stop condition was met.
+    pub reason: Option<StopReason>,
+}
+
+pub struct ContinuousRun {
+    state: State,
+    /// Taken from the settings whenever the run is started or resumed.
+    stop: StopConditions,
+    snapshot_interval: f64,
+    /// Seconds spent running, pauses excluded.
+    elapsed: f64,
+    /// [`Rwmh::step_count`] when the run was started.
+    start_steps: u32,
+    /// Of the last snapshot.
+    ess: Option<f64>,
+}
+
+impl ContinuousRun {
+    /// `now` is the `egui` input time.
+    pub fn start(
+        algo: Arc<Rwmh>,
+        step: StepFn,
+        snapshot_interval: f32,
+        stop: StopConditions,
+        now: f64,
+    ) -> Self {
+        let mut run = Self {
+            state: State::Paused,
+            stop: StopConditions::default(),
+            snapshot_interval: 0.0,
+            elapsed: 0.0,
+            start_steps: algo.step_count(),
+            ess: None,
+        };
+        run.resume(algo, step, snapshot_interval, stop, now);
+        run
+    }
+
+    /// Continues with `algo`, which may have been edited while paused.
+    pub fn resume(
+        &mut self,
+        algo: Arc<Rwmh>,
+        step: StepFn,
+        snapshot_interval: f32,
+        stop: StopConditions,
+        now: f64,
+    ) {
+        // a chain that was reset meanwhile starts over.
+        self.start_steps = self.start_steps.min(algo.step_count());
+        self.stop = stop;
+        self.snapshot_interval = f64::from(snapshot_interval);
+        self.state = State::Running {
+            worker: Worker::spawn(algo, step, &self.stop, self.start_steps),
+            last_frame: now,
+            last_snapshot: now,
+        };
+    }
+
+    pub fn pause(&mut self) {
+        self.end(Then::Pause);
+    }
+
+    pub fn stop(&mut self) {
+        self.end(Then::Stop(None));
+    }
+
+    fn end(&mut self, then: Then) {
+        let stops = matches!(then, Then::Stop(_));
+        self.state = match std::mem::replace(&mut self.state, State::Stopped) {
+            State::Running { mut worker, .. } => {
+                worker.handle.request_abort();
+                State::Ending { worker, then }
+            }
+            // the worker already ends, but stopping overrides pausing.
+            State::Ending {
+                worker,
+                then: previous,
+            } => State::Ending {
+                worker,
+                then: if stops { then } else { previous },
+            },
+            State::Paused if stops => State::Stopped,
+            state @ (State::Paused | State::Stopped) => state,
+        };
+    }
+
+    /// Whether a background thread steps the chain, settings can't be changed then.
+    pub const fn is_running(&self) -> bool {
+        matches!(self.state, State::Running { .. } | State::Ending { .. })
+    }
+
+    pub const fn is_paused(&self) -> bool {
+        matches!(self.state, State::Paused)
+    }
+
+    /// Call every frame.
+    pub fn poll(&mut self, now: f64) -> Update {
+        let mut update = Update::default();
+        match self.state {
+            State::Running {
+                ref worker,
+                ref mut last_frame,
+                ref mut last_snapshot,
+            } => {
+                self.elapsed += now - *last_frame;
+                *last_frame = now;
+                if now - *last_snapshot >= self.snapshot_interval {
+                    *last_snapshot = now;
+                    worker.shared.snapshot_wanted.store(true, Ordering::Release);
+                }
+                // the main thread mustn't block on the web, the snapshot is picked up next frame instead.
+                if let Ok(mut snapshot) = worker.shared.snapshot.try_lock()
+                    && let Some(Snapshot { algo, ess }) = snapshot.take()
+                {
+                    update.algo = Some(algo);
+                    self.ess = ess.or(self.ess);
+                }
+                if self
+                    .stop
+                    .time_limit
+                    .is_some_and(|limit| self.elapsed >= limit)
+                {
+                    self.end(Then::Stop(Some(StopReason::TimeLimit)));
+                } else if worker.is_finished() {
+                    // it ended on its own, with the reason in its result.
+                    self.end(Then::Stop(None));
+                }
+            }
+            State::Ending { ref worker, .. } => {
+                if worker.is_finished() {
+                    let State::Ending {
+                        worker: finished,
+                        then,
+                    } = std::mem::replace(&mut self.state, State::Paused)
+                    else {
+                        unreachable!("matched above");
+                    };
+                    let (algo, reason) = finished.handle.get_value();
+                    match then {
+                        Then::Pause => update.algo = Some(algo),
+                        Then::Stop(requested) => {
+                            update.algo = Some(algo);
+                            update.ended = true;
+                            update.reason = reason.or(requested);
+                        }
+                    }
+                }
+            }
+            State::Stopped => update.ended = true,
+            State::Paused => {}
+        }
+        update
+    }
+
+    /// Shows the progress of the run, `algo` being the shown chain.
+    pub fn status_ui(&self, ui: &mut Ui, algo: &Rwmh) {
+        let steps = algo.step_count().saturating_sub(self.start_steps);
+        let mut status = format!("{steps} steps in {:.1} s", self.elapsed);
+        if let Some(ess) = self.ess {
+            status.push_str(&format!(", ESS ≈ {ess:.0}"));
+        }
+        match self.state {
+            State::Running { .. } => {}
+            State::Ending { .. } => status.push_str(", finishing"),
+            State::Paused | State::Stopped => status.push_str(", paused"),
+        }
+        ui.label(status);
+    }
+}
+
+/// Settings of [`ProgressMode::Continuous`].
+///
+/// [`ProgressMode::Continuous`]: crate::simulation::random_walk_metropolis_hastings::ProgressMode::Continuous
+pub fn settings_ui(ui: &mut Ui, snapshot_interval: &mut f32, stop: &mut StopConditions) {
+    ui.add(
+        egui::Slider::new(snapshot_interval, 0.02..=2.0)
+            .logarithmic(true)
+            .suffix(" s")
+            .text("snapshot interval"),
+    )
+    .on_hover_text("How often the shown chain is updated while it runs.");
+    ui.label("stop after (optional):");
+    optional_value(ui, "steps", &mut stop.max_steps, 100_000, |value| {
+        egui::DragValue::new(value).range(1..=u32::MAX).speed(100)
+    });
+    optional_value(
+        ui,
+        "effective sample size",
+        &mut stop.target_ess,
+        1_000.0,
+        |value| egui::DragValue::new(value).range(1.0..=1e9).speed(10),
+    );
+    optional_value(ui, "seconds", &mut stop.time_limit, 10.0, |value| {
+        egui::DragValue::new(value).range(0.1..=86_400.0).speed(0.1)
+    });
+}
+
+fn optional_value<T>(
+    ui: &mut Ui,
+    label: &str,
+    value: &mut Option<T>,
+    default: T,
+    drag: impl FnOnce(&mut T) -> egui::DragValue<'_>,
+) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        ui.checkbox(&mut enabled, label);
+        match (enabled, value.as_mut()) {
+            (true, Some(inner)) => {
+                ui.add(drag(inner));
+            }
+            (true, None) => *value = Some(default),
+            (false, _) => *value = None,
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::target_distr::Gaussian;
+
+    fn step() -> StepFn {
+        let target = Gaussian::default();
+        Box::new(move |algo: &mut Rwmh| algo.step(&target))
+    }
+
+    fn max_steps(max: u32) -> StopConditions {
+        StopConditions {
+            max_steps: Some(max),
+            ..Default::default()
+        }
+    }
+
+    /// Polls until the run is paused or over, returning the last chain.
+    fn settle(run: &mut ContinuousRun) -> (Arc<Rwmh>, Update) {
+        let mut last = None;
+        loop {
+            let update = run.poll(0.0);
+            if let Some(ref algo) = update.algo {
+                last = Some(algo.clone());
+            }
+            if update.ended || run.is_paused() {
+                return (last.unwrap(), update);
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn paused_runs_continue_identically() {
+        let mut uninterrupted =
+            ContinuousRun::start(Arc::default(), step(), 1.0, max_steps(20_000), 0.0);
+        let (expected, first) = settle(&mut uninterrupted);
+        assert_eq!(first.reason, Some(StopReason::MaxSteps));
+        assert_eq!(expected.step_count(), 20_000);
+
+        let mut paused = ContinuousRun::start(Arc::default(), step(), 1.0, max_steps(20_000), 0.0);
+        paused.pause();
+        let (halfway, _) = settle(&mut paused);
+        paused.resume(halfway, step(), 1.0, max_steps(20_000), 0.0);
+        let (resumed, second) = settle(&mut paused);
+        assert_eq!(second.reason, Some(StopReason::MaxSteps));
+        assert_eq!(resumed.history, expected.history);
+        assert_eq!(resumed.current_loc, expected.current_loc);
+    }
+
+    #[test]
+    fn resuming_with_a_reset_chain_counts_from_it() {
+        let target = Gaussian::default();
+        let mut stepped = Rwmh::default();
+        for _ in 0..1_000 {
+            stepped.step(&target);
+        }
+        let mut run = ContinuousRun::start(Arc::new(stepped), step(), 1.0, max_steps(500), 0.0);
+        run.pause();
+        let (paused, _) = settle(&mut run);
+        assert!(paused.step_count() >= 1_000);
+        run.resume(Arc::default(), step(), 1.0, max_steps(500), 0.0);
+        let (resumed, update) = settle(&mut run);
+        assert_eq!(update.reason, Some(StopReason::MaxSteps));
+        assert_eq!(resumed.step_count(), 500);
+    }
+
+    #[test]
+    fn stopping_ends_the_run() {
+        let mut stopped =
+            ContinuousRun::start(Arc::default(), step(), 1.0, StopConditions::default(), 0.0);
+        stopped.stop();
+        let (_, by_user) = settle(&mut stopped);
+        assert!(by_user.ended);
+        assert_eq!(by_user.reason, None);
+
+        let mut limited = ContinuousRun::start(
+            Arc::default(),
+            step(),
+            1.0,
+            StopConditions {
+                time_limit: Some(1.0),
+                ..Default::default()
+            },
+            0.0,
+        );
+        // two seconds later, the worker still has to notice though.
+        assert!(!limited.poll(2.0).ended);
+        let (_, by_limit) = settle(&mut limited);
+        assert_eq!(by_limit.reason, Some(StopReason::TimeLimit));
+    }
+}
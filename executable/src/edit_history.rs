@@ -0,0 +1,375 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+use crate::{
+    simulation::{
+        DEFAULT_SEED, WrappedRng, WrappedRngDiscriminants,
+        random_walk_metropolis_hastings::{AlgoParams, OutOfSupport, ProgressMode},
+    },
+    target_distr,
+};
+
+/// Everything undo and redo restores.
+#[derive(Clone, PartialEq)]
+pub struct EditState {
+    pub target_distr: target_distr::Gaussian,
+    pub params: EditableParams,
+}
+
+/// The parts of [`AlgoParams`] the user sets.
+///
+/// The RNGs advance with every step, so only their kind and seed are edits.
+#[derive(Clone, PartialEq)]
+pub struct EditableParams {
+    pub sigma: f32,
+    pub proposal_rng: RngChoice,
+    pub accept_rng: RngChoice,
+    pub progress_mode: ProgressMode,
+    pub out_of_support: OutOfSupport,
+    pub start: [f32; 2],
+}
+
+impl EditableParams {
+    pub fn of(params: &AlgoParams) -> Self {
+        Self {
+            sigma: params.proposal.sigma,
+            proposal_rng: RngChoice::of(&params.proposal.rng.rng, params.proposal.rng.seed),
+            accept_rng: RngChoice::of(&params.accept.rng, params.accept.seed),
+            progress_mode: params.progress_mode.clone(),
+            out_of_support: params.out_of_support,
+            start: params.start,
+        }
+    }
+
+    /// The RNGs are only reseeded if their kind or seed differ, otherwise they keep their state.
+    pub fn apply_to(&self, params: &mut AlgoParams) {
+        params.proposal.sigma = self.sigma;
+        // an unknown seed stays unknown, otherwise that would look like another edit.
+        let proposal = &mut params.proposal.rng;
+        if let Some(rng) = self.proposal_rng.reseeded(&proposal.rng, proposal.seed) {
+            proposal.rng = rng;
+            proposal.seed = self.proposal_rng.seed;
+        }
+        let accept = &mut params.accept;
+        if let Some(rng) = self.accept_rng.reseeded(&accept.rng, accept.seed) {
+            accept.rng = rng;
+            accept.seed = self.accept_rng.seed;
+        }
+        params.progress_mode = self.progress_mode.clone();
+        params.out_of_support = self.out_of_support;
+        params.start = self.start;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct RngChoice {
+    pub kind: WrappedRngDiscriminants,
+    pub seed: Option<u64>,
+}
+
+impl RngChoice {
+    fn of(rng: &WrappedRng, seed: Option<u64>) -> Self {
+        Self {
+            kind: WrappedRngDiscriminants::from(rng),
+            seed,
+        }
+    }
+
+    /// A freshly seeded RNG if `rng` isn't this choice already, an unknown seed is replaced by the default one.
+    fn reseeded(self, rng: &WrappedRng, seed: Option<u64>) -> Option<WrappedRng> {
+        (Self::of(rng, seed) != self)
+            .then(|| self.kind.seed_from_u64(self.seed.unwrap_or(DEFAULT_SEED)))
+    }
+}
+
+struct EditCommand {
+    description: String,
+    before: EditState,
+    after: EditState,
+}
+
+/// Undo/redo stack of edits to the target distribution and the sampler parameters.
+///
+/// Edits are detected by comparing against the state after the last recorded command,
+/// so the UI code doing the edits doesn't need to know about this.
+#[derive(Default)]
+pub struct EditHistory {
+    committed: Option<EditState>,
+    undo_stack: Vec<EditCommand>,
+    redo_stack: Vec<EditCommand>,
+}
+
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+impl EditHistory {
+    /// Oldest commands are dropped beyond this.
+    const MAX_LEN: usize = 200;
+
+    /// Records the changes since the last call as a single command.
+    ///
+    /// Only call this while no interaction is ongoing, that way a whole drag ends up as one command.
+    pub fn record(&mut self, current: &EditState) {
+        let Some(ref committed) = self.committed else {
+            self.committed = Some(current.clone());
+            return;
+        };
+        if committed == current {
+            return;
+        }
+        self.undo_stack.push(EditCommand {
+            description: describe(committed, current),
+            before: committed.clone(),
+            after: current.clone(),
+        });
+        if self.undo_stack.len() > Self::MAX_LEN {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+        self.committed = Some(current.clone());
+    }
+
+    /// Compares further edits against `current`, without recording the changes up to it.
+    ///
+    /// For changes that aren't edits, like loading a scenario or taking over a sampled chain.
+    pub fn rebase(&mut self, current: &EditState) {
+        self.committed = Some(current.clone());
+    }
+
+    /// Returns the state to restore.
+    pub fn undo(&mut self) -> Option<EditState> {
+        let command = self.undo_stack.pop()?;
+        let state = command.before.clone();
+        self.redo_stack.push(command);
+        self.committed = Some(state.clone());
+        Some(state)
+    }
+
+    /// Returns the state to restore.
+    pub fn redo(&mut self) -> Option<EditState> {
+        let command = self.redo_stack.pop()?;
+        let state = command.after.clone();
+        self.undo_stack.push(command);
+        self.committed = Some(state.clone());
+        Some(state)
+    }
+
+    /// Ctrl+Z and Ctrl+Shift+Z (Cmd on Mac), returns the state to restore.
+    pub fn handle_shortcuts(&mut self, ctx: &egui::Context) -> Option<EditState> {
+        // redo first, as its shortcut contains the one of undo.
+        if ctx.input_mut(|input| input.consume_shortcut(&REDO_SHORTCUT)) {
+            self.redo()
+        } else if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT)) {
+            self.undo()
+        } else {
+            None
+        }
+    }
+
+    /// Buttons and a list of all commands, clicking one jumps to the state after it.
+    /// Returns the state to restore.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<EditState> {
+        let mut restore = None;
+        ui.horizontal(|ui| {
+            let undo = ui.add_enabled(!self.undo_stack.is_empty(), egui::Button::new("undo"));
+            if undo
+                .on_hover_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT))
+                .clicked()
+            {
+                restore = self.undo();
+            }
+            let redo = ui.add_enabled(!self.redo_stack.is_empty(), egui::Button::new("redo"));
+            if redo
+                .on_hover_text(ui.ctx().format_shortcut(&REDO_SHORTCUT))
+                .clicked()
+            {
+                restore = self.redo();
+            }
+        });
+        let mut undo_count = 0;
+        let mut redo_count = 0;
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                if ui
+                    .selectable_label(self.undo_stack.is_empty(), "initial state")
+                    .clicked()
+                {
+                    undo_count = self.undo_stack.len();
+                }
+                for (idx, command) in self.undo_stack.iter().enumerate() {
+                    let is_current = idx + 1 == self.undo_stack.len();
+                    if ui
+                        .selectable_label(is_current, &command.description)
+                        .clicked()
+                    {
+                        undo_count = self.undo_stack.len() - idx - 1;
+                    }
+                }
+                // redo stack is reversed, the next command to redo is on top.
+                for (idx, command) in self.redo_stack.iter().rev().enumerate() {
+                    let label = egui::RichText::new(&command.description).weak();
+                    if ui.selectable_label(false, label).clicked() {
+                        redo_count = idx + 1;
+                    }
+                }
+            });
+        for _ in 0..undo_count {
+            restore = self.undo();
+        }
+        for _ in 0..redo_count {
+            restore = self.redo();
+        }
+        restore
+    }
+}
+
+#[expect(
+    clippy::float_cmp,
+    reason = "any change is an edit, no matter how small"
+)]
+fn describe(before: &EditState, after: &EditState) -> String {
+    let mut changes = Vec::new();
+    let (old_elements, new_elements) = (
+        &before.target_distr.gaussians,
+        &after.target_distr.gaussians,
+    );
+    if new_elements.len() > old_elements.len() {
+        changes.push("added Gaussian element".to_owned());
+    } else if new_elements.len() < old_elements.len() {
+        changes.push("deleted Gaussian element".to_owned());
+    } else {
+        let changed: Vec<_> = old_elements
+            .iter()
+            .zip(new_elements)
+            .enumerate()
+            .filter(|&(_, (old, new))| old != new)
+            .collect();
+        match *changed.as_slice() {
+            [] => {}
+            [(idx, (old, new))] if old.position != new.position => {
+                changes.push(format!("moved Gaussian element {idx}"));
+            }
+            [(idx, _)] => changes.push(format!("changed Gaussian element {idx}")),
+            _ => changes.push("changed Gaussian elements".to_owned()),
+        }
+    }
+    if before.target_distr.support != after.target_distr.support {
+        changes.push("changed support".to_owned());
+    }
+    let (old_params, new_params) = (&before.params, &after.params);
+    if old_params.sigma != new_params.sigma {
+        changes.push("changed proposal sigma".to_owned());
+    }
+    if old_params.proposal_rng != new_params.proposal_rng {
+        changes.push("changed proposal RNG".to_owned());
+    }
+    if old_params.accept_rng != new_params.accept_rng {
+        changes.push("changed acceptance RNG".to_owned());
+    }
+    if old_params.progress_mode != new_params.progress_mode {
+        changes.push(describe_progress_mode(
+            &old_params.progress_mode,
+            &new_params.progress_mode,
+        ));
+    }
+    if old_params.out_of_support != new_params.out_of_support {
+        changes.push("changed out-of-support handling".to_owned());
+    }
+    if old_params.start != new_params.start {
+        changes.push("changed start point".to_owned());
+    }
+    changes.join(", ")
+}
+
+fn describe_progress_mode(before: &ProgressMode, after: &ProgressMode) -> String {
+    use ProgressMode as P;
+    match (before, after) {
+        (&P::Batched { .. }, &P::Batched { .. }) => "changed batch size",
+        (&P::Continuous { .. }, &P::Continuous { .. }) => "changed continuous run settings",
+        (&P::Animated { .. }, &P::Animated { .. }) => "changed animation speed",
+        (_, &P::Batched { .. }) => "switched to batches",
+        (_, &P::Continuous { .. }) => "switched to continuous runs",
+        (_, &P::Animated { .. }) => "switched to animated steps",
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn undo_redo_round_trip() {
+        let mut history = EditHistory::default();
+        let initial = EditState {
+            target_distr: Default::default(),
+            params: EditableParams::of(&Default::default()),
+        };
+        history.record(&initial);
+        let mut edited = initial.clone();
+        edited.target_distr.gaussians.pop();
+        history.record(&edited);
+
+        assert!(history.undo() == Some(initial.clone()));
+        assert!(history.undo().is_none());
+        assert!(history.redo() == Some(edited.clone()));
+        // a new edit discards what could have been redone.
+        history.undo();
+        edited.params.sigma *= 2.0;
+        history.record(&edited);
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn progress_mode_changes_are_told_apart() {
+        let batched = ProgressMode::default();
+        assert_eq!(
+            describe_progress_mode(&batched, &ProgressMode::animated()),
+            "switched to animated steps"
+        );
+        assert_eq!(
+            describe_progress_mode(&ProgressMode::continuous(), &batched),
+            "switched to batches"
+        );
+        assert_eq!(
+            describe_progress_mode(
+                &ProgressMode::animated(),
+                &ProgressMode::Animated { step_duration: 0.5 }
+            ),
+            "changed animation speed"
+        );
+    }
+
+    #[test]
+    fn sampling_is_no_edit() {
+        let mut history = EditHistory::default();
+        let mut params = AlgoParams::default();
+        history.record(&EditState {
+            target_distr: Default::default(),
+            params: EditableParams::of(&params),
+        });
+        params.proposal.rng.unwrapped_next();
+        params.accept.unwrapped_next();
+        history.record(&EditState {
+            target_distr: Default::default(),
+            params: EditableParams::of(&params),
+        });
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn restoring_keeps_the_rng_state() {
+        let mut params = AlgoParams::default();
+        params.proposal.rng.unwrapped_next();
+        let advanced = params.proposal.rng.clone();
+        let mut editable = EditableParams::of(&params);
+        editable.sigma *= 2.0;
+        editable.apply_to(&mut params);
+        assert!(params.proposal.rng == advanced);
+
+        editable.proposal_rng.seed = Some(DEFAULT_SEED + 1);
+        editable.apply_to(&mut params);
+        assert!(EditableParams::of(&params) == editable);
+    }
+}
@@ -28,6 +28,12 @@ impl BackgroundTaskManager {
         self.abort.is_abort_requested()
     }
 
+    /// For tasks without a meaningful progress, see [`Self::update_progress_and_check_abort`] otherwise.
+    #[must_use]
+    pub fn is_abort_requested(&self) -> bool {
+        self.abort.is_abort_requested()
+    }
+
     #[must_use]
     pub(super) fn current_progress(&self) -> TaskProgress {
         self.progress.current_progress()
@@ -46,6 +46,11 @@ impl<Final: Send + 'static> BgTaskHandle<Final> {
         self.communicate.current_progress()
     }
 
+    /// Asks the task to end early, it's finished once it noticed.
+    pub fn request_abort(&mut self) {
+        self.communicate.request_abort();
+    }
+
     /// # Panics
     ///
     /// Panics if task is not finished or panicked
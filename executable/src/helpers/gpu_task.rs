@@ -4,10 +4,7 @@ pub(crate) trait GpuTask {
     async fn run(&mut self, compute_device: Arc<wgpu::Device>, compute_queue: Arc<wgpu::Queue>);
 }
 
-use crate::{
-    definition_location, diagnostics::cfg_gpu_profile::required_wgpu_features,
-    visualizations::BdaComputeTask,
-};
+use crate::visualizations::BdaComputeTask;
 
 use super::async_last_task_processor::{self, TaskDispatcher, TaskExecutorFactory};
 
@@ -32,19 +29,6 @@ pub(crate) fn get_gpu_channels() -> (GpuTaskSenders, GpuTaskReceivers) {
     )
 }
 
-/// # Panics
-/// If no wgpu device could be found with the provided settings, if the gpu_task channel was closed.
-pub(crate) async fn get_compute_queue(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
-    adapter
-        .request_device(&wgpu::DeviceDescriptor {
-            label: Some(definition_location!()),
-            required_features: required_wgpu_features(adapter),
-            ..Default::default()
-        })
-        .await
-        .unwrap()
-}
-
 /// TODO: Consider moving this to be a struct instead, with cancel on drop etc.
 /// This being a function was originally required from embassy-rs, which is now replaced with tokio.
 pub(crate) async fn gpu_scheduler(
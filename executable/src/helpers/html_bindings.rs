@@ -18,6 +18,62 @@ pub fn get_egui_canvas() -> web_sys::HtmlCanvasElement {
         .expect("Root element is no canvas")
 }
 
+/// Offers `bytes` as a file download named `name`.
+///
+/// # Errors
+/// If any of the involved browser APIs fails, with its error formatted.
+pub fn download(name: &str, bytes: &[u8]) -> Result<(), String> {
+    let describe = |err: web_sys::wasm_bindgen::JsValue| format!("{name}: {err:?}");
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).map_err(describe)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(describe)?;
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| format!("{name}: no document"))?
+        .create_element("a")
+        .map_err(describe)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|element| describe(element.into()))?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    // Not revoking the URL, as the download might not have started yet. It's freed with the page.
+    anchor.click();
+    Ok(())
+}
+
+/// The part of the page URL after `#`, `None` if it's empty.
+pub fn url_fragment() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    hash.strip_prefix('#')
+        .filter(|fragment| !fragment.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+/// Removes the fragment from the page URL without reloading the page.
+pub fn clear_url_fragment() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    if let (Ok(path), Ok(search), Ok(history)) =
+        (location.pathname(), location.search(), window.history())
+        && let Err(err) = history.replace_state_with_url(
+            &web_sys::wasm_bindgen::JsValue::NULL,
+            "",
+            Some(&format!("{path}{search}")),
+        )
+    {
+        tracing::warn!("Failed to remove the URL fragment: {err:?}");
+    }
+}
+
+/// The page URL with its fragment replaced by `fragment`.
+pub fn url_with_fragment(fragment: &str) -> Option<String> {
+    let href = web_sys::window()?.location().href().ok()?;
+    let page = href.split_once('#').map_or(href.as_str(), |(page, _)| page);
+    Some(format!("{page}#{fragment}"))
+}
+
 pub fn remove_el_if_present(id: &str) {
     get_element_by_id(id).as_ref().map(Element::remove);
 }
@@ -10,9 +10,7 @@ use tokio::task;
 
 pub use async_last_task_processor::TaskDispatcher;
 pub use bg_task::{BackgroundTaskManager, BgTaskHandle, TaskProgress};
-pub(crate) use gpu_task::{
-    GpuTask, GpuTaskSenders, get_compute_queue, get_gpu_channels, gpu_scheduler,
-};
+pub(crate) use gpu_task::{GpuTask, GpuTaskSenders, get_gpu_channels, gpu_scheduler};
 pub use temp_ui_state::TempStateDataAccess;
 
 // use crate::{definition_location, diagnostics::cfg_gpu_profile};
@@ -1,8 +1,10 @@
 mod app;
+mod continuous_run;
 pub mod diagnostics;
+mod edit_history;
 mod helpers;
-mod simulation;
-mod target_distr;
+#[cfg(feature = "persistence")]
+mod persisted_state;
 mod visualizations;
 pub use app::McmcDemo;
 #[cfg(feature = "tracing")]
@@ -10,7 +12,10 @@ pub use diagnostics::tracing::{define_subscriber, set_default_and_redirect_log};
 #[cfg(target_arch = "wasm32")]
 pub use helpers::html_bindings;
 pub use helpers::wgpu_options;
+#[cfg(feature = "persistence")]
+use sampling::checkpoint;
+use sampling::share_link;
+use sampling::{
+    contours, export, external_samples, kde, metrics, scenario, simulation, target_distr,
+};
 pub use visualizations::INITIAL_RENDER_SIZE;
-
-#[cfg(not(any(feature = "rng_pcg", feature = "rng_xorshift", feature = "rng_xoshiro")))]
-compile_error!("no rng compiled in.");
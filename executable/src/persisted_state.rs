@@ -0,0 +1,339 @@
+//! Versioned storage of [`McmcDemo`], so that changing its fields doesn't wipe the state of returning users.
+//!
+//! The state is stored as JSON together with the version of its layout, instead of eframe's RON,
+//! as migrations work on the untyped tree and RON's one can't represent enums.
+//! Older states are migrated step by step, whatever still doesn't load afterwards is recovered field by field.
+//! That way e.g. the target distribution survives even if the chain can't be restored.
+
+use serde_json::{Map, Value};
+
+use crate::{McmcDemo, target_distr::Gaussian};
+
+/// Separate from [`eframe::APP_KEY`], which holds the unversioned RON of earlier releases.
+const STATE_KEY: &str = "versioned_state";
+
+/// Transforms a state of one layout into the next one.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// Entry `n` turns a state of version `n + 1` into one of version `n + 2`,
+/// so the current version is one more than the number of entries.
+///
+/// Add an entry whenever a persisted field is renamed, removed or changes its meaning or type.
+const MIGRATIONS: &[Migration] = &[contours_keep_their_settings];
+
+/// Layout 2 keeps the contour settings while the lines are hidden, layout 1 had `null` for no lines.
+fn contours_keep_their_settings(state: &mut Map<String, Value>) -> Result<(), String> {
+    match state.get_mut("contours") {
+        // the defaults are hidden.
+        None | Some(&mut Value::Null) => {
+            state.remove("contours");
+        }
+        Some(&mut Value::Object(ref mut settings)) => {
+            settings.insert("shown".to_owned(), Value::Bool(true));
+        }
+        Some(_) => return Err("contours aren't an object or null".to_owned()),
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Envelope {
+    version: u64,
+    state: Value,
+}
+
+pub struct Loaded {
+    pub state: McmcDemo,
+    /// What couldn't be restored, to be shown to the user.
+    pub problem: Option<String>,
+}
+
+pub fn save(storage: &mut dyn eframe::Storage, state: &McmcDemo) {
+    let envelope = serde_json::to_value(state).and_then(|state| {
+        serde_json::to_string(&Envelope {
+            version: current_version(MIGRATIONS),
+            state,
+        })
+    });
+    match envelope {
+        Ok(envelope) => storage.set_string(STATE_KEY, envelope),
+        Err(err) => tracing::error!("Failed to serialize the app state: {err}"),
+    }
+}
+
+/// `None` if nothing was stored yet.
+pub fn load(storage: &dyn eframe::Storage) -> Option<Loaded> {
+    if let Some(stored) = storage.get_string(STATE_KEY) {
+        return Some(load_versioned(&stored, MIGRATIONS));
+    }
+    storage.get_string(eframe::APP_KEY)?;
+    if let Some(state) = eframe::get_value::<McmcDemo>(storage, eframe::APP_KEY) {
+        return Some(Loaded {
+            state,
+            problem: None,
+        });
+    }
+    #[derive(serde::Deserialize)]
+    struct TargetOnly {
+        target_distr: Gaussian,
+    }
+    let Some(TargetOnly { target_distr }) = eframe::get_value(storage, eframe::APP_KEY) else {
+        return Some(Loaded {
+            state: McmcDemo::default(),
+            problem: Some("The state saved by an older version couldn't be read.".to_owned()),
+        });
+    };
+    let mut stored = Map::new();
+    if let Ok(target_distr) = serde_json::to_value(target_distr) {
+        stored.insert("target_distr".to_owned(), target_distr);
+    }
+    let Loaded { state, .. } = recover(&stored, "");
+    Some(Loaded {
+        state,
+        problem: Some(
+            "Only the target distribution of the state saved by an older version was restored."
+                .to_owned(),
+        ),
+    })
+}
+
+const fn current_version(migrations: &[Migration]) -> u64 {
+    migrations.len() as u64 + 1
+}
+
+fn load_versioned(stored: &str, migrations: &[Migration]) -> Loaded {
+    let (version, mut state) = match serde_json::from_str(stored) {
+        Ok(Envelope {
+            version,
+            state: Value::Object(state),
+        }) => (version, state),
+        Ok(_) => return unreadable("not an object"),
+        Err(err) => return unreadable(&err.to_string()),
+    };
+    let current = current_version(migrations);
+    if version > current {
+        return recover(
+            &state,
+            &format!(
+                "it was saved by a newer version (layout {version}, this one reads up to {current})"
+            ),
+        );
+    }
+    if let Err(err) = migrate(&mut state, version, migrations) {
+        return recover(&state, &err);
+    }
+    match serde_json::from_value(Value::Object(state.clone())) {
+        Ok(state) => Loaded {
+            state,
+            problem: None,
+        },
+        Err(err) => recover(&state, &err.to_string()),
+    }
+}
+
+fn migrate(
+    state: &mut Map<String, Value>,
+    version: u64,
+    migrations: &[Migration],
+) -> Result<(), String> {
+    let done = usize::try_from(version.saturating_sub(1)).unwrap_or(usize::MAX);
+    for (from, migration) in (version..).zip(migrations.iter().skip(done)) {
+        migration(state).map_err(|err| format!("migrating from layout {from} failed: {err}"))?;
+    }
+    Ok(())
+}
+
+fn unreadable(reason: &str) -> Loaded {
+    Loaded {
+        state: McmcDemo::default(),
+        problem: Some(format!(
+            "The saved state couldn't be read ({reason}), starting over."
+        )),
+    }
+}
+
+/// Starts from the default state, and takes over every stored top level field with which the state still loads.
+fn recover(stored: &Map<String, Value>, reason: &str) -> Loaded {
+    let loads = |candidate: &Map<String, Value>| {
+        serde_json::from_value::<McmcDemo>(Value::Object(candidate.clone())).ok()
+    };
+    let Ok(Value::Object(mut recovered)) = serde_json::to_value(McmcDemo::default()) else {
+        return unreadable(reason);
+    };
+    let mut reset = Vec::new();
+    for (key, value) in stored {
+        // Unknown fields are ignored when loading anyways.
+        let Some(slot) = recovered.get_mut(key) else {
+            continue;
+        };
+        let default = std::mem::replace(slot, value.clone());
+        if loads(&recovered).is_none() {
+            recovered.insert(key.clone(), default);
+            reset.push(key.as_str());
+        }
+    }
+    reset.extend(
+        recovered
+            .keys()
+            .filter(|key| !stored.contains_key(*key))
+            .map(String::as_str),
+    );
+    Loaded {
+        state: loads(&recovered).unwrap_or_default(),
+        problem: (!reset.is_empty()).then(|| {
+            format!(
+                "The saved state couldn't be restored completely ({reason}), reset were: {}.",
+                reset.join(", ")
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn field(state: &McmcDemo, key: &str) -> Value {
+        serde_json::to_value(state)
+            .ok()
+            .and_then(|value| value.get(key).cloned())
+            .unwrap()
+    }
+
+    fn custom_target() -> Value {
+        serde_json::json!({
+            "gaussians": [{ "position": [0.5, -0.25], "variance": 0.125, "scale": 2.0 }],
+            "support": "Unbounded",
+        })
+    }
+
+    fn stored_with_target() -> Map<String, Value> {
+        let Ok(Value::Object(mut state)) = serde_json::to_value(McmcDemo::default()) else {
+            panic!("state isn't an object");
+        };
+        state.insert("target_distr".to_owned(), custom_target());
+        state
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = MemoryStorage::default();
+        assert!(load(&storage).is_none());
+        save(&mut storage, &McmcDemo::default());
+        let Loaded { state, problem } = load(&storage).unwrap();
+        assert!(problem.is_none(), "{problem:?}");
+        assert_eq!(
+            serde_json::to_value(&state).ok(),
+            serde_json::to_value(McmcDemo::default()).ok()
+        );
+    }
+
+    #[test]
+    fn broken_fields_are_reset() {
+        let mut stored = stored_with_target();
+        stored.insert("algo".to_owned(), Value::from("garbage"));
+        let envelope = serde_json::to_string(&Envelope {
+            version: current_version(MIGRATIONS),
+            state: Value::Object(stored),
+        })
+        .unwrap();
+        let Loaded { state, problem } = load_versioned(&envelope, MIGRATIONS);
+        assert_eq!(field(&state, "target_distr"), custom_target());
+        assert!(problem.unwrap().ends_with("reset were: algo."));
+    }
+
+    #[test]
+    fn migrations_run_in_order() {
+        let mut stored = stored_with_target();
+        let renamed = stored.remove("target_distr").unwrap();
+        stored.insert("target".to_owned(), renamed);
+        let migrations: &[Migration] = &[
+            |state| {
+                let moved = state.remove("target").ok_or("no target")?;
+                state.insert("old_target_distr".to_owned(), moved);
+                Ok(())
+            },
+            |state| {
+                let moved = state.remove("old_target_distr").ok_or("no target")?;
+                state.insert("target_distr".to_owned(), moved);
+                Ok(())
+            },
+        ];
+        let oldest = serde_json::to_string(&Envelope {
+            version: 1,
+            state: Value::Object(stored.clone()),
+        })
+        .unwrap();
+        let migrated = load_versioned(&oldest, migrations);
+        assert!(migrated.problem.is_none(), "{:?}", migrated.problem);
+        assert_eq!(field(&migrated.state, "target_distr"), custom_target());
+
+        // a state of the newest layout isn't migrated again.
+        let newest = serde_json::to_string(&Envelope {
+            version: 3,
+            state: Value::Object(stored),
+        })
+        .unwrap();
+        let not_migrated = load_versioned(&newest, migrations);
+        assert!(
+            not_migrated
+                .problem
+                .is_some_and(|problem| problem.ends_with("reset were: target_distr."))
+        );
+        assert_ne!(field(&not_migrated.state, "target_distr"), custom_target());
+    }
+
+    #[test]
+    fn layout_1_keeps_shown_contours() {
+        let Loaded { state, problem } =
+            load_versioned(include_str!("persisted_state/layout_1.json"), MIGRATIONS);
+        assert!(problem.is_none(), "{problem:?}");
+        assert_eq!(field(&state, "target_distr"), custom_target());
+        let contours = field(&state, "contours");
+        assert_eq!(contours.get("shown"), Some(&Value::Bool(true)));
+        assert_eq!(contours.get("masses"), Some(&serde_json::json!([0.5, 0.8])));
+
+        let mut hidden = Map::new();
+        hidden.insert("contours".to_owned(), Value::Null);
+        assert_eq!(contours_keep_their_settings(&mut hidden), Ok(()));
+        assert!(hidden.is_empty());
+    }
+
+    #[test]
+    fn unversioned_target_survives() {
+        #[derive(serde::Serialize)]
+        struct OldState {
+            target_distr: Gaussian,
+            algo: &'static str,
+        }
+        let mut storage = MemoryStorage::default();
+        eframe::set_value(
+            &mut storage,
+            eframe::APP_KEY,
+            &OldState {
+                target_distr: serde_json::from_value(custom_target()).unwrap(),
+                algo: "from an old release",
+            },
+        );
+        let Loaded { state, problem } = load(&storage).unwrap();
+        assert!(problem.is_some());
+        assert_eq!(field(&state, "target_distr"), custom_target());
+    }
+}
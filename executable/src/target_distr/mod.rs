@@ -1,3 +0,0 @@
-mod gaussian;
-
-pub use gaussian::{Distr as Gaussian, NormalDistribution};
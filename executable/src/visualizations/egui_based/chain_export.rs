@@ -0,0 +1,90 @@
+use egui::Ui;
+
+use crate::export::{Format, Layout, OutputFile};
+
+/// Settings for exporting the chain, natively to a directory and on the web as downloads.
+pub struct ChainExport {
+    format: Format,
+    layout: Layout,
+    #[cfg_attr(
+        target_arch = "wasm32",
+        expect(dead_code, reason = "the browser decides where downloads go")
+    )]
+    directory: String,
+    last_result: Option<Result<String, String>>,
+}
+
+impl Default for ChainExport {
+    fn default() -> Self {
+        Self {
+            format: Format::default(),
+            layout: Layout::default(),
+            directory: "mcmc-export".to_owned(),
+            last_result: None,
+        }
+    }
+}
+
+impl ChainExport {
+    /// Returns the chosen format and layout if an export was requested.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<(Format, Layout)> {
+        ui.horizontal(|ui| {
+            for format in Format::VARIANTS {
+                ui.selectable_value(&mut self.format, *format, format.display_name());
+            }
+        });
+        if self.format == Format::ArviZ {
+            ui.label("A single InferenceData file with one draw per iteration, load it with `arviz.from_json`.");
+        } else {
+            ui.horizontal(|ui| {
+                for layout in Layout::VARIANTS {
+                    ui.selectable_value(&mut self.layout, *layout, layout.display_name())
+                        .on_hover_text(layout.explanation());
+                }
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.label("directory:");
+            ui.text_edit_singleline(&mut self.directory);
+        });
+        let requested = ui.button("export").clicked();
+        match self.last_result {
+            Some(Ok(ref message)) => {
+                ui.label(message);
+            }
+            Some(Err(ref error)) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            None => {}
+        }
+        requested.then_some((self.format, self.layout))
+    }
+
+    pub fn save(&mut self, files: Vec<OutputFile>) {
+        self.last_result = Some(self.write(files));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(&self, files: Vec<OutputFile>) -> Result<String, String> {
+        let directory = std::path::Path::new(&self.directory);
+        std::fs::create_dir_all(directory)
+            .map_err(|err| format!("{}: {err}", directory.display()))?;
+        let count = files.len();
+        for file in files {
+            let path = directory.join(file.name);
+            std::fs::write(&path, file.bytes)
+                .map_err(|err| format!("{}: {err}", path.display()))?;
+        }
+        Ok(format!("wrote {count} files to {}", directory.display()))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(&self, files: Vec<OutputFile>) -> Result<String, String> {
+        let count = files.len();
+        for file in files {
+            crate::helpers::html_bindings::download(&format!("mcmc-{}", file.name), &file.bytes)?;
+        }
+        Ok(format!("downloaded {count} files"))
+    }
+}
@@ -0,0 +1,190 @@
+use egui::Ui;
+
+use crate::{
+    checkpoint::{self, Checkpoint},
+    simulation::random_walk_metropolis_hastings::Rwmh,
+    target_distr::{CpuTarget, Gaussian},
+};
+
+use super::has_extension;
+
+/// Saves and loads [`Checkpoint`]s, natively by path and on the web as downloads and dropped files.
+pub struct CheckpointFile {
+    #[cfg_attr(
+        target_arch = "wasm32",
+        expect(dead_code, reason = "the browser decides where downloads go")
+    )]
+    path: String,
+    last_result: Option<Result<String, String>>,
+}
+
+impl Default for CheckpointFile {
+    fn default() -> Self {
+        Self {
+            path: format!("mcmc-run.{}", checkpoint::EXTENSION),
+            last_result: None,
+        }
+    }
+}
+
+impl CheckpointFile {
+    /// Returns a checkpoint once one was loaded successfully.
+    ///
+    /// Loading would replace the chain, so it's only offered if `can_load`.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        algo: &Rwmh,
+        target_distr: &Gaussian,
+        cpu_target: Option<&CpuTarget>,
+        can_load: bool,
+    ) -> Option<Checkpoint> {
+        #[cfg_attr(
+            target_arch = "wasm32",
+            expect(unused_mut, reason = "only loaded by dropping")
+        )]
+        let mut loaded = None;
+        ui.label("Saves the chain with the state of the RNGs, a loaded run continues exactly where it stopped.");
+        #[cfg(target_arch = "wasm32")]
+        ui.label(format!(
+            "Drop a .{} file onto the window to load it.",
+            checkpoint::EXTENSION
+        ));
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.label("file:");
+            ui.text_edit_singleline(&mut self.path);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("save").clicked() {
+                self.last_result = Some(self.save(algo, target_distr, cpu_target));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .add_enabled(can_load, egui::Button::new("load"))
+                .clicked()
+            {
+                loaded = self.handle(load_from_path(std::path::Path::new(&self.path)));
+            }
+        });
+        if !can_load {
+            ui.label("Loading is possible once the running batch finished.");
+        }
+        match self.last_result {
+            Some(Ok(ref message)) => {
+                ui.label(message);
+            }
+            Some(Err(ref error)) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            None => {}
+        }
+        loaded
+    }
+
+    /// Returns the last checkpoint that was dropped onto the window this frame and could be loaded.
+    pub fn dropped(&mut self, ctx: &egui::Context, can_load: bool) -> Option<Checkpoint> {
+        let mut loaded = None;
+        for file in ctx.input(|input| input.raw.dropped_files.clone()) {
+            if !is_checkpoint_file(&file) {
+                continue;
+            }
+            if !can_load {
+                self.last_result = Some(Err(format!(
+                    "{}: wait for the running batch to finish",
+                    file.name
+                )));
+                continue;
+            }
+            let result = if let Some(bytes) = file.bytes {
+                parse_bytes(&file.name, &bytes)
+            } else if let Some(path) = file.path {
+                load_from_path(&path)
+            } else {
+                continue;
+            };
+            loaded = self.handle(result).or(loaded);
+        }
+        loaded
+    }
+
+    fn handle(&mut self, result: Result<Checkpoint, String>) -> Option<Checkpoint> {
+        match result {
+            Ok(checkpoint) => {
+                self.last_result = Some(Ok(format!(
+                    "loaded a run of {} steps",
+                    checkpoint.algo.step_count()
+                )));
+                Some(checkpoint)
+            }
+            Err(error) => {
+                self.last_result = Some(Err(error));
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(
+        &self,
+        algo: &Rwmh,
+        target_distr: &Gaussian,
+        cpu_target: Option<&CpuTarget>,
+    ) -> Result<String, String> {
+        let path = std::path::Path::new(&self.path);
+        std::fs::File::create(path)
+            .and_then(|file| {
+                checkpoint::write(
+                    algo,
+                    target_distr,
+                    cpu_target,
+                    std::io::BufWriter::new(file),
+                )
+            })
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(format!(
+            "saved {} steps to {}",
+            algo.step_count(),
+            path.display()
+        ))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(
+        &self,
+        algo: &Rwmh,
+        target_distr: &Gaussian,
+        cpu_target: Option<&CpuTarget>,
+    ) -> Result<String, String> {
+        let name = format!("mcmc-run.{}", checkpoint::EXTENSION);
+        let mut bytes = Vec::new();
+        checkpoint::write(algo, target_distr, cpu_target, &mut bytes)
+            .map_err(|err| format!("{name}: {err}"))?;
+        crate::helpers::html_bindings::download(&name, &bytes)?;
+        Ok(format!("downloaded {} steps", algo.step_count()))
+    }
+}
+
+/// Dropped files are told apart by their extension.
+pub fn is_checkpoint_file(file: &egui::DroppedFile) -> bool {
+    has_extension(file, checkpoint::EXTENSION)
+}
+
+fn parse_bytes(name: &str, bytes: &[u8]) -> Result<Checkpoint, String> {
+    Checkpoint::read(bytes).map_err(|err| format!("{name}: {err}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_from_path(path: &std::path::Path) -> Result<Checkpoint, String> {
+    let describe = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+    let file = std::fs::File::open(path).map_err(|err| describe(&err))?;
+    Checkpoint::read(std::io::BufReader::new(file)).map_err(|err| describe(&err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_from_path(path: &std::path::Path) -> Result<Checkpoint, String> {
+    Err(format!(
+        "{}: no file system access in the browser",
+        path.display()
+    ))
+}
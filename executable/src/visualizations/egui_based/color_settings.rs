@@ -0,0 +1,385 @@
+use egui::{Align2, Color32, FontId, Pos2, Rect, Stroke, Vec2, epaint::Mesh};
+
+use macros::cfg_persistence_derive;
+
+/// Perceptually uniform maps that stay readable with the common forms of colour blindness.
+#[cfg_persistence_derive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMap {
+    Viridis,
+    Cividis,
+    /// Diverging from red over white to blue.
+    RdBu,
+}
+
+impl ColorMap {
+    pub const VARIANTS: &'static [Self] = &[Self::Viridis, Self::Cividis, Self::RdBu];
+
+    /// Of all maps, the shaders have room for this many, see `color_map.wgsl`.
+    pub const MAX_STOPS: usize = 11;
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Viridis => "viridis",
+            Self::Cividis => "cividis",
+            Self::RdBu => "RdBu",
+        }
+    }
+
+    /// sRGB colors evenly spaced along the map, sampled from matplotlib and ColorBrewer.
+    pub const fn stops(&self) -> &'static [[u8; 3]] {
+        match *self {
+            Self::Viridis => &[
+                [0x44, 0x01, 0x54],
+                [0x47, 0x2d, 0x7b],
+                [0x3b, 0x52, 0x8b],
+                [0x2c, 0x72, 0x8e],
+                [0x21, 0x91, 0x8c],
+                [0x28, 0xae, 0x80],
+                [0x5e, 0xc9, 0x62],
+                [0xad, 0xdc, 0x30],
+                [0xfd, 0xe7, 0x25],
+            ],
+            Self::Cividis => &[
+                [0x00, 0x20, 0x4d],
+                [0x00, 0x33, 0x6f],
+                [0x39, 0x48, 0x6b],
+                [0x57, 0x5c, 0x6d],
+                [0x70, 0x71, 0x73],
+                [0x8a, 0x87, 0x79],
+                [0xa6, 0x9d, 0x75],
+                [0xc4, 0xb5, 0x6c],
+                [0xe4, 0xcf, 0x5b],
+                [0xff, 0xea, 0x46],
+            ],
+            Self::RdBu => &[
+                [0x67, 0x00, 0x1f],
+                [0xb2, 0x18, 0x2b],
+                [0xd6, 0x60, 0x4d],
+                [0xf4, 0xa5, 0x82],
+                [0xfd, 0xdb, 0xc7],
+                [0xf7, 0xf7, 0xf7],
+                [0xd1, 0xe5, 0xf0],
+                [0x92, 0xc5, 0xde],
+                [0x43, 0x93, 0xc3],
+                [0x21, 0x66, 0xac],
+                [0x05, 0x30, 0x61],
+            ],
+        }
+    }
+
+    /// Linear interpolation between the stops around `position`, like `color_map_at` in `color_map.wgsl`.
+    pub fn at(&self, position: f32) -> Color32 {
+        let stops = self.stops();
+        let last = stops.len().saturating_sub(1);
+        let along = position.clamp(0.0, 1.0) * last as f32;
+        let lower = (along as usize).min(last.saturating_sub(1));
+        let weight = along - lower as f32;
+        let channel = |from: u8, to: u8| {
+            (f32::from(from) + (f32::from(to) - f32::from(from)) * weight).round() as u8
+        };
+        match (stops.get(lower), stops.get(lower + 1)) {
+            (Some(&[r0, g0, b0]), Some(&[r1, g1, b1])) => {
+                Color32::from_rgb(channel(r0, r1), channel(g0, g1), channel(b0, b1))
+            }
+            (Some(&[r, g, b]), None) => Color32::from_rgb(r, g, b),
+            _ => Color32::BLACK,
+        }
+    }
+}
+
+/// How values in [0, 1] are spread along the map.
+#[cfg_persistence_derive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    Linear,
+    /// The last [`Scaling::LOG_DECADES`] decades below 1, everything smaller gets the first color.
+    Log,
+    #[default]
+    Sqrt,
+}
+
+impl Scaling {
+    pub const VARIANTS: &'static [Self] = &[Self::Linear, Self::Log, Self::Sqrt];
+
+    pub const LOG_DECADES: f32 = 3.0;
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Linear => "linear",
+            Self::Log => "log",
+            Self::Sqrt => "sqrt",
+        }
+    }
+
+    /// Identifies the scaling in the shaders, see `color_map.wgsl`.
+    pub const fn shader_id(&self) -> u32 {
+        match *self {
+            Self::Linear => 0,
+            Self::Log => 1,
+            Self::Sqrt => 2,
+        }
+    }
+
+    /// Position of `value` along the map, see `color_map_position` in `color_map.wgsl`.
+    pub fn position(&self, value: f32) -> f32 {
+        let clamped = value.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => clamped,
+            Self::Log if clamped <= 0.0 => 0.0,
+            Self::Log => (1.0 + clamped.log10() / Self::LOG_DECADES).max(0.0),
+            Self::Sqrt => clamped.sqrt(),
+        }
+    }
+
+    /// Values worth labelling on the color bar, in [0, 1].
+    fn ticks(&self) -> Vec<f32> {
+        match *self {
+            Self::Linear | Self::Sqrt => vec![0.0, 0.25, 0.5, 0.75, 1.0],
+            Self::Log => (0..=Self::LOG_DECADES as i32)
+                .rev()
+                .map(|decade| 10f32.powi(-decade))
+                .collect(),
+        }
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorScale {
+    pub map: ColorMap,
+    pub scaling: Scaling,
+}
+
+impl ColorScale {
+    /// The densities are shown relative to the largest component, see `calc_gaussian_density`.
+    pub fn density_color(&self, density: f32) -> Color32 {
+        self.map.at(self.scaling.position(density))
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("map:");
+            for &map in ColorMap::VARIANTS {
+                ui.selectable_value(&mut self.map, map, map.display_name());
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("scaling:");
+            for &scaling in Scaling::VARIANTS {
+                ui.selectable_value(&mut self.scaling, scaling, scaling.display_name());
+            }
+        });
+    }
+}
+
+/// The color scales of the background displays, with a legend drawn over the canvas.
+#[cfg_persistence_derive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorSettings {
+    /// For the densities, from zero to the maximum.
+    pub density: ColorScale,
+    /// For the difference between the target and the estimate, zero being the middle of the map.
+    pub diff: ColorScale,
+    pub show_legend: bool,
+}
+
+impl Default for ColorSettings {
+    fn default() -> Self {
+        Self {
+            density: ColorScale {
+                map: ColorMap::Viridis,
+                scaling: Scaling::default(),
+            },
+            diff: ColorScale {
+                map: ColorMap::RdBu,
+                scaling: Scaling::default(),
+            },
+            show_legend: true,
+        }
+    }
+}
+
+impl ColorSettings {
+    const LEGEND_SIZE: Vec2 = Vec2::new(14.0, 160.0);
+    const LEGEND_MARGIN: f32 = 12.0;
+    /// Steps of the gradient, the maps are linear in between their stops anyway.
+    const LEGEND_STEPS: usize = 64;
+
+    pub const fn scale(&self, diff: bool) -> &ColorScale {
+        if diff { &self.diff } else { &self.density }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, diff: bool) {
+        if diff {
+            self.diff.ui(ui);
+        } else {
+            self.density.ui(ui);
+        }
+        ui.checkbox(&mut self.show_legend, "show color bar");
+    }
+
+    /// A vertical color bar in the top right corner of `rect`, with ticks at values of the scale.
+    pub fn paint_legend(&self, painter: &egui::Painter, rect: Rect, diff: bool) {
+        if !self.show_legend {
+            return;
+        }
+        let scale = self.scale(diff);
+        let bar = Rect::from_min_size(
+            Pos2::new(
+                rect.right() - Self::LEGEND_MARGIN - Self::LEGEND_SIZE.x,
+                rect.top() + Self::LEGEND_MARGIN,
+            ),
+            Self::LEGEND_SIZE,
+        );
+        // from the bottom of the bar to the top.
+        let y_at = |position: f32| bar.bottom() - position * bar.height();
+        let bar_position = |value: f32| {
+            if diff {
+                0.5 + 0.5 * value.signum() * scale.scaling.position(value.abs())
+            } else {
+                scale.scaling.position(value)
+            }
+        };
+
+        let font = FontId::monospace(10.0);
+        let text_color = painter.ctx().style().visuals.strong_text_color();
+        let ticks: Vec<(f32, String)> = scale
+            .scaling
+            .ticks()
+            .into_iter()
+            .flat_map(|tick| {
+                let mirrored = (diff && tick > 0.0).then_some(-tick);
+                std::iter::once(tick).chain(mirrored)
+            })
+            .map(|value| (y_at(bar_position(value)), format!("{value}")))
+            .collect();
+        let label_width = ticks
+            .iter()
+            .map(|&(_, ref label)| {
+                painter
+                    .layout_no_wrap(label.clone(), font.clone(), text_color)
+                    .size()
+                    .x
+            })
+            .fold(0.0, f32::max);
+        let title = if diff { "target - estimate" } else { "density" };
+        let background = Rect::from_min_max(
+            Pos2::new(bar.left() - label_width - 10.0, bar.top() - 18.0),
+            bar.max + Vec2::splat(6.0),
+        );
+        painter.rect_filled(
+            background,
+            4.0,
+            painter
+                .ctx()
+                .style()
+                .visuals
+                .extreme_bg_color
+                .gamma_multiply(0.8),
+        );
+        painter.text(
+            Pos2::new(bar.right(), bar.top() - 4.0),
+            Align2::RIGHT_BOTTOM,
+            title,
+            font.clone(),
+            text_color,
+        );
+
+        let mut gradient = Mesh::default();
+        for step in 0..=Self::LEGEND_STEPS {
+            let position = step as f32 / Self::LEGEND_STEPS as f32;
+            let color = scale.map.at(position);
+            let y = y_at(position);
+            gradient.colored_vertex(Pos2::new(bar.left(), y), color);
+            gradient.colored_vertex(Pos2::new(bar.right(), y), color);
+            if step > 0 {
+                let idx = 2 * step as u32;
+                gradient.add_triangle(idx - 2, idx - 1, idx);
+                gradient.add_triangle(idx - 1, idx, idx + 1);
+            }
+        }
+        painter.add(gradient);
+        painter.rect_stroke(
+            bar,
+            0.0,
+            Stroke::new(1.0, text_color),
+            egui::StrokeKind::Outside,
+        );
+        for (y, label) in ticks {
+            painter.line_segment(
+                [Pos2::new(bar.left() - 3.0, y), Pos2::new(bar.left(), y)],
+                Stroke::new(1.0, text_color),
+            );
+            painter.text(
+                Pos2::new(bar.left() - 5.0, y),
+                Align2::RIGHT_CENTER,
+                label,
+                font.clone(),
+                text_color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColorMap, Scaling};
+
+    #[test]
+    fn scalings_cover_the_map() {
+        for &scaling in Scaling::VARIANTS {
+            assert!(scaling.position(0.0).abs() < f32::EPSILON, "{scaling:?}");
+            assert!(
+                (scaling.position(1.0) - 1.0).abs() < f32::EPSILON,
+                "{scaling:?}"
+            );
+            let positions: Vec<f32> = (0..=100)
+                .map(|step| scaling.position(step as f32 / 100.0))
+                .collect();
+            assert!(
+                positions.windows(2).all(|pair| pair.first() <= pair.last()),
+                "{scaling:?} isn't monotonic"
+            );
+        }
+        assert!((Scaling::Log.position(0.01) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn maps_fit_the_shaders() {
+        for &map in ColorMap::VARIANTS {
+            assert!(map.stops().len() >= 2, "{map:?}");
+            assert!(map.stops().len() <= ColorMap::MAX_STOPS, "{map:?}");
+        }
+    }
+
+    #[test]
+    fn sequential_maps_get_lighter() {
+        for map in [ColorMap::Viridis, ColorMap::Cividis] {
+            let lightness: Vec<u32> = map
+                .stops()
+                .iter()
+                .map(|&[r, g, b]| u32::from(r) + u32::from(g) + u32::from(b))
+                .collect();
+            assert!(
+                lightness.windows(2).all(|pair| pair.first() < pair.last()),
+                "{map:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn interpolation_hits_the_stops() {
+        for &map in ColorMap::VARIANTS {
+            let stops = map.stops();
+            let last = (stops.len() - 1) as f32;
+            for (idx, &[r, g, b]) in stops.iter().enumerate() {
+                assert_eq!(
+                    map.at(idx as f32 / last),
+                    egui::Color32::from_rgb(r, g, b),
+                    "{map:?} stop {idx}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use egui::{Align2, Color32, FontId, Pos2, Rect, Shape, Stroke};
+use macros::cfg_persistence_derive;
+
+use crate::{
+    contours::{hdr_levels, iso_lines},
+    kde::Grid,
+    simulation::random_walk_metropolis_hastings::AlgoVec,
+    target_distr::{CpuTarget, Density, Gaussian},
+    visualizations::View,
+};
+
+/// Iso-density lines of the target, drawn over whichever background is shown.
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+pub struct ContourSettings {
+    /// The other settings are kept while the lines are hidden.
+    #[serde(default)]
+    pub shown: bool,
+    /// Fractions of the target's mass, each line encloses the highest density region holding one.
+    pub masses: Vec<f64>,
+    pub color: Color32,
+    pub width: f32,
+}
+
+impl Default for ContourSettings {
+    fn default() -> Self {
+        Self {
+            shown: false,
+            masses: vec![0.5, 0.9, 0.99],
+            color: Color32::WHITE,
+            width: 1.5,
+        }
+    }
+}
+
+impl ContourSettings {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        for (idx, mass) in self.masses.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let mut percent = *mass * 100.0;
+                ui.add(
+                    egui::DragValue::new(&mut percent)
+                        .range(0.1..=99.9)
+                        .speed(0.1)
+                        .suffix("%"),
+                );
+                *mass = percent / 100.0;
+                if ui.button("remove").clicked() {
+                    removed = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = removed {
+            self.masses.remove(idx);
+        }
+        if ui.button("add level").clicked() {
+            self.masses.push(0.75);
+        }
+        ui.horizontal(|ui| {
+            ui.label("line color");
+            ui.color_edit_button_srgba(&mut self.color);
+        });
+        ui.add(egui::Slider::new(&mut self.width, 0.5..=4.0).text("line width"));
+    }
+}
+
+/// What the lines were traced for.
+#[derive(PartialEq)]
+struct Traced {
+    target: Gaussian,
+    cpu_target: Option<CpuTarget>,
+    masses: Vec<f64>,
+}
+
+/// The lines of [`ContourSettings`], traced by marching squares over the density of the target.
+///
+/// The levels only depend on the target, so the lines are traced over all of it rather than the part on the canvas.
+pub struct ContourLines {
+    traced: Traced,
+    /// In NDC, together with the mass each encloses.
+    lines: Vec<(f64, Vec<Pos2>)>,
+}
+
+impl ContourLines {
+    /// Grid cells along each side of the extent.
+    const RESOLUTION: u32 = 200;
+
+    /// `extent` is where the target has practically all of its mass, as the view fitted to it.
+    pub fn new(
+        target: &Gaussian,
+        cpu_target: Option<&CpuTarget>,
+        extent: &View,
+        masses: &[f64],
+    ) -> Self {
+        let cell_size = 2.0 * extent.half_extent / Self::RESOLUTION as f32;
+        let [center_x, center_y] = extent.center;
+        let grid = Grid {
+            origin: [center_x, center_y]
+                .map(|center| center - extent.half_extent + cell_size / 2.0),
+            cell_size,
+            width: Self::RESOLUTION,
+            height: Self::RESOLUTION,
+        };
+        let (width, height) = (grid.width, grid.height);
+        let [origin_x, origin_y] = grid.origin;
+        let log_densities: Vec<f64> = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let position = AlgoVec::new(
+                    (col as f32).mul_add(grid.cell_size, origin_x),
+                    (row as f32).mul_add(grid.cell_size, origin_y),
+                );
+                match cpu_target {
+                    Some(cpu_target) => cpu_target.canvas_log_density(position),
+                    None => f64::from(target.calculate_probability_density(position)).ln(),
+                }
+            })
+            .collect();
+        let max_log_density = log_densities
+            .iter()
+            .copied()
+            .filter(|val| val.is_finite())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let densities: Vec<f64> = log_densities
+            .into_iter()
+            .map(|log_density| (log_density - max_log_density).exp())
+            .collect();
+
+        let lines = masses
+            .iter()
+            .zip(hdr_levels(&densities, masses))
+            .flat_map(|(&mass, level)| {
+                iso_lines(&densities, &grid, level)
+                    .into_iter()
+                    .map(move |line| (mass, line.into_iter().map(Pos2::from).collect()))
+            })
+            .collect();
+        Self {
+            traced: Traced {
+                target: target.clone(),
+                cpu_target: cpu_target.cloned(),
+                masses: masses.to_vec(),
+            },
+            lines,
+        }
+    }
+
+    pub fn is_current(
+        &self,
+        target: &Gaussian,
+        cpu_target: Option<&CpuTarget>,
+        masses: &[f64],
+    ) -> bool {
+        let traced = &self.traced;
+        traced.target == *target
+            && traced.cpu_target.as_ref() == cpu_target
+            && traced.masses == masses
+    }
+
+    /// Each line is labelled with its mass at its topmost point.
+    pub fn paint(
+        &self,
+        painter: &egui::Painter,
+        rect: Rect,
+        view: &View,
+        settings: &ContourSettings,
+    ) {
+        let stroke = Stroke::new(settings.width, settings.color);
+        let font = FontId::proportional(10.0);
+        for &(mass, ref line) in &self.lines {
+            let points: Vec<Pos2> = line
+                .iter()
+                .map(|&ndc| view.ndc_to_canvas_coord(ndc, rect.size()))
+                .collect();
+            if let Some(&top) = points
+                .iter()
+                .filter(|&&point| rect.contains(point))
+                .min_by(|lhs, rhs| lhs.y.total_cmp(&rhs.y))
+            {
+                painter.text(
+                    top,
+                    Align2::CENTER_BOTTOM,
+                    percent_label(mass),
+                    font.clone(),
+                    settings.color,
+                );
+            }
+            painter.add(Shape::line(points, stroke));
+        }
+    }
+}
+
+/// Without trailing zeros, e.g. `50%` or `99.5%`.
+fn percent_label(mass: f64) -> String {
+    let percent = format!("{:.1}", mass * 100.0);
+    format!("{}%", percent.trim_end_matches('0').trim_end_matches('.'))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ContourLines, ContourSettings, percent_label};
+    use crate::visualizations::View;
+
+    #[test]
+    fn traces_every_level_of_the_default_target() {
+        let masses = ContourSettings::default().masses;
+        let extent = View {
+            center: [0.0; 2],
+            half_extent: 2.0,
+        };
+        let contours = ContourLines::new(&Default::default(), None, &extent, &masses);
+        for mass in masses {
+            assert!(
+                contours
+                    .lines
+                    .iter()
+                    .any(|&(traced, ref line)| (traced - mass).abs() < f64::EPSILON
+                        && line.len() > 2),
+                "{mass}"
+            );
+        }
+    }
+
+    #[test]
+    fn labels_drop_trailing_zeros() {
+        assert_eq!(percent_label(0.5), "50%");
+        assert_eq!(percent_label(0.995), "99.5%");
+        assert_eq!(percent_label(0.1), "10%");
+    }
+}
@@ -0,0 +1,95 @@
+use egui::{Color32, Pos2, Rect};
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::AlgoVec,
+    target_distr::CpuTarget,
+    visualizations::{CanvasPainter, ColorScale, View},
+};
+
+/// The density of a [`CpuTarget`], evaluated on the CPU.
+///
+/// The shader based displays only know about Gaussian mixtures, and these targets change rarely enough that a texture suffices.
+pub struct DensityImage {
+    target: CpuTarget,
+    canvas_size: egui::Vec2,
+    view: View,
+    colors: ColorScale,
+    texture: egui::TextureHandle,
+}
+
+impl DensityImage {
+    /// Texels along the longer side of the canvas.
+    const RESOLUTION: f32 = 160.0;
+
+    pub fn new(
+        ctx: &egui::Context,
+        target: &CpuTarget,
+        rect: Rect,
+        view: &View,
+        colors: &ColorScale,
+    ) -> Self {
+        let canvas_size = rect.size();
+        let texel_size = canvas_size.max_elem() / Self::RESOLUTION;
+        let [width, height] =
+            [canvas_size.x, canvas_size.y].map(|side| ((side / texel_size).ceil() as usize).max(1));
+        let log_densities: Vec<f64> = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let canvas_coord = Pos2::new(
+                    (col as f32 + 0.5) / width as f32 * canvas_size.x,
+                    (row as f32 + 0.5) / height as f32 * canvas_size.y,
+                );
+                let ndc = view.canvas_coord_to_ndc(canvas_coord, canvas_size);
+                target.canvas_log_density(AlgoVec::new(ndc.x, ndc.y))
+            })
+            .collect();
+        let max_log_density = log_densities
+            .iter()
+            .copied()
+            .filter(|val| val.is_finite())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let pixels = log_densities
+            .into_iter()
+            .map(|log_density| {
+                // relative to the maximum, like the shader based target display.
+                colors.density_color((log_density - max_log_density).exp() as f32)
+            })
+            .collect();
+        let texture = ctx.load_texture(
+            "cpu target density",
+            egui::ColorImage::new([width, height], pixels),
+            egui::TextureOptions::LINEAR,
+        );
+        Self {
+            target: target.clone(),
+            canvas_size,
+            view: *view,
+            colors: *colors,
+            texture,
+        }
+    }
+
+    pub fn is_current(
+        &self,
+        target: &CpuTarget,
+        rect: Rect,
+        view: &View,
+        colors: &ColorScale,
+    ) -> bool {
+        self.target == *target
+            && self.canvas_size == rect.size()
+            && self.view == *view
+            && self.colors == *colors
+    }
+}
+
+impl CanvasPainter for DensityImage {
+    fn paint(&self, painter: &egui::Painter, rect: egui::Rect) {
+        painter.image(
+            self.texture.id(),
+            rect,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            Color32::WHITE,
+        );
+    }
+}
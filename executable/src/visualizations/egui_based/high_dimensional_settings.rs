@@ -0,0 +1,109 @@
+use egui::Ui;
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::Rwmh,
+    target_distr::{HighDimModel, HighDimensional},
+};
+
+pub struct HighDimEdit;
+
+impl HighDimEdit {
+    pub fn settings_ui(target: &mut HighDimensional, ui: &mut Ui) {
+        let HighDimensional {
+            ref mut model,
+            ref mut projection,
+        } = *target;
+        ui.horizontal(|ui| {
+            for variant in HighDimModel::variants(model.dim()) {
+                let selected = model.display_name() == variant.display_name();
+                if ui
+                    .selectable_label(selected, variant.display_name())
+                    .clicked()
+                    && !selected
+                {
+                    *model = variant;
+                }
+            }
+        });
+        match *model {
+            HighDimModel::CorrelatedGaussian {
+                ref mut dim,
+                ref mut sd,
+                ref mut correlation,
+            } => {
+                ui.add(egui::Slider::new(dim, 2..=HighDimModel::MAX_DIM).text("dimensions"));
+                ui.add(egui::Slider::new(sd, 0.01..=1.0).text("standard deviation"));
+                let min_correlation = HighDimModel::min_correlation(*dim);
+                // stay away from the limits, the covariance matrix is singular there.
+                *correlation = correlation.clamp(min_correlation * 0.99, 0.99);
+                ui.add(
+                    egui::Slider::new(correlation, (min_correlation * 0.99)..=0.99)
+                        .text("correlation"),
+                );
+            }
+            HighDimModel::Funnel {
+                ref mut dim,
+                ref mut log_scale_sd,
+                ref mut base_sd,
+            } => {
+                ui.add(egui::Slider::new(dim, 2..=HighDimModel::MAX_DIM).text("dimensions"));
+                ui.add(egui::Slider::new(log_scale_sd, 0.05..=1.0).text("sd of the log scale"));
+                ui.add(egui::Slider::new(base_sd, 0.01..=1.0).text("base sd"));
+            }
+        }
+        let dim = model.dim();
+        ui.label("shown coordinates:");
+        ui.horizontal(|ui| {
+            let [ref mut x_axis, ref mut y_axis] = *projection;
+            *x_axis = (*x_axis).min(dim - 1);
+            *y_axis = (*y_axis).min(dim - 1);
+            axis_selection(x_axis, *y_axis, dim, "x axis", ui);
+            axis_selection(y_axis, *x_axis, dim, "y axis", ui);
+            if x_axis == y_axis {
+                *y_axis = (*x_axis + 1) % dim;
+            }
+        });
+        if matches!(*model, HighDimModel::Funnel { .. }) {
+            ui.label("Coordinate 0 is the log scale of all others.");
+        }
+    }
+
+    /// Shows the full current position of the chain, which the canvas only shows 2 coordinates of.
+    pub fn state_ui(target: &HighDimensional, algo: &Rwmh, ui: &mut Ui) {
+        let accepted = algo.history.len() - 1;
+        let steps = accepted + algo.rejected_history.len();
+        if steps > 0 {
+            ui.label(format!(
+                "acceptance rate: {:.3}\n(about 0.234 is optimal in high dimensions)",
+                accepted as f32 / steps as f32
+            ));
+        }
+        ui.label("current position:");
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("current full position")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (coordinate, val) in algo.current_full.iter().enumerate() {
+                        let label = format!("{coordinate}");
+                        if target.projection.contains(&coordinate) {
+                            ui.strong(label);
+                        } else {
+                            ui.label(label);
+                        }
+                        ui.label(format!("{val:.4}"));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+fn axis_selection(axis: &mut usize, other_axis: usize, dim: usize, label: &str, ui: &mut Ui) {
+    egui::ComboBox::from_label(label)
+        .selected_text(format!("{axis}"))
+        .show_ui(ui, |ui| {
+            for coordinate in (0..dim).filter(|&coordinate| coordinate != other_axis) {
+                ui.selectable_value(axis, coordinate, format!("{coordinate}"));
+            }
+        });
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::simulation::random_walk_metropolis_hastings::{HistoryIndex, Rwmh};
+
+/// Replays the chain up to some iteration, limiting all displays and statistics to it.
+pub struct HistoryScrubber {
+    /// Shown steps, following the end of the chain while it is there.
+    steps: u32,
+    /// The chain [`Self::index`] and [`Self::shown`] were built from.
+    source: Arc<Rwmh>,
+    index: HistoryIndex,
+    shown: Arc<Rwmh>,
+}
+
+impl HistoryScrubber {
+    pub fn new(algo: &Arc<Rwmh>) -> Self {
+        Self {
+            steps: algo.step_count(),
+            source: algo.clone(),
+            index: HistoryIndex::new(algo),
+            shown: algo.clone(),
+        }
+    }
+
+    /// The chain truncated to the selected iteration.
+    ///
+    /// Rebuilds the index whenever `algo` isn't the chain from last time, staying at the end if it was there.
+    pub fn shown(&mut self, algo: &Arc<Rwmh>) -> Arc<Rwmh> {
+        if !Arc::ptr_eq(&self.source, algo) {
+            let at_end = self.steps >= self.source.step_count();
+            let steps = self.steps;
+            *self = Self::new(algo);
+            if !at_end {
+                self.select(steps);
+            }
+        }
+        self.shown.clone()
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut steps = self.steps;
+        ui.add(
+            egui::Slider::new(&mut steps, 0..=self.source.step_count())
+                .text("iterations")
+                .clamping(egui::SliderClamping::Always),
+        );
+        if steps != self.steps {
+            self.select(steps);
+        }
+    }
+
+    fn select(&mut self, steps: u32) {
+        self.steps = steps.min(self.source.step_count());
+        self.shown = if self.steps == self.source.step_count() {
+            self.source.clone()
+        } else {
+            Arc::new(self.source.truncated(&self.index, self.steps))
+        };
+    }
+}
@@ -0,0 +1,94 @@
+use std::cell::Cell;
+
+use macros::cfg_persistence_derive;
+
+use crate::{
+    kde::{Bandwidth, Kernel},
+    simulation::random_walk_metropolis_hastings::Rwmh,
+    visualizations::KdeParams,
+};
+
+/// Used by the bandwidth rules as long as the chain has no spread.
+const FALLBACK_BANDWIDTH: f32 = 0.1;
+
+/// The kernel density estimate shown by the BDA displays.
+#[cfg_persistence_derive]
+#[derive(Default)]
+pub struct KdeSettings {
+    pub kernel: Kernel,
+    pub bandwidth: Bandwidth,
+    /// The rules need a pass over the chain, so they are only applied again once it changed.
+    #[serde(skip)]
+    resolved: Cell<Option<Resolved>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Resolved {
+    rule: Bandwidth,
+    history_len: usize,
+    step_count: u32,
+    bandwidth: f32,
+}
+
+impl KdeSettings {
+    pub fn params(&self, algo: &Rwmh) -> KdeParams {
+        let (history_len, step_count) = (algo.history.len(), algo.step_count());
+        let bandwidth = match self.resolved.get() {
+            Some(resolved)
+                if resolved.rule == self.bandwidth
+                    && resolved.history_len == history_len
+                    && resolved.step_count == step_count =>
+            {
+                resolved.bandwidth
+            }
+            _ => {
+                let bandwidth = self.bandwidth.resolve(algo, FALLBACK_BANDWIDTH);
+                self.resolved.set(Some(Resolved {
+                    rule: self.bandwidth,
+                    history_len,
+                    step_count,
+                    bandwidth,
+                }));
+                bandwidth
+            }
+        };
+        KdeParams {
+            kernel: self.kernel,
+            bandwidth,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, algo: &Rwmh) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("kernel:");
+            for &kernel in Kernel::VARIANTS {
+                ui.selectable_value(&mut self.kernel, kernel, kernel.display_name());
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("bandwidth:");
+            for &rule in Bandwidth::VARIANTS {
+                let selected = self.bandwidth.display_name() == rule.display_name();
+                if ui.selectable_label(selected, rule.display_name()).clicked() && !selected {
+                    self.bandwidth = match rule {
+                        // starting from what the rule chose.
+                        Bandwidth::Fixed(_) => Bandwidth::Fixed(self.params(algo).bandwidth),
+                        Bandwidth::Scott | Bandwidth::Silverman => rule,
+                    };
+                }
+            }
+        });
+        if let Bandwidth::Fixed(ref mut bandwidth) = self.bandwidth {
+            ui.add(
+                egui::Slider::new(bandwidth, 1e-3..=1.0)
+                    .logarithmic(true)
+                    .clamping(egui::SliderClamping::Never)
+                    .text("bandwidth"),
+            );
+            *bandwidth = bandwidth.max(f32::EPSILON);
+        } else {
+            ui.label(format!("bandwidth {:.4}", self.params(algo).bandwidth))
+                .on_hover_text("The standard deviation of each coordinate, for all kernels.");
+        }
+    }
+}
@@ -1,7 +1,37 @@
+mod chain_export;
+#[cfg(feature = "persistence")]
+mod checkpoint_file;
+mod color_settings;
+mod contour_overlay;
+mod density_image;
+mod high_dimensional_settings;
+mod history_scrubber;
+mod kde_settings;
 mod point_display;
+mod posterior_display;
+mod rng_settings;
+mod sample_import;
+mod scenario_loader;
+mod step_animation;
+mod support_settings;
 mod target_distrib_settings;
 
+pub use chain_export::ChainExport;
+#[cfg(feature = "persistence")]
+pub use checkpoint_file::CheckpointFile;
+pub use color_settings::{ColorMap, ColorScale, ColorSettings, Scaling};
+pub use contour_overlay::{ContourLines, ContourSettings};
+pub use density_image::DensityImage;
+pub use high_dimensional_settings::HighDimEdit;
+pub use history_scrubber::HistoryScrubber;
+pub use kde_settings::KdeSettings;
 pub use point_display::SamplePointVisualizer;
+pub use posterior_display::{CsvLoader, PosteriorEdit};
+pub use rng_settings::RngSettingsUi;
+pub use sample_import::SampleImport;
+pub use scenario_loader::ScenarioLoader;
+pub use step_animation::StepAnimation;
+pub use support_settings::SupportEdit;
 pub use target_distrib_settings::{DistrEdit, ElementSettings};
 
 use egui::{
@@ -11,6 +41,20 @@ use egui::{
 
 use super::CanvasPainter;
 
+/// Whether the name or path of a dropped file ends in `.{extension}`, ignoring case.
+fn has_extension(file: &egui::DroppedFile, extension: &str) -> bool {
+    let matches = |name: &str| {
+        std::path::Path::new(name)
+            .extension()
+            .is_some_and(|found| found.eq_ignore_ascii_case(extension))
+    };
+    matches(&file.name)
+        || file
+            .path
+            .as_ref()
+            .is_some_and(|path| matches(&path.to_string_lossy()))
+}
+
 /// In contrast to the egui arrow, this arrow has an arrow head of constant size.
 /// Note that the head will be added on top of start + direction, otherwise drawing an arrow of zero length is kinda awkward.
 pub struct Arrow {
@@ -2,9 +2,8 @@ use egui::{Color32, Pos2};
 use macros::cfg_persistence_derive;
 
 use crate::{
-    app::ndc_to_canvas_coord,
     simulation::random_walk_metropolis_hastings::{AcceptRecord, Rwmh},
-    visualizations::{self, CanvasPainter},
+    visualizations::View,
 };
 
 #[cfg_persistence_derive]
@@ -27,7 +26,7 @@ impl Default for SamplePointVisualizer {
 }
 
 impl SamplePointVisualizer {
-    pub fn paint(&self, painter: &egui::Painter, rect: egui::Rect, algo: &Rwmh) {
+    pub fn paint(&self, painter: &egui::Painter, rect: egui::Rect, algo: &Rwmh, view: &View) {
         for &AcceptRecord {
             position,
             remain_count,
@@ -35,7 +34,7 @@ impl SamplePointVisualizer {
         } in algo.history.iter().skip(1)
         // skipping the first empty element I added to avoid WebGPU bind exceptions (see shader for explanation!)
         {
-            let canvas_loc = ndc_to_canvas_coord(Pos2::new(position[0], position[1]), rect.size());
+            let canvas_loc = view.ndc_to_canvas_coord(position.into(), rect.size());
             let normalized_lifespan =
                 (remain_count + 1) as f32 / (algo.max_remain_count + 1) as f32;
             // with the above there may be a point where most accepted points are very close to 0, this seeks to always have them above a certain threshold.
@@ -49,7 +48,7 @@ impl SamplePointVisualizer {
         }
         if let Some(color) = self.rejected_point_color {
             for step in algo.rejected_history.iter() {
-                let step = ndc_to_canvas_coord(Pos2::new(step.x, step.y), rect.size());
+                let step = view.ndc_to_canvas_coord(Pos2::new(step.x, step.y), rect.size());
                 painter.circle_filled(
                     step,
                     self.point_radius,
@@ -57,13 +56,5 @@ impl SamplePointVisualizer {
                 );
             }
         }
-        #[expect(unused, reason = "I want this to compile")]
-        if false {
-            todo!();
-            let current_spot: Pos2 = [300.0, 400.0].into();
-            visualizations::Arrow::new(current_spot, [100.0, 100.0]).paint(painter, rect);
-            visualizations::PredictionVariance::new(current_spot, 200.0).paint(painter, rect);
-            visualizations::SamplingPoint::new(current_spot, 0.65).paint(painter, rect);
-        }
     }
 }
@@ -0,0 +1,313 @@
+use egui::{Color32, Pos2, Rect, Stroke, Ui};
+
+use super::scenario_loader::is_scenario_file;
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::AlgoVec,
+    target_distr::{Dataset, Model, Posterior, Prior},
+};
+
+pub struct PosteriorEdit;
+
+impl PosteriorEdit {
+    pub fn settings_ui(posterior: &mut Posterior, ui: &mut Ui) {
+        let Posterior {
+            ref data,
+            ref mut model,
+            ref mut priors,
+            ..
+        } = *posterior;
+        ui.label(format!(
+            "{}: {} rows, {} columns",
+            data.name,
+            data.row_count(),
+            data.columns.len()
+        ));
+        ui.horizontal(|ui| {
+            ui.label("model:");
+            let regression = Model::LinearRegression {
+                x_column: 0,
+                y_column: 1.min(data.columns.len().saturating_sub(1)),
+                noise_sd: 1.0,
+            };
+            for variant in [regression, Model::Normal { column: 0 }] {
+                let selected = model.display_name() == variant.display_name();
+                if ui
+                    .selectable_label(selected, variant.display_name())
+                    .clicked()
+                    && !selected
+                {
+                    *model = variant;
+                }
+            }
+        });
+        match *model {
+            Model::LinearRegression {
+                ref mut x_column,
+                ref mut y_column,
+                ref mut noise_sd,
+            } => {
+                column_selection(data, x_column, "x", ui);
+                column_selection(data, y_column, "y", ui);
+                ui.add(
+                    egui::Slider::new(noise_sd, 0.01..=10.0)
+                        .logarithmic(true)
+                        .text("known noise sd"),
+                );
+            }
+            Model::Normal { ref mut column } => {
+                column_selection(data, column, "data", ui);
+            }
+        }
+        for (prior, name) in priors.iter_mut().zip(model.parameter_names()) {
+            ui.horizontal(|ui| {
+                ui.label(format!("prior of {name}:"));
+                for variant in [Prior::Flat, Prior::Normal { mean: 0.0, sd: 1.0 }] {
+                    let selected = prior.display_name() == variant.display_name();
+                    if ui
+                        .selectable_label(selected, variant.display_name())
+                        .clicked()
+                        && !selected
+                    {
+                        *prior = variant;
+                    }
+                }
+                if let Prior::Normal {
+                    ref mut mean,
+                    ref mut sd,
+                } = *prior
+                {
+                    ui.add(egui::DragValue::new(mean).speed(0.05).prefix("mean "));
+                    ui.add(
+                        egui::DragValue::new(sd)
+                            .speed(0.05)
+                            .range(0.001..=f32::MAX)
+                            .prefix("sd "),
+                    );
+                }
+            });
+        }
+        ui.label("visible parameter window:");
+        let names = posterior.model.parameter_names();
+        for (axis, name) in names.into_iter().enumerate() {
+            let (Some(center), Some(extent)) = (
+                posterior.center.get_mut(axis),
+                posterior.half_extent.get_mut(axis),
+            ) else {
+                unreachable!("2 parameters")
+            };
+            ui.horizontal(|ui| {
+                ui.label(name);
+                ui.add(egui::DragValue::new(center).speed(0.01).prefix("center "));
+                ui.add(
+                    egui::DragValue::new(extent)
+                        .speed(0.01)
+                        .range(1e-6..=f32::MAX)
+                        .prefix("± "),
+                );
+            });
+        }
+        if ui.button("fit window to data").clicked() {
+            posterior.fit_view();
+        }
+    }
+
+    /// Shows the data, together with the model at the given position of the sampler.
+    pub fn data_fit_ui(posterior: &Posterior, current: AlgoVec, ui: &mut Ui) {
+        let [first, second] = posterior.to_parameters(current);
+        let [first_name, second_name] = posterior.model.parameter_names();
+        ui.label(format!(
+            "{first_name} = {first:.4}\n{second_name} = {second:.4}"
+        ));
+        let size = egui::Vec2::splat(ui.available_width());
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let plot_rect = rect.shrink(8.0);
+        const FIT_STROKE: Stroke = Stroke {
+            width: 2.0,
+            color: Color32::RED,
+        };
+        match posterior.model {
+            Model::LinearRegression {
+                x_column, y_column, ..
+            } => {
+                let xs = posterior.data.column(x_column);
+                let ys = posterior.data.column(y_column);
+                let (Some(x_range), Some(y_range)) = (value_range(xs), value_range(ys)) else {
+                    return;
+                };
+                let to_screen = |x: f32, y: f32| {
+                    Pos2::new(
+                        egui::remap(x, x_range.clone(), plot_rect.x_range()),
+                        egui::remap(y, y_range.clone(), plot_rect.bottom_up_range()),
+                    )
+                };
+                for (&x, &y) in xs.iter().zip(ys) {
+                    painter.circle_filled(to_screen(x, y), 2.5, Color32::LIGHT_GRAY);
+                }
+                let line_at = |x: f32| to_screen(x, first + second * x);
+                painter.line_segment(
+                    [line_at(*x_range.start()), line_at(*x_range.end())],
+                    FIT_STROKE,
+                );
+            }
+            Model::Normal { column } => {
+                const BINS: usize = 20;
+                let vals = posterior.data.column(column);
+                let Some(range) = value_range(vals) else {
+                    return;
+                };
+                let bin_width = (range.end() - range.start()) / BINS as f32;
+                let mut counts = [0usize; BINS];
+                for &val in vals {
+                    let bin = (((val - range.start()) / bin_width) as usize).min(BINS - 1);
+                    if let Some(count) = counts.get_mut(bin) {
+                        *count += 1;
+                    }
+                }
+                let to_density = |count: usize| count as f32 / (vals.len() as f32 * bin_width);
+                let max_height = counts.iter().copied().map(to_density).fold(0.0, f32::max) * 1.2;
+                let to_screen = |val: f32, density: f32| {
+                    Pos2::new(
+                        egui::remap(val, range.clone(), plot_rect.x_range()),
+                        egui::remap(density, 0.0..=max_height, plot_rect.bottom_up_range()),
+                    )
+                };
+                for (bin, &count) in counts.iter().enumerate() {
+                    let start = range.start() + bin as f32 * bin_width;
+                    painter.rect_filled(
+                        Rect::from_two_pos(
+                            to_screen(start, 0.0),
+                            to_screen(start + bin_width, to_density(count)),
+                        ),
+                        0.0,
+                        Color32::GRAY,
+                    );
+                }
+                let sd = second.exp();
+                const CURVE_POINTS: usize = 100;
+                let curve = (0..=CURVE_POINTS)
+                    .map(|idx| {
+                        let val = egui::lerp(range.clone(), idx as f32 / CURVE_POINTS as f32);
+                        let density = (-0.5 * ((val - first) / sd).powi(2)).exp()
+                            / (sd * std::f32::consts::TAU.sqrt());
+                        to_screen(val, density)
+                    })
+                    .collect();
+                painter.add(egui::Shape::line(curve, FIT_STROKE));
+            }
+        }
+    }
+}
+
+fn column_selection(data: &Dataset, column: &mut usize, label: &str, ui: &mut Ui) {
+    egui::ComboBox::from_label(label)
+        .selected_text(data.headers.get(*column).map_or("-", String::as_str))
+        .show_ui(ui, |ui| {
+            for (idx, header) in data.headers.iter().enumerate() {
+                ui.selectable_value(column, idx, header);
+            }
+        });
+}
+
+/// Padded a bit, so that no point sits on the edge.
+fn value_range(vals: &[f32]) -> Option<std::ops::RangeInclusive<f32>> {
+    let (min, max) = vals.iter().copied().filter(|val| val.is_finite()).fold(
+        None,
+        |acc: Option<(f32, f32)>, val| {
+            Some(acc.map_or((val, val), |(min, max)| (min.min(val), max.max(val))))
+        },
+    )?;
+    let padding = ((max - min) * 0.05).max(f32::EPSILON);
+    Some((min - padding)..=(max + padding))
+}
+
+/// Loads a [`Dataset`] from a path or from files dropped onto the window.
+#[derive(Default)]
+pub struct CsvLoader {
+    path: String,
+    last_error: Option<String>,
+}
+
+impl CsvLoader {
+    /// Returns a dataset once one was loaded successfully.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Dataset> {
+        #[cfg_attr(
+            target_arch = "wasm32",
+            expect(unused_mut, reason = "only loaded by dropping")
+        )]
+        let mut loaded = None;
+        ui.label("Drop a CSV file onto the window to load it.");
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+            if ui.button("load").clicked() {
+                loaded = self.handle(load_from_path(std::path::Path::new(&self.path)));
+            }
+        });
+        if let Some(ref error) = self.last_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        loaded
+    }
+
+    /// Returns the last dataset that was dropped onto the window this frame and could be loaded.
+    pub fn dropped(&mut self, ctx: &egui::Context) -> Option<Dataset> {
+        let mut loaded = None;
+        for file in ctx.input(|input| input.raw.dropped_files.clone()) {
+            if is_scenario_file(&file) {
+                continue;
+            }
+            #[cfg(feature = "persistence")]
+            if super::checkpoint_file::is_checkpoint_file(&file) {
+                continue;
+            }
+            let result = if let Some(bytes) = file.bytes {
+                parse_bytes(&file.name, &bytes)
+            } else if let Some(path) = file.path {
+                load_from_path(&path)
+            } else {
+                continue;
+            };
+            loaded = self.handle(result).or(loaded);
+        }
+        loaded
+    }
+
+    fn handle(&mut self, result: Result<Dataset, String>) -> Option<Dataset> {
+        match result {
+            Ok(data) => {
+                self.last_error = None;
+                Some(data)
+            }
+            Err(error) => {
+                self.last_error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+fn parse_bytes(name: &str, bytes: &[u8]) -> Result<Dataset, String> {
+    let text = std::str::from_utf8(bytes).map_err(|err| format!("{name}: {err}"))?;
+    Dataset::parse_csv(name, text).map_err(|err| format!("{name}: {err}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_from_path(path: &std::path::Path) -> Result<Dataset, String> {
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let bytes = std::fs::read(path).map_err(|err| format!("{name}: {err}"))?;
+    parse_bytes(&name, &bytes)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_from_path(path: &std::path::Path) -> Result<Dataset, String> {
+    Err(format!(
+        "{}: no file system access in the browser",
+        path.display()
+    ))
+}
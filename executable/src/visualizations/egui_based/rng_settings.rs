@@ -0,0 +1,62 @@
+use egui::Slider;
+
+use crate::{
+    helpers::TempStateDataAccess,
+    simulation::{DEFAULT_SEED, WrappedRng, WrappedRngDiscriminants},
+};
+
+/// The RNGs live in the GUI-free `sampling` crate, so their UI is attached via these traits.
+pub trait RngSelectionUi {
+    fn selection_ui(&mut self, ui: &mut egui::Ui);
+}
+
+pub trait RngSettingsUi {
+    /// Returns the seed if the RNG was reseeded.
+    fn settings_ui(&mut self, ui: &mut egui::Ui, id: egui::Id) -> Option<u64>;
+}
+
+impl RngSelectionUi for WrappedRngDiscriminants {
+    fn selection_ui(&mut self, ui: &mut egui::Ui) {
+        for ele in Self::VARIANTS.iter() {
+            ui.selectable_value(self, *ele, ele.display_name())
+                .on_hover_text(ele.explanation());
+        }
+    }
+}
+
+impl RngSettingsUi for WrappedRng {
+    fn settings_ui(&mut self, ui: &mut egui::Ui, id: egui::Id) -> Option<u64> {
+        #[derive(Clone, Copy)]
+        struct Settings {
+            discr: WrappedRngDiscriminants,
+            seed: u64,
+        }
+
+        let current_settings = ui.temp_ui_state::<Settings>().with_id(id).get();
+
+        if let Some(mut current_settings) = current_settings {
+            current_settings.discr.selection_ui(ui);
+            // If I set this to u64::MAX to provide all options, its not realistically possible to select many values.
+            ui.add(Slider::new(&mut current_settings.seed, 0..=300).text("Seed"));
+
+            if ui.button("apply").clicked() {
+                *self = current_settings.discr.seed_from_u64(current_settings.seed);
+                ui.temp_ui_state::<Settings>().with_id(id).remove();
+                return Some(current_settings.seed);
+            }
+            ui.temp_ui_state::<Settings>()
+                .with_id(id)
+                .set_or_create(current_settings);
+        } else {
+            let current_rng_setting = WrappedRngDiscriminants::from(&*self);
+            ui.label("Current RNG:");
+            if ui.button(format!("{current_rng_setting}")).clicked() {
+                ui.temp_ui_state::<Settings>().with_id(id).create(Settings {
+                    discr: current_rng_setting,
+                    seed: DEFAULT_SEED,
+                });
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,43 @@
+use egui::Ui;
+
+use crate::{
+    external_samples::chain_from_dataset, simulation::random_walk_metropolis_hastings::Rwmh,
+    target_distr::Dataset,
+};
+
+use super::CsvLoader;
+
+/// Loads samples produced elsewhere as a read-only chain, see [`crate::external_samples`].
+#[derive(Default)]
+pub struct SampleImport {
+    loader: CsvLoader,
+    /// On the web dropping is the only way to load a file,
+    /// so this decides whether dropped CSV files are samples or data for a posterior.
+    pub takes_drops: bool,
+    last_error: Option<String>,
+}
+
+impl SampleImport {
+    /// Returns the name of the file and the imported chain, once one was loaded successfully.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<(String, Rwmh)> {
+        ui.checkbox(&mut self.takes_drops, "dropped CSV files are samples");
+        let imported = self.loader.ui(ui).and_then(|data| self.import(&data));
+        if let Some(ref error) = self.last_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        imported
+    }
+
+    pub fn import(&mut self, data: &Dataset) -> Option<(String, Rwmh)> {
+        match chain_from_dataset(data) {
+            Ok(algo) => {
+                self.last_error = None;
+                Some((data.name.clone(), algo))
+            }
+            Err(err) => {
+                self.last_error = Some(format!("{}: {err}", data.name));
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use egui::Ui;
+
+use crate::scenario::Scenario;
+
+use super::has_extension;
+
+/// Loads a [`Scenario`] from a path or from `.toml` files dropped onto the window.
+#[derive(Default)]
+pub struct ScenarioLoader {
+    path: String,
+    last_error: Option<String>,
+}
+
+impl ScenarioLoader {
+    /// Returns a scenario once one was loaded successfully.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Scenario> {
+        #[cfg_attr(
+            target_arch = "wasm32",
+            expect(unused_mut, reason = "only loaded by dropping")
+        )]
+        let mut loaded = None;
+        ui.label("Drop a .toml scenario file onto the window to load it.");
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+            if ui.button("load").clicked() {
+                loaded = self.handle(load_from_path(std::path::Path::new(&self.path)));
+            }
+        });
+        if let Some(ref error) = self.last_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        loaded
+    }
+
+    /// Returns the last scenario that was dropped onto the window this frame and could be loaded.
+    pub fn dropped(&mut self, ctx: &egui::Context) -> Option<Scenario> {
+        let mut loaded = None;
+        for file in ctx.input(|input| input.raw.dropped_files.clone()) {
+            if !is_scenario_file(&file) {
+                continue;
+            }
+            let result = if let Some(bytes) = file.bytes {
+                parse_bytes(&file.name, &bytes)
+            } else if let Some(path) = file.path {
+                load_from_path(&path)
+            } else {
+                continue;
+            };
+            loaded = self.handle(result).or(loaded);
+        }
+        loaded
+    }
+
+    fn handle(&mut self, result: Result<Scenario, String>) -> Option<Scenario> {
+        match result {
+            Ok(scenario) => {
+                self.last_error = None;
+                Some(scenario)
+            }
+            Err(error) => {
+                self.last_error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+/// Dropped files are told apart by their extension, everything else is left to the CSV loader.
+pub fn is_scenario_file(file: &egui::DroppedFile) -> bool {
+    has_extension(file, "toml")
+}
+
+fn parse_bytes(name: &str, bytes: &[u8]) -> Result<Scenario, String> {
+    let text = std::str::from_utf8(bytes).map_err(|err| format!("{name}: {err}"))?;
+    Scenario::parse(text).map_err(|err| format!("{name}: {err}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_from_path(path: &std::path::Path) -> Result<Scenario, String> {
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let bytes = std::fs::read(path).map_err(|err| format!("{name}: {err}"))?;
+    parse_bytes(&name, &bytes)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_from_path(path: &std::path::Path) -> Result<Scenario, String> {
+    Err(format!(
+        "{}: no file system access in the browser",
+        path.display()
+    ))
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use egui::{Align2, Color32, FontId, Pos2, Rect, Stroke, Vec2};
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::{AlgoVec, Rwmh, StepTrace},
+    visualizations::{Arrow, CanvasPainter, PredictionVariance, SamplingPoint, View},
+};
+
+/// Where the acceptance test ends and the move begins, as fractions of the step duration.
+const PROPOSAL_END: f32 = 1.0 / 3.0;
+const TEST_END: f32 = 2.0 / 3.0;
+
+const TEST_BAR_WIDTH: f32 = 200.0;
+
+const fn outcome_color(accepted: bool) -> Color32 {
+    if accepted {
+        Color32::GREEN
+    } else {
+        Color32::RED
+    }
+}
+
+/// Shows a single step like the original JS demo: the proposal, the acceptance test, and the move.
+///
+/// The step is taken on a copy of the chain, which only replaces the shown one once the animation is done,
+/// so that the history doesn't give away the outcome.
+pub struct StepAnimation {
+    /// The chain after the step.
+    pub next: Arc<Rwmh>,
+    trace: StepTrace,
+    sigma: f32,
+    /// Of the point the step started from, see [`SamplingPoint`].
+    normalized_sample_count: f32,
+    /// `egui` input time when the animation started.
+    started: f64,
+}
+
+impl StepAnimation {
+    pub fn new(algo: &Arc<Rwmh>, step: impl FnOnce(&mut Rwmh) -> StepTrace, now: f64) -> Self {
+        let mut next = algo.clone();
+        let trace = step(Arc::make_mut(&mut next));
+        Self {
+            trace,
+            sigma: algo.params.proposal.sigma,
+            normalized_sample_count: (algo.current_loc.remain_count + 1) as f32
+                / (algo.max_remain_count + 1) as f32,
+            next,
+            started: now,
+        }
+    }
+
+    /// Between 0 and 1, the animation is done at 1.
+    pub fn progress(&self, now: f64, step_duration: f32) -> f32 {
+        ((now - self.started) as f32 / step_duration).clamp(0.0, 1.0)
+    }
+
+    pub fn paint(&self, painter: &egui::Painter, rect: Rect, view: &View, progress: f32) {
+        let StepTrace {
+            from,
+            proposal,
+            acceptance_ratio,
+            uniform,
+            accepted,
+        } = self.trace;
+        let to_canvas = |position: AlgoVec| {
+            view.ndc_to_canvas_coord(Pos2::new(position.x, position.y), rect.size())
+        };
+        let (from, proposal) = (to_canvas(from), to_canvas(proposal));
+        let unit = view.scale(rect.size());
+
+        // proposal: drawn from a normal distribution around the current point.
+        PredictionVariance::new(from, self.sigma * unit).paint(painter, rect);
+        let grown = (progress / PROPOSAL_END).min(1.0);
+        Arrow::new(from, (proposal - from) * grown).paint(painter, rect);
+
+        // acceptance test: the uniform draw against the density ratio.
+        if progress >= PROPOSAL_END {
+            let revealed = ((progress - PROPOSAL_END) / (TEST_END - PROPOSAL_END)).min(1.0);
+            self.paint_test(painter, rect, revealed);
+        }
+
+        // move: to the proposal if accepted, otherwise it stays and counts the current point once more.
+        let moved = ((progress - TEST_END) / (1.0 - TEST_END)).clamp(0.0, 1.0);
+        if progress >= TEST_END {
+            painter.circle_stroke(proposal, 6.0, Stroke::new(2.0, outcome_color(accepted)));
+        }
+        let current = if accepted {
+            from + (proposal - from) * moved
+        } else {
+            from
+        };
+        SamplingPoint::new(current, self.normalized_sample_count).paint(painter, rect);
+        painter.text(
+            rect.left_top() + Vec2::splat(10.0),
+            Align2::LEFT_TOP,
+            if progress < PROPOSAL_END {
+                "proposing".to_owned()
+            } else if progress < TEST_END {
+                format!("accept if {uniform:.3} ≤ {acceptance_ratio:.3}")
+            } else if accepted {
+                "accepted, moving to the proposal".to_owned()
+            } else {
+                "rejected, staying".to_owned()
+            },
+            FontId::proportional(16.0),
+            Color32::WHITE,
+        );
+    }
+
+    /// A bar from 0 to 1 filled up to the acceptance ratio, with the uniform draw marked once `revealed` reaches 1.
+    fn paint_test(&self, painter: &egui::Painter, rect: Rect, revealed: f32) {
+        let StepTrace {
+            acceptance_ratio,
+            uniform,
+            accepted,
+            ..
+        } = self.trace;
+        let bar = Rect::from_min_size(
+            rect.left_bottom() + Vec2::new(10.0, -40.0),
+            Vec2::new(TEST_BAR_WIDTH, 12.0),
+        );
+        let at = |fraction: f32| bar.left() + fraction.clamp(0.0, 1.0) * bar.width();
+        painter.rect_stroke(
+            bar,
+            0.0,
+            Stroke::new(1.0, Color32::WHITE),
+            egui::StrokeKind::Outside,
+        );
+        let mut filled = bar;
+        filled.set_right(at(acceptance_ratio * revealed));
+        painter.rect_filled(filled, 0.0, Color32::LIGHT_BLUE.gamma_multiply(0.7));
+        painter.text(
+            bar.left_top() - Vec2::new(0.0, 4.0),
+            Align2::LEFT_BOTTOM,
+            format!("density ratio {acceptance_ratio:.3}"),
+            FontId::proportional(14.0),
+            Color32::WHITE,
+        );
+        if revealed >= 1.0 {
+            let x = at(uniform);
+            painter.line_segment(
+                [
+                    Pos2::new(x, bar.top() - 4.0),
+                    Pos2::new(x, bar.bottom() + 4.0),
+                ],
+                Stroke::new(2.0, outcome_color(accepted)),
+            );
+            painter.text(
+                Pos2::new(x, bar.bottom() + 6.0),
+                Align2::CENTER_TOP,
+                format!("uniform draw {uniform:.3}"),
+                FontId::proportional(14.0),
+                Color32::WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use egui::{Color32, Pos2, Stroke, Ui};
+
+use crate::{
+    helpers::TempStateDataAccess, simulation::random_walk_metropolis_hastings::AlgoVec,
+    target_distr::Support, visualizations::View,
+};
+
+const BOUNDARY_STROKE: Stroke = Stroke {
+    width: 1.5,
+    color: Color32::LIGHT_BLUE,
+};
+
+/// Present while the user adds polygon vertices by clicking on the canvas.
+#[derive(Clone, Copy, Default)]
+pub struct SupportEdit;
+
+impl SupportEdit {
+    pub fn settings_ui(support: &mut Support, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("support:");
+            for variant in Support::variants() {
+                let selected = support.display_name() == variant.display_name();
+                if ui
+                    .selectable_label(selected, variant.display_name())
+                    .clicked()
+                    && !selected
+                {
+                    *support = variant;
+                }
+            }
+        });
+        match *support {
+            Support::Unbounded => {}
+            Support::Box {
+                ref mut min,
+                ref mut max,
+            } => {
+                for (axis, name) in ["x", "y"].into_iter().enumerate() {
+                    let (Some(lower), Some(upper)) = (min.get_mut(axis), max.get_mut(axis)) else {
+                        unreachable!("2 dimensional")
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(name);
+                        ui.add(egui::DragValue::new(lower).speed(0.01).range(-1.0..=*upper));
+                        ui.add(egui::DragValue::new(upper).speed(0.01).range(*lower..=1.0));
+                    });
+                }
+            }
+            Support::HalfPlane {
+                ref mut normal,
+                ref mut offset,
+            } => {
+                ui.label("all points x with normal · x >= offset");
+                ui.horizontal(|ui| {
+                    ui.label("normal");
+                    for component in normal.iter_mut() {
+                        ui.add(egui::DragValue::new(component).speed(0.01));
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(offset, -1.0..=1.0)
+                        .clamping(egui::SliderClamping::Never)
+                        .text("offset"),
+                );
+            }
+            Support::Polygon { ref mut vertices } => {
+                if Self::is_present(ui) {
+                    ui.label("Click on the canvas to add vertices.");
+                    if ui.button("Stop Drawing").clicked() {
+                        Self::remove(ui);
+                    }
+                } else if ui.button("Draw").clicked() {
+                    Self::open(ui);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("clear vertices").clicked() {
+                        vertices.clear();
+                    }
+                    if ui.button("remove last vertex").clicked() {
+                        vertices.pop();
+                    }
+                });
+                if vertices.len() < 3 {
+                    ui.label("At least 3 vertices are required, unbounded until then.");
+                }
+            }
+        }
+    }
+
+    fn open(ui: &Ui) {
+        ui.temp_ui_state::<Self>().create_default();
+    }
+
+    fn remove(ui: &Ui) {
+        ui.temp_ui_state::<Self>().remove();
+    }
+
+    fn is_present(ui: &Ui) -> bool {
+        ui.temp_ui_state::<Self>().get().is_some()
+    }
+
+    /// Adds a polygon vertex where the canvas was clicked, if drawing.
+    pub fn show_if_open(
+        support: &mut Support,
+        ui: &Ui,
+        response: &egui::Response,
+        rect: egui::Rect,
+        view: &View,
+    ) {
+        let Support::Polygon { ref mut vertices } = *support else {
+            return;
+        };
+        if !Self::is_present(ui) || !response.clicked() {
+            return;
+        }
+        if let Some(pos) = response.interact_pointer_pos() {
+            let ndc = view.canvas_coord_to_ndc(pos, rect.size());
+            vertices.push([ndc.x, ndc.y]);
+        }
+    }
+
+    pub fn paint(support: &Support, painter: &egui::Painter, rect: egui::Rect, view: &View) {
+        let to_canvas =
+            |point: AlgoVec| view.ndc_to_canvas_coord(Pos2::new(point.x, point.y), rect.size());
+        match *support {
+            Support::Unbounded => {}
+            Support::Box { min, max } => {
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(to_canvas(min.into()), to_canvas(max.into())),
+                    0.0,
+                    BOUNDARY_STROKE,
+                    egui::StrokeKind::Middle,
+                );
+            }
+            Support::HalfPlane { normal, offset } => {
+                let normal = AlgoVec::from(normal);
+                let norm_squared = normal.norm_squared();
+                if norm_squared <= 0.0 {
+                    return;
+                }
+                let on_boundary = normal * (offset / norm_squared);
+                // long enough to leave the visible area in both directions.
+                let reach = 10.0 * (view.half_extent + AlgoVec::from(view.center).norm());
+                let tangent = AlgoVec::new(-normal.y, normal.x).normalize() * reach;
+                painter.line_segment(
+                    [
+                        to_canvas(on_boundary - tangent),
+                        to_canvas(on_boundary + tangent),
+                    ],
+                    BOUNDARY_STROKE,
+                );
+                // a short tick towards the inside
+                painter.arrow(
+                    to_canvas(on_boundary),
+                    to_canvas(on_boundary + normal.normalize() * 0.1) - to_canvas(on_boundary),
+                    BOUNDARY_STROKE,
+                );
+            }
+            Support::Polygon { ref vertices } => {
+                let points: Vec<_> = vertices
+                    .iter()
+                    .map(|&vertex| to_canvas(vertex.into()))
+                    .collect();
+                for &point in points.iter() {
+                    painter.circle_filled(point, 3.0, BOUNDARY_STROKE.color);
+                }
+                if points.len() >= 3 {
+                    painter.add(egui::Shape::closed_line(points, BOUNDARY_STROKE));
+                } else {
+                    painter.add(egui::Shape::line(points, BOUNDARY_STROKE));
+                }
+            }
+        }
+    }
+}
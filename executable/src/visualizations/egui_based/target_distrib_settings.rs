@@ -1,10 +1,6 @@
 use egui::Ui;
 
-use crate::{
-    app::{canvas_coord_to_ndc, ndc_to_canvas_coord},
-    helpers::TempStateDataAccess,
-    target_distr,
-};
+use crate::{helpers::TempStateDataAccess, target_distr, visualizations::View};
 
 #[derive(Clone, Copy)]
 pub struct ElementSettings(usize);
@@ -22,9 +18,16 @@ impl ElementSettings {
         gaussians: &mut Vec<target_distr::NormalDistribution>,
         ui: &egui::Ui,
         rect: egui::Rect,
+        view: &View,
         ctx: &egui::Context,
     ) {
         if let Some(Self(idx)) = ui.temp_ui_state().get() {
+            // the elements might have been replaced as a whole, e.g. by undo.
+            let Some(&target_distr::NormalDistribution { position, .. }) = gaussians.get(idx)
+            else {
+                Self::remove(ui);
+                return;
+            };
             #[expect(clippy::shadow_unrelated, reason = "false positive, is related.")]
             let close_planel = |ui: &egui::Ui| {
                 Self::remove(ui);
@@ -34,30 +37,32 @@ impl ElementSettings {
             let mut opened_proxy = true;
             egui::Window::new(format!("Settings for Gauss-Element {idx}"))
                 .open(&mut opened_proxy)
-                .fixed_pos(ndc_to_canvas_coord(
-                    gaussians
-                        .get(idx)
-                        .expect("Guaranteed to be present")
-                        .position
-                        .into(),
-                    rect.size(),
-                ))
+                .fixed_pos(view.ndc_to_canvas_coord(position.into(), rect.size()))
                 .collapsible(false)
                 .show(
                     ctx,
                     #[expect(clippy::shadow_unrelated, reason = "false positive")]
                     |ui| {
                         let el = gaussians.get_mut(idx).unwrap();
+                        ui.horizontal(|ui| {
+                            ui.label("Position");
+                            for coord in el.position.iter_mut() {
+                                ui.add(egui::DragValue::new(coord).speed(0.01));
+                            }
+                        });
                         ui.add(egui::Slider::new(&mut el.scale, f32::EPSILON..=1.0).text("Scale"));
                         ui.add(
                             egui::Slider::new(&mut el.variance, f32::EPSILON..=4.0)
                                 .logarithmic(true)
                                 .text("Variance"),
                         );
-                        if ui.button("delete").clicked() {
-                            gaussians.remove(idx);
-                            close_planel(ui);
-                        }
+                        ui.horizontal(|ui| {
+                            for action in ComponentAction::VARIANTS {
+                                if ui.button(action.display_name()).clicked() {
+                                    action.apply(gaussians, idx, ui);
+                                }
+                            }
+                        });
                     },
                 );
             if !opened_proxy {
@@ -67,6 +72,66 @@ impl ElementSettings {
     }
 }
 
+#[derive(Clone, Copy)]
+enum ComponentAction {
+    MoveUp,
+    MoveDown,
+    Duplicate,
+    Delete,
+}
+
+impl ComponentAction {
+    const VARIANTS: [Self; 4] = [Self::MoveUp, Self::MoveDown, Self::Duplicate, Self::Delete];
+
+    const fn display_name(self) -> &'static str {
+        match self {
+            Self::MoveUp => "move up",
+            Self::MoveDown => "move down",
+            Self::Duplicate => "duplicate",
+            Self::Delete => "delete",
+        }
+    }
+
+    /// Keeps an opened [`ElementSettings`] window on the same element.
+    fn apply(self, gaussians: &mut Vec<target_distr::NormalDistribution>, idx: usize, ui: &Ui) {
+        let Some(&el) = gaussians.get(idx) else {
+            return;
+        };
+        let settings_idx = ui.temp_ui_state::<ElementSettings>().get();
+        let swap_with = match self {
+            Self::MoveUp => idx.checked_sub(1),
+            Self::MoveDown => Some(idx + 1).filter(|&other| other < gaussians.len()),
+            Self::Duplicate => {
+                gaussians.insert(idx + 1, el);
+                None
+            }
+            Self::Delete => {
+                gaussians.remove(idx);
+                match settings_idx {
+                    Some(ElementSettings(open)) if open == idx => ElementSettings::remove(ui),
+                    Some(ElementSettings(open)) if open > idx => {
+                        ui.temp_ui_state().set_or_create(ElementSettings(open - 1));
+                    }
+                    _ => {}
+                }
+                None
+            }
+        };
+        if let Some(other) = swap_with {
+            gaussians.swap(idx, other);
+            match settings_idx {
+                Some(ElementSettings(open)) if open == idx => {
+                    ui.temp_ui_state().set_or_create(ElementSettings(other));
+                }
+                Some(ElementSettings(open)) if open == other => {
+                    ui.temp_ui_state().set_or_create(ElementSettings(idx));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 pub enum DistrEdit {
     #[default]
@@ -90,6 +155,58 @@ impl DistrEdit {
         } else if ui.button("Edit").clicked() {
             DistrEdit::open(ui);
         };
+        if ui
+            .button("Normalise weights")
+            .on_hover_text("Rescales the weights of all elements to sum up to 1.")
+            .clicked()
+        {
+            let total: f32 = gaussians.iter().map(|el| el.scale).sum();
+            if total > 0.0 {
+                for el in gaussians.iter_mut() {
+                    el.scale /= total;
+                }
+            }
+        }
+        ui.collapsing("all elements", |ui| {
+            Self::table_ui(gaussians, ui);
+        });
+    }
+
+    fn table_ui(gaussians: &mut Vec<target_distr::NormalDistribution>, ui: &mut Ui) {
+        let mut action = None;
+        egui::Grid::new("gauss elements")
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["", "x", "y", "weight", "variance"] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for (idx, el) in gaussians.iter_mut().enumerate() {
+                    ui.label(format!("{idx}"));
+                    for coord in el.position.iter_mut() {
+                        ui.add(egui::DragValue::new(coord).speed(0.01));
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut el.scale)
+                            .speed(0.01)
+                            .range(f32::EPSILON..=f32::MAX),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut el.variance)
+                            .speed(0.001)
+                            .range(f32::EPSILON..=f32::MAX),
+                    );
+                    for ele in ComponentAction::VARIANTS {
+                        if ui.small_button(ele.display_name()).clicked() {
+                            action = Some((ele, idx));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some((action, idx)) = action {
+            action.apply(gaussians, idx, ui);
+        }
     }
 
     fn open(ui: &Ui) {
@@ -110,13 +227,14 @@ impl DistrEdit {
         ui: &Ui,
         response: &egui::Response,
         rect: egui::Rect,
+        view: &View,
         painter: &egui::Painter,
     ) {
         if let Some(Self::Editing) = ui.temp_ui_state::<Self>().get() {
             let res_id = response.id;
             // draw centers of gaussians, move them if dragged, open more settings if clicked
             for (idx, ele) in gaussians.iter_mut().enumerate() {
-                let pos = ndc_to_canvas_coord(ele.position.into(), rect.size());
+                let pos = view.ndc_to_canvas_coord(ele.position.into(), rect.size());
                 const CIRCLE_SIZE: f32 = 5.0;
                 let pos_sense_rect =
                     egui::Rect::from_center_size(pos, egui::Vec2::splat(CIRCLE_SIZE));
@@ -133,12 +251,13 @@ impl DistrEdit {
                 if pos_resp.clicked() {
                     ElementSettings::open_for(idx, ui);
                 };
-                let pos = rect.clamp(pos + pos_resp.drag_delta());
-
-                let ndc_pos = canvas_coord_to_ndc(pos, rect.size());
-
-                ele.position[0] = ndc_pos.x;
-                ele.position[1] = ndc_pos.y;
+                // elements outside of the view stay where they are until dragged.
+                let pos = pos + pos_resp.drag_delta();
+                if pos_resp.dragged() {
+                    let ndc_pos = view.canvas_coord_to_ndc(pos, rect.size());
+                    ele.position[0] = ndc_pos.x;
+                    ele.position[1] = ndc_pos.y;
+                }
 
                 let pos_active = pos_resp.clicked() || pos_resp.dragged() || pos_resp.hovered();
 
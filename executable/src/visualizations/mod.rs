@@ -1,17 +1,25 @@
 mod egui_based;
 mod shader_based;
+mod view;
 
 use std::sync::Arc;
 
 use macros::cfg_persistence_derive;
 
+#[cfg(feature = "persistence")]
+pub use egui_based::CheckpointFile;
 pub use egui_based::{
-    Arrow, DistrEdit, ElementSettings, PredictionVariance, SamplePointVisualizer, SamplingPoint,
+    Arrow, ChainExport, ColorMap, ColorScale, ColorSettings, ContourLines, ContourSettings,
+    CsvLoader, DensityImage, DistrEdit, ElementSettings, HighDimEdit, HistoryScrubber, KdeSettings,
+    PosteriorEdit, PredictionVariance, RngSettingsUi, SampleImport, SamplePointVisualizer,
+    SamplingPoint, Scaling, ScenarioLoader, StepAnimation, SupportEdit,
 };
 
+pub use view::View;
+
 pub use shader_based::{
     BDAComputeDiff, BDADiff, BDADiffState, BdaComputeState, BdaComputeTask, INITIAL_RENDER_SIZE,
-    MMGState, NormalDistribution, RWMHAcceptRecord, TargetDistribution,
+    KdeParams, MMGState, TargetDistribution,
 };
 
 use crate::{simulation::random_walk_metropolis_hastings::Rwmh, target_distr};
@@ -51,6 +59,8 @@ macro_rules! bg_display {
                 rect: egui::Rect,
                 algo: Arc<Rwmh>,
                 target: &target_distr::Gaussian,
+                view: &View,
+                colors: &ColorScale,
             ) {
                 match self {
                     $(&Self::$struct_name(ref inner) => {
@@ -60,6 +70,8 @@ macro_rules! bg_display {
                             rect,
                             algo,
                             target,
+                            view,
+                            colors,
                         );
                     })+
                 }
@@ -95,11 +107,32 @@ trait AlgoPainter {
         rect: egui::Rect,
         algo: Arc<Rwmh>,
         target: &target_distr::Gaussian,
+        view: &View,
+        colors: &ColorScale,
     );
 }
 
 bg_display!(TargetDistribution, BDAComputeDiff, BDADiff,);
 
+impl BackgroundDisplay {
+    /// Of the displays that show a kernel density estimate.
+    pub const fn kde_mut(&mut self) -> Option<&mut KdeSettings> {
+        match *self {
+            Self::TargetDistribution(_) => None,
+            Self::BDAComputeDiff(ref mut display) => Some(&mut display.kde),
+            Self::BDADiff(ref mut display) => Some(&mut display.kde),
+        }
+    }
+
+    /// Whether the display shows the difference to the target instead of a density, see [`ColorSettings::diff`].
+    pub const fn shows_diff(&self) -> bool {
+        match *self {
+            Self::TargetDistribution(_) => false,
+            Self::BDAComputeDiff(_) | Self::BDADiff(_) => true,
+        }
+    }
+}
+
 impl Default for BackgroundDisplay {
     fn default() -> Self {
         BackgroundDisplay::TargetDistribution(Default::default())
@@ -2,7 +2,10 @@
 //!
 //! The diff at the end of the BDA(Compute)Diff is meant to signify that we umtilately look at the difference between the approximation of and the target probability density
 
-use std::{ops::Deref, sync::Arc};
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex, PoisonError},
+};
 
 use eframe::egui_wgpu::CallbackTrait;
 use macros::{cfg_educe_debug, cfg_persistence_derive};
@@ -15,28 +18,43 @@ use wgpu::{
 use crate::{
     create_shader_module,
     helpers::{GpuTask, TaskDispatcher},
-    simulation::random_walk_metropolis_hastings::Rwmh,
-    target_distr,
-    visualizations::AlgoPainter,
+    kde::{self, Grid, Kernel},
+    simulation::random_walk_metropolis_hastings::{AcceptRecord, Rwmh},
+    target_distr::{self, NormalDistribution},
+    visualizations::{AlgoPainter, ColorScale, KdeParams, KdeSettings, View},
 };
 
 use super::{
     INITIAL_RENDER_SIZE,
     bda_immediate::{get_approx_buffers, shader_bindings::RWMHCountInfo},
+    color_uniform::{color_map_options, get_color_map_buffer},
     fullscreen_quad,
-    resolution_uniform::get_resolution_buffer,
-    target_distr::{NormalDistribution, get_normaldistr_buffer},
+    kde_uniform::get_kde_buffer,
+    max_norm::MaxNorm,
+    resolution_uniform::{get_resolution_buffer, resolution_info},
+    target_distr::get_normaldistr_buffer,
 };
 
 create_shader_module!("binary_distance_approx.compute", mod compute_bindings);
 create_shader_module!("binary_distance_approx.fragment", mod fragment_bindings);
 
-pub use compute_bindings::ResolutionInfo;
+pub use compute_bindings::{BinaryDistanceApproxOptions, ResolutionInfo};
 
 pub fn compute_buffer_size_in_bytes(resolution: &[f32; 2]) -> u64 {
     (resolution[0] * resolution[1]) as u64 * size_of::<f32>() as u64
 }
 
+/// The cells of the compute output, which the estimate is binned into.
+fn binning_grid(resolution: [f32; 2], view: &View) -> Grid {
+    let size = egui::Vec2::from(resolution);
+    Grid {
+        origin: view.canvas_coord_to_ndc(egui::Pos2::ZERO, size).into(),
+        cell_size: view.scale(size).recip(),
+        width: resolution[0] as u32,
+        height: resolution[1] as u32,
+    }
+}
+
 #[macro_export]
 macro_rules! definition_location {
     () => {
@@ -60,10 +78,61 @@ pub(super) fn create_compute_output_buffer(
 
 #[cfg_persistence_derive]
 #[derive(Default)]
-pub struct BDAComputeDiff {}
+pub struct BDAComputeDiff {
+    #[serde(default)]
+    pub kde: KdeSettings,
+    #[serde(default)]
+    pub grid: ComputeGrid,
+}
+
+/// The approximation is computed on this grid instead of the pixels of the canvas, which samples it bilinearly.
+///
+/// It's a square around the shown part of the sample space, so that resizing the canvas usually doesn't require computing it again.
+#[cfg_persistence_derive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputeGrid {
+    /// Along each side.
+    pub cells: u32,
+}
+
+impl Default for ComputeGrid {
+    fn default() -> Self {
+        Self { cells: 1024 }
+    }
+}
 
-// I dont need to read this properly on the CPU right now
-type ComputeBufCpuRepr = Vec<f32>;
+impl ComputeGrid {
+    /// Relative to the shown square at least, so that canvases up to this many times wider than high are covered.
+    const MIN_REACH: f32 = 2.0;
+    const MIN_CELLS: u32 = 64;
+    const MAX_CELLS: u32 = 4096;
+
+    /// Persisted states might ask for more, which the device might not provide.
+    fn resolution(&self) -> [f32; 2] {
+        [self.cells.clamp(Self::MIN_CELLS, Self::MAX_CELLS) as f32; 2]
+    }
+
+    /// The covered part of the sample space, the grid is treated like a square canvas showing it.
+    ///
+    /// It covers the canvas of `canvas_size`, only growing in powers of two, so that it doesn't change on every resize.
+    fn view(&self, view: &View, canvas_size: [f32; 2]) -> View {
+        let [width, height] = canvas_size;
+        let aspect_ratio = width.max(height) / width.min(height).max(1.0);
+        View {
+            center: view.center,
+            half_extent: view.half_extent * aspect_ratio.log2().ceil().exp2().max(Self::MIN_REACH),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.cells, Self::MIN_CELLS..=Self::MAX_CELLS)
+                .logarithmic(true)
+                .text("compute grid cells per side"),
+        )
+        .on_hover_text("Fewer cells compute faster, but blur the approximation.");
+    }
+}
 
 pub struct PipelineStateHolder {
     fragment_pipeline: RenderPipeline,
@@ -71,14 +140,50 @@ pub struct PipelineStateHolder {
     fragment_group_1: fragment_bindings::BindGroup1,
     compute_output_buffer: Buffer,
     resolution_buffer: Buffer,
+    color_map_buffer: Buffer,
+    compute_grid_buffer: Buffer,
     target_buffer: Buffer,
+    /// For the compute tasks, they run on the same device.
+    max_norm: Arc<MaxNorm>,
     gpu_tx: TaskDispatcher<ComputeTask>,
-    compute_results_tx: watch::Sender<Option<ComputeBufCpuRepr>>,
-    compute_results_rx: watch::Receiver<Option<ComputeBufCpuRepr>>,
+    /// The normalized approximation, computed on the same device, so it never has to leave the GPU.
+    compute_results_tx: watch::Sender<Option<Buffer>>,
+    compute_results_rx: watch::Receiver<Option<Buffer>>,
     prev_approx_len: usize,
+    /// Of the compute grid, the approximation is computed for it.
+    prev_view: View,
+    prev_kde: KdeParams,
+    /// Kept by the compute tasks from one to the next.
+    accumulator: Arc<Mutex<Option<Accumulator>>>,
     refresh_token: Arc<Notify>,
 }
 
+/// The bins of the last computation, so that the next one only bins the records added since.
+#[derive(Debug)]
+struct Accumulator {
+    bins: Buffer,
+    /// What the bins were made for, they start over when any of it changes.
+    grid: Grid,
+    kernel: Kernel,
+    target: Vec<NormalDistribution>,
+    /// Of the history, the last binned record tells whether a chain continues the binned one.
+    binned_len: usize,
+    last_binned: AcceptRecord,
+}
+
+impl Accumulator {
+    fn continues(&self, task: &ComputeTask, grid: &Grid) -> bool {
+        self.grid == *grid
+            && self.kernel == task.kde.kernel
+            && self.target == task.target
+            && self
+                .binned_len
+                .checked_sub(1)
+                .and_then(|last| task.algo_state.history.get(last))
+                == Some(&self.last_binned)
+    }
+}
+
 impl AlgoPainter for BDAComputeDiff {
     fn paint(
         &self,
@@ -86,13 +191,19 @@ impl AlgoPainter for BDAComputeDiff {
         rect: egui::Rect,
         algo: Arc<Rwmh>,
         target: &target_distr::Gaussian,
+        view: &View,
+        colors: &ColorScale,
     ) {
         painter.add(eframe::egui_wgpu::Callback::new_paint_callback(
             rect,
             RenderCall {
-                algo_state: algo.clone(),
+                kde: self.kde.params(&algo),
+                algo_state: algo,
                 px_res: rect.size().into(),
+                grid: self.grid,
                 target_distr: target.gaussians.clone(),
+                view: *view,
+                colors: *colors,
             },
         ));
     }
@@ -128,6 +239,8 @@ impl PipelineStateHolder {
         });
 
         let resolution_buffer = get_resolution_buffer(device);
+        let color_map_buffer = get_color_map_buffer(device);
+        let compute_grid_buffer = get_resolution_buffer(device);
 
         let target_buffer = get_normaldistr_buffer(device, None);
 
@@ -137,6 +250,7 @@ impl PipelineStateHolder {
             device,
             fragment_bindings::bind_groups::BindGroupLayout0 {
                 resolution_info: resolution_buffer.as_entire_buffer_binding(),
+                color_map: color_map_buffer.as_entire_buffer_binding(),
             },
         );
 
@@ -145,16 +259,20 @@ impl PipelineStateHolder {
             fragment_bindings::BindGroupLayout1 {
                 gauss_bases: target_buffer.as_entire_buffer_binding(),
                 compute_output: compute_output_buffer.as_entire_buffer_binding(),
+                compute_grid: compute_grid_buffer.as_entire_buffer_binding(),
             },
         );
 
         let (compute_results_tx, compute_results_rx) = watch::channel(None);
 
         Self {
+            max_norm: Arc::new(MaxNorm::new(device)),
             fragment_group_0,
             fragment_group_1,
             fragment_pipeline,
             resolution_buffer,
+            color_map_buffer,
+            compute_grid_buffer,
             compute_output_buffer,
             target_buffer,
             gpu_tx,
@@ -162,14 +280,21 @@ impl PipelineStateHolder {
             compute_results_tx,
             refresh_token,
             prev_approx_len: 0,
+            prev_view: View::default(),
+            prev_kde: KdeParams::default(),
+            accumulator: Arc::default(),
         }
     }
 }
 
 struct RenderCall {
     px_res: [f32; 2],
+    grid: ComputeGrid,
     target_distr: Vec<NormalDistribution>,
     algo_state: Arc<Rwmh>,
+    view: View,
+    kde: KdeParams,
+    colors: ColorScale,
 }
 
 impl CallbackTrait for RenderCall {
@@ -188,6 +313,8 @@ impl CallbackTrait for RenderCall {
     ) -> Vec<wgpu::CommandBuffer> {
         let &mut PipelineStateHolder {
             ref resolution_buffer,
+            ref color_map_buffer,
+            ref compute_grid_buffer,
             ref mut target_buffer,
             ref mut compute_output_buffer,
             ref mut fragment_group_1,
@@ -195,6 +322,10 @@ impl CallbackTrait for RenderCall {
             ref compute_results_tx,
             ref mut compute_results_rx,
             ref mut prev_approx_len,
+            ref mut prev_view,
+            ref mut prev_kde,
+            ref accumulator,
+            ref max_norm,
             ref refresh_token,
             ..
         } = callback_resources
@@ -209,32 +340,47 @@ impl CallbackTrait for RenderCall {
         let curr_approx_len = accepted_approx.len();
         let approx_changed = curr_approx_len != *prev_approx_len;
         *prev_approx_len = curr_approx_len;
-        let res_changed =
-            compute_output_buffer.size() != compute_buffer_size_in_bytes(&self.px_res);
-        if res_changed {
-            *compute_output_buffer = create_compute_output_buffer(device, Some(&self.px_res));
+        let grid_view = self.grid.view(&self.view, self.px_res);
+        let view_changed = *prev_view != grid_view;
+        *prev_view = grid_view;
+        let kde_changed = *prev_kde != self.kde;
+        *prev_kde = self.kde;
+        // the canvas only samples the grid, so resizing it only changes the approximation once it outgrows the grid.
+        queue.write_buffer(
+            resolution_buffer,
+            0,
+            bytemuck::cast_slice(&[resolution_info(self.px_res, &self.view)]),
+        );
+        queue.write_buffer(
+            color_map_buffer,
+            0,
+            bytemuck::cast_slice(&[color_map_options(&self.colors)]),
+        );
+        let grid_resolution = self.grid.resolution();
+        let grid_changed =
+            compute_output_buffer.size() != compute_buffer_size_in_bytes(&grid_resolution);
+        if grid_changed {
+            *compute_output_buffer = create_compute_output_buffer(device, Some(&grid_resolution));
+        }
+        if grid_changed || view_changed {
             queue.write_buffer(
-                resolution_buffer,
+                compute_grid_buffer,
                 0,
-                bytemuck::cast_slice(&[ResolutionInfo {
-                    resolution: self.px_res,
-                    _pad: [0.0; 2],
-                }]),
+                bytemuck::cast_slice(&[resolution_info(grid_resolution, &grid_view)]),
             );
         }
-        if res_changed || approx_changed {
-            let compute_results_tx = compute_results_tx.clone();
-            let refresh_token = refresh_token.clone();
+        if grid_changed || approx_changed || view_changed || kde_changed {
             // old value is now outdated.
             tracing::info!("resetting compute result");
             compute_results_tx.send(None).unwrap();
-            let rx = dispatch_approximation_gpu(gpu_tx, self);
-            // TODO: reconsider why this isn't a task
-            // I think it was that this would block on main thread on the web, and making this and a task on native compatible with each other is nasty.
-            wasm_thread::spawn(move || {
-                dispatch_maxnorm_rayon(rx, &compute_results_tx);
-                refresh_token.notify_waiters();
-            });
+            dispatch_approximation_gpu(
+                gpu_tx,
+                self,
+                compute_results_tx,
+                accumulator,
+                max_norm,
+                refresh_token,
+            );
         }
         if compute_results_rx
             .has_changed()
@@ -242,24 +388,16 @@ impl CallbackTrait for RenderCall {
         {
             compute_results_rx.mark_unchanged();
             if let &Some(ref val) = compute_results_rx.borrow().deref() {
-                if compute_output_buffer.size() != (val.as_slice().len() * 4) as u64 {
+                if compute_output_buffer.size() != val.size() {
                     tracing::error!("Resolution mismatch.");
                 } else {
-                    queue.write_buffer(
-                        compute_output_buffer,
-                        0,
-                        bytemuck::cast_slice(val.as_slice()),
-                    );
+                    *compute_output_buffer = val.clone();
                 }
             } else {
                 tracing::debug!("Clearing Buffer because of empty watch channel");
-                // clear the buffer, instead of leaving wrong values there.
-                // There ought to be a better way....
-                queue.write_buffer(
-                    compute_output_buffer,
-                    0,
-                    &vec![0u8; compute_output_buffer.size() as usize],
-                );
+                // a new buffer starts zeroed, instead of leaving wrong values there.
+                *compute_output_buffer =
+                    create_compute_output_buffer(device, Some(&grid_resolution));
             }
         }
 
@@ -275,6 +413,7 @@ impl CallbackTrait for RenderCall {
             fragment_bindings::BindGroupLayout1 {
                 compute_output: compute_output_buffer.as_entire_buffer_binding(),
                 gauss_bases: target_buffer.as_entire_buffer_binding(),
+                compute_grid: compute_grid_buffer.as_entire_buffer_binding(),
             },
         );
         Vec::new()
@@ -299,54 +438,44 @@ impl CallbackTrait for RenderCall {
     }
 }
 
-fn dispatch_maxnorm_rayon(
-    rx: oneshot::Receiver<ComputeBufCpuRepr>,
-    compute_results_tx: &watch::Sender<Option<ComputeBufCpuRepr>>,
-) {
-    use rayon::prelude::*;
-    // TODO: ensure this doesn't copy when sending over the channel.
-    // Otherwise I will have to find an alternative.
-    let Ok(mut prob_buffer) = rx.blocking_recv() else {
-        tracing::info!("Closing Maxnorm worker main-thread, as sender channel-end closed");
-        return;
-    };
-    tracing::info!("calculating maxnorm");
-    let max = *prob_buffer
-        .par_iter()
-        .max_by(|lhs, rhs| lhs.total_cmp(rhs))
-        .unwrap_or(&1.0);
-    prob_buffer
-        .par_iter_mut()
-        .for_each(|unnorm_prob| *unnorm_prob /= max);
-    compute_results_tx
-        .send(Some(prob_buffer))
-        .map_err(|_err| "Channel Closed")
-        .unwrap();
-}
-
 fn dispatch_approximation_gpu(
     gpu_tx: &TaskDispatcher<ComputeTask>,
     render_call: &RenderCall,
-) -> oneshot::Receiver<Vec<f32>> {
-    let (tx, rx) = oneshot::channel::<ComputeBufCpuRepr>();
+    compute_results_tx: &watch::Sender<Option<Buffer>>,
+    accumulator: &Arc<Mutex<Option<Accumulator>>>,
+    max_norm: &Arc<MaxNorm>,
+    refresh_token: &Arc<Notify>,
+) {
     match gpu_tx.dispatch_task_blocking(crate::visualizations::BdaComputeTask {
-        px_size: render_call.px_res,
+        resolution: render_call.grid.resolution(),
+        view: render_call.grid.view(&render_call.view, render_call.px_res),
+        kde: render_call.kde,
         algo_state: render_call.algo_state.clone(),
-        result_tx: Some(tx),
+        target: render_call.target_distr.clone(),
+        accumulator: accumulator.clone(),
+        max_norm: max_norm.clone(),
+        results_tx: compute_results_tx.clone(),
+        refresh_token: refresh_token.clone(),
     }) {
         Ok(_) => {}
         Err(_err) => {
             tracing::warn!("GpuTasks filled");
         }
     }
-    rx
 }
 
 #[cfg_educe_debug]
 pub struct ComputeTask {
-    px_size: [f32; 2],
+    /// Of the compute grid, and the part of the sample space it covers.
+    resolution: [f32; 2],
+    view: View,
+    kde: KdeParams,
     algo_state: Arc<Rwmh>,
-    result_tx: Option<oneshot::Sender<ComputeBufCpuRepr>>,
+    target: Vec<NormalDistribution>,
+    accumulator: Arc<Mutex<Option<Accumulator>>>,
+    max_norm: Arc<MaxNorm>,
+    results_tx: watch::Sender<Option<Buffer>>,
+    refresh_token: Arc<Notify>,
 }
 
 impl GpuTask for ComputeTask {
@@ -362,15 +491,21 @@ impl GpuTask for ComputeTask {
         let queue = queue.as_ref();
 
         let compute_layout = compute_bindings::create_pipeline_layout(device);
-
-        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-            module: &compute_bindings::create_shader_module(device),
-            entry_point: None,
-            compilation_options: Default::default(),
-            label: webgpu_debug_name,
-            layout: Some(&compute_layout),
-            cache: None,
-        });
+        let compute_module = compute_bindings::create_shader_module(device);
+
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                module: &compute_module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                label: webgpu_debug_name,
+                layout: Some(&compute_layout),
+                cache: None,
+            })
+        };
+        let bin_pipeline = create_pipeline("bin");
+        let convolve_rows_pipeline = create_pipeline("convolve_rows");
+        let convolve_columns_pipeline = create_pipeline("convolve_columns");
 
         let mut compute_encoder = device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some(definition_location!()),
@@ -379,14 +514,54 @@ impl GpuTask for ComputeTask {
             label: Some(definition_location!()),
             timestamp_writes: None,
         });
-        compute_pass.set_pipeline(&compute_pipeline);
 
         let resolution_buffer = get_resolution_buffer(device);
 
-        let approx_accepted = self.algo_state.history.as_slice();
-        let (accept_buffer, info_buffer) = get_approx_buffers(device, Some(approx_accepted));
+        let compute_output_buffer = create_compute_output_buffer(device, Some(&self.resolution));
 
-        let compute_output_buffer = create_compute_output_buffer(device, Some(&self.px_size));
+        let grid = binning_grid(self.resolution, &self.view);
+        let margin = grid.margin(self.kde.kernel, self.kde.bandwidth);
+        // the margin never exceeds this, so the bins don't depend on the bandwidth.
+        let padding = kde::MAX_MARGIN;
+
+        let history = self.algo_state.history.as_slice();
+        let continued = self
+            .accumulator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .filter(|accumulator| accumulator.continues(self, &grid));
+        let (bins_buffer, binned_len) = match continued {
+            Some(Accumulator {
+                bins, binned_len, ..
+            }) => (bins, binned_len),
+            None => (
+                // zeroed on creation.
+                device.create_buffer(&BufferDescriptor {
+                    label: Some(definition_location!()),
+                    usage: BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                    size: u64::from(grid.width + 2 * padding)
+                        * u64::from(grid.height + 2 * padding)
+                        * size_of::<u32>() as u64,
+                }),
+                // the dummy element.
+                1,
+            ),
+        };
+        // The shader skips the first record, which was binned before.
+        let new_records = history
+            .get(binned_len.saturating_sub(1)..)
+            .unwrap_or(history);
+        let (accept_buffer, info_buffer) = get_approx_buffers(device, Some(new_records));
+
+        let convolved_height = grid.height + 2 * margin;
+        let rows_convolved_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some(definition_location!()),
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+            size: u64::from(grid.width) * u64::from(convolved_height) * size_of::<f32>() as u64,
+        });
 
         let compute_group_0 = compute_bindings::BindGroup0::from_bindings(
             device,
@@ -397,15 +572,7 @@ impl GpuTask for ComputeTask {
         queue.write_buffer(
             &resolution_buffer,
             0,
-            bytemuck::cast_slice(&[ResolutionInfo {
-                resolution: self.px_size,
-                _pad: [0.0; 2],
-            }]),
-        );
-        queue.write_buffer(
-            &accept_buffer,
-            0,
-            bytemuck::cast_slice(self.algo_state.history.as_slice()),
+            bytemuck::cast_slice(&[resolution_info(self.resolution, &self.view)]),
         );
         queue.write_buffer(
             &info_buffer,
@@ -415,52 +582,98 @@ impl GpuTask for ComputeTask {
                 total_point_count: self.algo_state.total_point_count,
             }]),
         );
+        let kde_buffer = get_kde_buffer(device);
+        queue.write_buffer(
+            &kde_buffer,
+            0,
+            bytemuck::cast_slice(&[self.kde.uniform(margin, padding)]),
+        );
+        let compute_group_2 = compute_bindings::BindGroup2::from_bindings(
+            device,
+            compute_bindings::BindGroupLayout2 {
+                kde_options: kde_buffer.as_entire_buffer_binding(),
+            },
+        );
         let compute_group_1 = compute_bindings::BindGroup1::from_bindings(
             device,
             compute_bindings::BindGroupLayout1 {
                 compute_output: compute_output_buffer.as_entire_buffer_binding(),
                 accepted: accept_buffer.as_entire_buffer_binding(),
                 count_info: info_buffer.as_entire_buffer_binding(),
+                bins: bins_buffer.as_entire_buffer_binding(),
+                rows_convolved: rows_convolved_buffer.as_entire_buffer_binding(),
             },
         );
         compute_group_0.set(&mut compute_pass);
         compute_group_1.set(&mut compute_pass);
-        compute_pass.dispatch_workgroups(self.px_size[0] as u32, self.px_size[1] as u32, 1);
+        compute_group_2.set(&mut compute_pass);
+
+        let [bin_workgroup_size, ..] = compute_bindings::compute::BIN_WORKGROUP_SIZE;
+        let [convolve_workgroup_width, convolve_workgroup_height, _] =
+            compute_bindings::compute::CONVOLVE_ROWS_WORKGROUP_SIZE;
+        let record_count = new_records.len() as u32;
+        compute_pass.set_pipeline(&bin_pipeline);
+        // the shader loops over the records that don't fit.
+        compute_pass.dispatch_workgroups(
+            record_count
+                .div_ceil(bin_workgroup_size)
+                .clamp(1, device.limits().max_compute_workgroups_per_dimension),
+            1,
+            1,
+        );
+        compute_pass.set_pipeline(&convolve_rows_pipeline);
+        compute_pass.dispatch_workgroups(
+            grid.width.div_ceil(convolve_workgroup_width),
+            convolved_height.div_ceil(convolve_workgroup_height),
+            1,
+        );
+        compute_pass.set_pipeline(&convolve_columns_pipeline);
+        compute_pass.dispatch_workgroups(
+            grid.width.div_ceil(convolve_workgroup_width),
+            grid.height.div_ceil(convolve_workgroup_height),
+            1,
+        );
+        self.max_norm.record(
+            device,
+            &mut compute_pass,
+            &compute_output_buffer,
+            grid.width * grid.height,
+        );
         // I dont understand precisely why, but this is required.
         // It must do some management in the drop impl.
         // The drop impl is in [`wgpu::ComputePassInner`].
         drop(compute_pass);
         let compute_buffer = compute_encoder.finish();
-        queue.submit([compute_buffer]);
-        // asyncify the callback from read_buffer
-        // Doing this to allow for backpressure and to abort when a newer callback was already started
-        let (buffer_tx, buffer_rx) = oneshot::channel();
+        let submission_index = queue.submit([compute_buffer]);
+        // The queue runs the next task after this one, so it can already add to the bins.
+        *self
+            .accumulator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Accumulator {
+            bins: bins_buffer,
+            grid,
+            kernel: self.kde.kernel,
+            target: self.target.clone(),
+            binned_len: history.len(),
+            last_binned: history.last().copied().unwrap_or_default(),
+        });
+        // asyncify the callback from on_submitted_work_done
+        // Doing this to allow for backpressure, the next task only starts once the GPU finished this one.
+        let (done_tx, done_rx) = oneshot::channel();
         // Debugging lack of callback getting called:
-        // This does also use queue.submit, however its used before an BufferSlice::map_async.
-        // If one reads BufferSlice::map_async, then it requires either an queue.submit, an instance.poll or an device.poll
+        // Like BufferSlice::map_async, this requires either an queue.submit, an instance.poll or an device.poll
         // afterwards, to complete the callback:
 
         // > For the callback to complete, either queue.submit(..), instance.poll_all(..), or device.poll(..) must be called elsewhere in the runtime, possibly integrated into an event loop or run on a separate thread.
         // > The callback will be called on the thread that first calls the above functions after the gpu work has completed. There are no restrictions on the code you can run in the callback, however on native the call to the function will not complete until the callback returns, so prefer keeping callbacks short and used to set flags, send messages, etc.
 
-        // I'm not ENTIRELY sure what 'into an event loop or run on a separate thread.' means, does that mean I cant do that on this very thread that called map_async?
-        // Regarding 'The callback will be called on the thread that first calls the above functions after the gpu work has completed', this, to me, has a different connotation to the original ask.
-        // I get the impression that these functions might be required to be called after the GPU is finished, not at any time.
-        // Re: 'however on native the call to the function will not complete until the callback returns' makes sense, so on native that call would ensure backpressure. But not on the web? That might be annoying...
         // On the web I might otherwise get a deadlock when using a channel to wait here, while also calling poll here.
-        // So perhaps I have to use some global variable... But thats also annoying, as that might lead to race conditions, if I'm not careful
-        // (should not with how I intend to do things right now, but still).
-        //
-        // Also note that I think I need to submit the compute queue first (as-is), otherwise the encoder.copy_buffer_to_buffer will be in the queue before the compute.
-        wgpu::util::DownloadBuffer::read_buffer(device, queue, &compute_output_buffer.slice(..), {
+        queue.on_submitted_work_done({
             let current_span = tracing::Span::current();
-            move |val| {
+            move || {
                 let _guard = current_span.enter();
                 tracing::info!("Executing callback");
-                let val = val.unwrap();
-                let val: &[f32] = bytemuck::cast_slice(&val);
-                let val = val.to_vec();
-                if let Err(_err) = buffer_tx.send(val) {
+                if let Err(_err) = done_tx.send(()) {
                     tracing::info!("Failed send on closed channel");
                 }
             }
@@ -468,26 +681,147 @@ impl GpuTask for ComputeTask {
         // run this GPU task.
         #[cfg(not(target_arch = "wasm32"))]
         tokio::task::spawn_blocking(move || {
-            // TODO: this will do for now, in the future it might be problematic.
-            // Potential alternatives I considered:
-            // 1. Patching (and upstreaming) changes to DownloadBuffer::read_buffer to return SubmitIndex from the copy op subm_idx. then doing wgpu::Maintain::WaitForSubmissionIndex(subm_idx),
-            // 2. calling poll in an event loop somewhere else, however I don't want that to end in busy waiting, so perhaps add a sleep inbetween, that however increases lag...
-            // Also see <github link TODO>
+            // The device is shared with the rendering, which polls it as well,
+            // so this only waits for this submission instead of whatever was submitted last.
             device_arc
-                .poll(wgpu::PollType::wait_indefinitely())
+                .poll(wgpu::PollType::Wait {
+                    submission_index: Some(submission_index),
+                    timeout: None,
+                })
                 .unwrap();
-            // device_arc.poll(wgpu::Maintain::WaitForSubmissionIndex(subm_idx));
         });
 
-        let result_buf = buffer_rx
+        done_rx
             .await
             .expect("embedding ought to avoid drop of channel");
-        self.result_tx
-            .take()
-            .unwrap()
-            .send(result_buf)
-            .map_err(|_val| ())
+        // No need to read it back, it's on the device the fragment shader runs on.
+        self.results_tx
+            .send(Some(compute_output_buffer))
+            .map_err(|_err| "Channel Closed")
             .unwrap();
+        self.refresh_token.notify_waiters();
         tracing::info!("Finishing");
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use egui::{Pos2, Vec2};
+    use tokio::sync::{Notify, watch};
+
+    use super::{ComputeGrid, ComputeTask, MaxNorm, binning_grid};
+    use crate::{
+        helpers::GpuTask,
+        kde::{self, Kernel},
+        simulation::random_walk_metropolis_hastings::{AcceptRecord, Rwmh},
+        visualizations::{
+            KdeParams, View,
+            shader_based::{headless_device, read_back},
+        },
+    };
+
+    #[tokio::test]
+    #[ignore = "needs a GPU adapter"]
+    async fn shader_agrees_with_binned_density() {
+        let (device, queue) = headless_device().expect("needs a GPU adapter");
+        let (device, queue) = (Arc::new(device), Arc::new(queue));
+        let max_norm = Arc::new(MaxNorm::new(&device));
+        // not square, so swapped coordinates show.
+        let resolution = [64.0, 48.0];
+        let view = View {
+            center: [0.1, -0.2],
+            half_extent: 1.2,
+        };
+        let records: Vec<AcceptRecord> = (0u16..300)
+            .map(|idx| AcceptRecord {
+                position: [
+                    f32::from(idx * 37 % 101) / 40.0 - 1.2,
+                    f32::from(idx * 53 % 97) / 40.0 - 1.4,
+                ],
+                remain_count: u32::from(idx % 3),
+                _pad: [0],
+            })
+            .collect();
+        for &kernel in Kernel::VARIANTS {
+            let kde = KdeParams {
+                kernel,
+                bandwidth: 0.15,
+            };
+            let accumulator = Arc::default();
+            let (results_tx, results_rx) = watch::channel(None);
+            // the second one only bins the records added since the first.
+            for len in [150, records.len()] {
+                let algo =
+                    Rwmh::from_records(records.iter().copied().take(len)).expect("has records");
+                let expected = kde::binned_density(
+                    &algo,
+                    kernel,
+                    kde.bandwidth,
+                    &binning_grid(resolution, &view),
+                );
+                let mut task = ComputeTask {
+                    resolution,
+                    view,
+                    kde,
+                    algo_state: Arc::new(algo),
+                    target: Vec::new(),
+                    accumulator: Arc::clone(&accumulator),
+                    max_norm: Arc::clone(&max_norm),
+                    results_tx: results_tx.clone(),
+                    refresh_token: Arc::new(Notify::new()),
+                };
+                task.run(Arc::clone(&device), Arc::clone(&queue)).await;
+                let output = results_rx.borrow().clone().expect("task sends its result");
+                let computed = read_back(&device, &queue, &output);
+
+                // the task normalizes by the maximum.
+                let max = expected.iter().copied().fold(0.0, f64::max);
+                assert_eq!(computed.len(), expected.len());
+                for (idx, (gpu, cpu)) in computed.into_iter().zip(expected).enumerate() {
+                    let cpu = cpu / max;
+                    assert!(
+                        (f64::from(gpu) - cpu).abs() < 1e-4,
+                        "{kernel:?} with {len} records, at {idx}: {gpu} != {cpu}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn grid_covers_wide_canvases() {
+        let view = View {
+            center: [1.0, -2.0],
+            half_extent: 3.0,
+        };
+        for size in [
+            Vec2::new(1600.0, 800.0),
+            Vec2::new(3000.0, 500.0),
+            Vec2::new(300.0, 1000.0),
+        ] {
+            let covered = ComputeGrid::default().view(&view, size.into());
+            for corner in [Pos2::ZERO, size.to_pos2()] {
+                let offset = view.canvas_coord_to_ndc(corner, size) - Pos2::from(covered.center);
+                assert!(
+                    offset.abs().max_elem() <= covered.half_extent * (1.0 + f32::EPSILON),
+                    "{size:?}: {offset:?}"
+                );
+            }
+        }
+        // both need the grid to reach 4 times as far as the shown square.
+        assert_eq!(
+            ComputeGrid::default().view(&view, [1700.0, 800.0]),
+            ComputeGrid::default().view(&view, [3000.0, 800.0])
+        );
+    }
+
+    #[test]
+    fn stored_cells_are_clamped() {
+        let [most, _] = ComputeGrid { cells: u32::MAX }.resolution();
+        assert!((most - ComputeGrid::MAX_CELLS as f32).abs() < f32::EPSILON);
+        let [fewest, _] = ComputeGrid { cells: 0 }.resolution();
+        assert!((fewest - ComputeGrid::MIN_CELLS as f32).abs() < f32::EPSILON);
+    }
+}
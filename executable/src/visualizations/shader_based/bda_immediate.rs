@@ -10,13 +10,15 @@ use wgpu::{
 
 use crate::{
     create_shader_module, profile_scope,
-    simulation::random_walk_metropolis_hastings::Rwmh,
-    target_distr,
+    simulation::random_walk_metropolis_hastings::{AcceptRecord, Rwmh},
+    target_distr::{self, NormalDistribution},
     visualizations::{
-        AlgoPainter,
+        AlgoPainter, ColorScale, KdeParams, KdeSettings, View,
         shader_based::{
-            resolution_uniform::get_resolution_buffer,
-            target_distr::{get_normaldistr_buffer, shader_bindings::NormalDistribution},
+            color_uniform::{color_map_options, get_color_map_buffer},
+            kde_uniform::get_kde_buffer,
+            resolution_uniform::{get_resolution_buffer, resolution_info},
+            target_distr::get_normaldistr_buffer,
         },
     },
 };
@@ -26,19 +28,26 @@ use super::fullscreen_quad;
 create_shader_module!("diff_display.fragment");
 
 use shader_bindings::{
-    BindGroupLayout0, BindGroupLayout1, RWMHAcceptRecord, RWMHCountInfo, ResolutionInfo,
-    bind_groups::{BindGroup0, BindGroup1},
+    BindGroupLayout0, BindGroupLayout1, BindGroupLayout2, RWMHCountInfo,
+    bind_groups::{BindGroup0, BindGroup1, BindGroup2},
 };
 
+// The history is uploaded as is, but its type lives in the GUI-free `sampling` crate.
+const _: () = assert!(
+    size_of::<AcceptRecord>() == size_of::<shader_bindings::RWMHAcceptRecord>()
+        && align_of::<AcceptRecord>() == align_of::<shader_bindings::RWMHAcceptRecord>()
+);
+
 #[cfg_persistence_derive]
 #[derive(Default)]
 pub struct BDADiff {
-    // pub window_radius: f32,
+    #[serde(default)]
+    pub kde: KdeSettings,
 }
 
 pub fn get_approx_buffers(
     device: &wgpu::Device,
-    approx_points: Option<&[RWMHAcceptRecord]>,
+    approx_points: Option<&[AcceptRecord]>,
 ) -> (wgpu::Buffer, wgpu::Buffer) {
     let webgpu_debug_name = Some(file!());
 
@@ -72,7 +81,10 @@ pub struct PipelineStateHolder {
     pipeline: RenderPipeline,
     bind_group_0: shader_bindings::bind_groups::BindGroup0,
     bind_group_1: shader_bindings::bind_groups::BindGroup1,
+    bind_group_2: BindGroup2,
     resolution_buffer: Buffer,
+    color_map_buffer: Buffer,
+    kde_buffer: Buffer,
     target_buffer: Buffer,
     approx_accepted_buffer: Buffer,
     approx_info_buffer: Buffer,
@@ -85,13 +97,18 @@ impl AlgoPainter for BDADiff {
         rect: egui::Rect,
         algo: std::sync::Arc<Rwmh>,
         target: &target_distr::Gaussian,
+        view: &View,
+        colors: &ColorScale,
     ) {
         painter.add(eframe::egui_wgpu::Callback::new_paint_callback(
             rect,
             RenderCall {
-                algo_state: algo.clone(),
+                kde: self.kde.params(&algo),
+                algo_state: algo,
                 px_size: rect.size().into(),
                 targets: target.gaussians.clone(),
+                view: *view,
+                colors: *colors,
             },
         ));
     }
@@ -126,6 +143,7 @@ impl PipelineStateHolder {
         });
 
         let resolution_buffer = get_resolution_buffer(device);
+        let color_map_buffer = get_color_map_buffer(device);
 
         let normdistr_buffer = get_normaldistr_buffer(device, None);
 
@@ -135,6 +153,7 @@ impl PipelineStateHolder {
             device,
             BindGroupLayout0 {
                 resolution_info: resolution_buffer.as_entire_buffer_binding(),
+                color_map: color_map_buffer.as_entire_buffer_binding(),
             },
         );
 
@@ -147,6 +166,15 @@ impl PipelineStateHolder {
             },
         );
 
+        let kde_buffer = get_kde_buffer(device);
+
+        let bind_group_2 = BindGroup2::from_bindings(
+            device,
+            BindGroupLayout2 {
+                kde_options: kde_buffer.as_entire_buffer_binding(),
+            },
+        );
+
         // Because the graphics pipeline must have the same lifetime as the egui render pass,
         // instead of storing the pipeline in our struct, we insert it into the
         // `callback_resources` type map, which is stored alongside the render pass.
@@ -154,7 +182,10 @@ impl PipelineStateHolder {
             pipeline,
             bind_group_0,
             bind_group_1,
+            bind_group_2,
             resolution_buffer,
+            color_map_buffer,
+            kde_buffer,
             target_buffer: normdistr_buffer,
             approx_accepted_buffer,
             approx_info_buffer,
@@ -166,6 +197,9 @@ struct RenderCall {
     px_size: [f32; 2],
     targets: Vec<NormalDistribution>,
     algo_state: Arc<Rwmh>,
+    view: View,
+    kde: KdeParams,
+    colors: ColorScale,
 }
 
 impl CallbackTrait for RenderCall {
@@ -180,6 +214,8 @@ impl CallbackTrait for RenderCall {
     ) -> Vec<wgpu::CommandBuffer> {
         let &mut PipelineStateHolder {
             ref resolution_buffer,
+            ref color_map_buffer,
+            ref kde_buffer,
             ref mut target_buffer,
             ref mut approx_accepted_buffer,
             ref mut approx_info_buffer,
@@ -216,10 +252,18 @@ impl CallbackTrait for RenderCall {
         queue.write_buffer(
             resolution_buffer,
             0,
-            bytemuck::cast_slice(&[ResolutionInfo {
-                resolution: self.px_size,
-                _pad: [0.0; 2],
-            }]),
+            bytemuck::cast_slice(&[resolution_info(self.px_size, &self.view)]),
+        );
+        queue.write_buffer(
+            color_map_buffer,
+            0,
+            bytemuck::cast_slice(&[color_map_options(&self.colors)]),
+        );
+        // evaluated exactly for each pixel, nothing is binned.
+        queue.write_buffer(
+            kde_buffer,
+            0,
+            bytemuck::cast_slice(&[self.kde.uniform(0, 0)]),
         );
         queue.write_buffer(
             target_buffer,
@@ -250,11 +294,13 @@ impl CallbackTrait for RenderCall {
             ref pipeline,
             ref bind_group_0,
             ref bind_group_1,
+            ref bind_group_2,
             ..
         } = callback_resources.get().unwrap();
         render_pass.set_pipeline(pipeline);
         bind_group_0.set(render_pass);
         bind_group_1.set(render_pass);
+        bind_group_2.set(render_pass);
         render_pass.draw(0..fullscreen_quad::NUM_VERTICES, 0..1);
     }
 }
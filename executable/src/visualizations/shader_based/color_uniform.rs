@@ -0,0 +1,32 @@
+use wgpu::util::DeviceExt;
+
+use crate::visualizations::{ColorMap, ColorScale, ColorSettings, Scaling};
+
+// Shared by all shaders that import `color_map.wgsl`, like the resolution uniform.
+use super::target_distr::shader_bindings::{ColorMapOptions, MAX_COLOR_STOPS};
+
+const _: () = assert!(ColorMap::MAX_STOPS == MAX_COLOR_STOPS as usize);
+
+/// The uniform contents, the stops past the end of the map are unused.
+pub fn color_map_options(scale: &ColorScale) -> ColorMapOptions {
+    let map_stops = scale.map.stops();
+    let mut stops = [[0.0; 4]; MAX_COLOR_STOPS as usize];
+    for (stop, &[r, g, b]) in stops.iter_mut().zip(map_stops) {
+        *stop = [r, g, b, u8::MAX].map(|channel| f32::from(channel) / 255.0);
+    }
+    ColorMapOptions {
+        stops,
+        stop_count: map_stops.len().min(MAX_COLOR_STOPS as usize) as u32,
+        scaling: scale.scaling.shader_id(),
+        log_decades: Scaling::LOG_DECADES,
+        _pad: 0.0,
+    }
+}
+
+pub fn get_color_map_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(file!()),
+        contents: bytemuck::cast_slice(&[color_map_options(ColorSettings::default().scale(false))]),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+    })
+}
@@ -0,0 +1,42 @@
+use wgpu::util::DeviceExt;
+
+use crate::kde::Kernel;
+
+// Shared by all shaders that import `binary_distance_approx.wgsl`, like the resolution uniform.
+use super::bda_compute::BinaryDistanceApproxOptions;
+
+/// What the shaders need to evaluate the kernel density estimate, with the bandwidth already chosen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KdeParams {
+    pub kernel: Kernel,
+    pub bandwidth: f32,
+}
+
+impl Default for KdeParams {
+    fn default() -> Self {
+        Self {
+            kernel: Kernel::default(),
+            bandwidth: 0.1,
+        }
+    }
+}
+
+impl KdeParams {
+    /// `margin` and `padding` are only used by the binned estimate, see [`crate::kde::Grid::margin`].
+    pub const fn uniform(&self, margin: u32, padding: u32) -> BinaryDistanceApproxOptions {
+        BinaryDistanceApproxOptions {
+            bandwidth: self.bandwidth,
+            kernel: self.kernel.shader_id(),
+            margin,
+            padding,
+        }
+    }
+}
+
+pub fn get_kde_buffer(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(file!()),
+        contents: bytemuck::cast_slice(&[KdeParams::default().uniform(0, 0)]),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+    })
+}
@@ -0,0 +1,207 @@
+//! Normalizes the compute output by its maximum without it leaving the GPU.
+
+use wgpu::{ComputePipelineDescriptor, util::DeviceExt};
+
+use crate::{create_shader_module, definition_location};
+
+create_shader_module!("max_norm.compute", mod shader_bindings);
+
+use shader_bindings::{ReductionPass, compute::REDUCE_MAX_WORKGROUP_SIZE};
+
+/// The passes of the reduction, see `max_norm.compute.wgsl`.
+struct Plan {
+    reductions: Vec<ReductionPass>,
+    normalization: ReductionPass,
+    /// Of all passes together.
+    maxima_len: u32,
+}
+
+impl Plan {
+    /// Each pass reduces chunks of `workgroup_size` values to one, until one is left.
+    fn new(len: u32, workgroup_size: u32) -> Self {
+        let mut reductions = Vec::new();
+        let mut pass = ReductionPass {
+            from_output: 1,
+            input_offset: 0,
+            input_len: len,
+            output_offset: 0,
+        };
+        loop {
+            reductions.push(pass);
+            let output_len = pass.input_len.div_ceil(workgroup_size).max(1);
+            if output_len == 1 {
+                break;
+            }
+            pass = ReductionPass {
+                from_output: 0,
+                input_offset: pass.output_offset,
+                input_len: output_len,
+                output_offset: pass.output_offset + output_len,
+            };
+        }
+        Self {
+            normalization: ReductionPass {
+                from_output: 1,
+                input_offset: 0,
+                input_len: len,
+                output_offset: pass.output_offset,
+            },
+            maxima_len: pass.output_offset + 1,
+            reductions,
+        }
+    }
+}
+
+/// The pipelines of the reduction, created once on the device the compute output is on.
+#[derive(Debug)]
+pub struct MaxNorm {
+    reduce: wgpu::ComputePipeline,
+    normalize: wgpu::ComputePipeline,
+}
+
+impl MaxNorm {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = shader_bindings::create_pipeline_layout(device);
+        let module = shader_bindings::create_shader_module(device);
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                label: Some(definition_location!()),
+                layout: Some(&layout),
+                cache: None,
+            })
+        };
+        Self {
+            reduce: create_pipeline("reduce_max"),
+            normalize: create_pipeline("normalize"),
+        }
+    }
+
+    /// Records normalizing the first `len` values of `values` into `compute_pass`, after what was recorded before.
+    pub fn record(
+        &self,
+        device: &wgpu::Device,
+        compute_pass: &mut wgpu::ComputePass<'_>,
+        values: &wgpu::Buffer,
+        len: u32,
+    ) {
+        let [workgroup_size, ..] = REDUCE_MAX_WORKGROUP_SIZE;
+        let plan = Plan::new(len, workgroup_size);
+
+        // zeroed on creation.
+        let maxima = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(definition_location!()),
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+            size: u64::from(plan.maxima_len) * size_of::<f32>() as u64,
+        });
+        shader_bindings::BindGroup1::from_bindings(
+            device,
+            shader_bindings::BindGroupLayout1 {
+                compute_output: values.as_entire_buffer_binding(),
+                maxima: maxima.as_entire_buffer_binding(),
+            },
+        )
+        .set(compute_pass);
+
+        let max_per_dimension = device.limits().max_compute_workgroups_per_dimension;
+        let mut dispatch = |pipeline: &wgpu::ComputePipeline, pass: ReductionPass| {
+            let pass_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(definition_location!()),
+                contents: bytemuck::cast_slice(&[pass]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            shader_bindings::BindGroup0::from_bindings(
+                device,
+                shader_bindings::BindGroupLayout0 {
+                    reduction_pass: pass_buffer.as_entire_buffer_binding(),
+                },
+            )
+            .set(compute_pass);
+            compute_pass.set_pipeline(pipeline);
+            // the shaders skip the workgroups past the end.
+            let workgroups = pass.input_len.div_ceil(workgroup_size).max(1);
+            let width = workgroups.min(max_per_dimension);
+            compute_pass.dispatch_workgroups(width, workgroups.div_ceil(width), 1);
+        };
+        for &pass in &plan.reductions {
+            dispatch(&self.reduce, pass);
+        }
+        dispatch(&self.normalize, plan.normalization);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wgpu::util::DeviceExt;
+
+    use super::{MaxNorm, Plan, REDUCE_MAX_WORKGROUP_SIZE};
+    use crate::visualizations::shader_based::{headless_device, read_back};
+
+    /// How the values used to be normalized on the CPU, after reading them back.
+    fn normalized_with_rayon(mut values: Vec<f32>) -> Vec<f32> {
+        use rayon::prelude::*;
+        let max = *values
+            .par_iter()
+            .max_by(|lhs, rhs| lhs.total_cmp(rhs))
+            .unwrap_or(&1.0);
+        values
+            .par_iter_mut()
+            .for_each(|unnorm_prob| *unnorm_prob /= max);
+        values
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn shader_agrees_with_rayon() {
+        let (device, queue) = headless_device().expect("needs a GPU adapter");
+        let max_norm = MaxNorm::new(&device);
+        let [workgroup_size, ..] = REDUCE_MAX_WORKGROUP_SIZE;
+        for len in [
+            1,
+            workgroup_size - 1,
+            workgroup_size,
+            workgroup_size + 1,
+            workgroup_size * workgroup_size + 3,
+            300_000,
+        ] {
+            let values: Vec<f32> = (1..=len)
+                .map(|idx| ((idx * 7919) % 1009) as f32 / 7.0)
+                .collect();
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&values),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            });
+            let mut encoder = device.create_command_encoder(&Default::default());
+            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+            max_norm.record(&device, &mut compute_pass, &buffer, len);
+            drop(compute_pass);
+            queue.submit([encoder.finish()]);
+            let normalized = read_back(&device, &queue, &buffer);
+            let expected = normalized_with_rayon(values);
+            assert_eq!(normalized.len(), expected.len());
+            for (idx, (gpu, cpu)) in normalized.into_iter().zip(expected).enumerate() {
+                assert!(
+                    (gpu - cpu).abs() < 1e-6,
+                    "{len} values, at {idx}: {gpu} != {cpu}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn passes_dont_overlap() {
+        let plan = Plan::new(1_000_000, 256);
+        assert_eq!(plan.reductions.len(), 3);
+        for pair in plan.reductions.windows(2) {
+            if let &[ref previous, ref next] = pair {
+                assert_eq!(next.input_offset, previous.output_offset);
+                assert_eq!(next.output_offset, next.input_offset + next.input_len);
+            }
+        }
+        assert_eq!(plan.normalization.output_offset + 1, plan.maxima_len);
+    }
+}
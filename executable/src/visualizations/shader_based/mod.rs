@@ -1,16 +1,18 @@
 mod bda_compute;
 mod bda_immediate;
+mod color_uniform;
+mod kde_uniform;
+mod max_norm;
 mod resolution_uniform;
 mod target_distr;
 
 pub use bda_compute::{
     BDAComputeDiff, ComputeTask as BdaComputeTask, PipelineStateHolder as BdaComputeState,
 };
-pub use bda_immediate::{
-    BDADiff, PipelineStateHolder as BDADiffState, shader_bindings::RWMHAcceptRecord,
-};
+pub use bda_immediate::{BDADiff, PipelineStateHolder as BDADiffState};
+pub use kde_uniform::KdeParams;
 pub use resolution_uniform::INITIAL_RENDER_SIZE;
-pub use target_distr::{NormalDistribution, PipelineStateHolder as MMGState, TargetDistribution};
+pub use target_distr::{PipelineStateHolder as MMGState, TargetDistribution};
 
 #[macro_export]
 macro_rules! create_shader_module {
@@ -51,3 +53,43 @@ macro_rules! create_shader_module {
 }
 
 create_shader_module!("fullscreen_quad.vertex", mod fullscreen_quad; no redefine);
+
+/// Whatever adapter there is without a window, `None` if there's none.
+///
+/// The tests using it are ignored, as CI has no GPU, run them with `cargo test -- --ignored`.
+#[cfg(test)]
+fn headless_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    futures::executor::block_on(async {
+        let adapter = wgpu::Instance::default()
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .ok()?;
+        adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .ok()
+    })
+}
+
+/// Copies `buffer`, which needs to allow that, back to the CPU.
+#[cfg(test)]
+fn read_back(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> Vec<f32> {
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(crate::definition_location!()),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+        size: buffer.size(),
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit([encoder.finish()]);
+    let slice = staging.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |mapped| mapped.unwrap());
+    device
+        .poll(wgpu::PollType::Wait {
+            submission_index: None,
+            timeout: None,
+        })
+        .unwrap();
+    bytemuck::cast_slice(&slice.get_mapped_range()).to_vec()
+}
@@ -7,20 +7,25 @@ use wgpu::util::DeviceExt;
 // Sadly I could not find a way to structure the files in such a way that I could make this easy to tell.
 // Rusts nominal type-checking is also none-the-wiser, since the generic wgpu types for buffer and bindgroup erase this info.
 use super::bda_compute::ResolutionInfo;
+use crate::visualizations::View;
 
 pub const INITIAL_RENDER_SIZE: [f32; 2] = [640.0, 480.0];
 
-fn create_buffer_init_descr() -> wgpu::util::BufferInitDescriptor<'static> {
-    wgpu::util::BufferInitDescriptor {
-        label: Some(file!()),
-        contents: bytemuck::cast_slice(&[ResolutionInfo {
-            resolution: INITIAL_RENDER_SIZE,
-            _pad: [0.0; 2],
-        }]),
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+/// The uniform contents, the same for all shaders that import `canvas_ndc_conversion.wgsl`.
+pub const fn resolution_info(resolution: [f32; 2], view: &View) -> ResolutionInfo {
+    ResolutionInfo {
+        resolution,
+        view_center: view.center,
+        view_half_extent: view.half_extent,
+        _pad: 0.0,
+        _pad_2: [0.0; 2],
     }
 }
 
 pub fn get_resolution_buffer(device: &wgpu::Device) -> wgpu::Buffer {
-    device.create_buffer_init(&create_buffer_init_descr())
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(file!()),
+        contents: bytemuck::cast_slice(&[resolution_info(INITIAL_RENDER_SIZE, &View::default())]),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+    })
 }
@@ -9,36 +9,46 @@ use wgpu::{
 };
 
 use crate::{
-    definition_location, simulation::random_walk_metropolis_hastings::Rwmh, target_distr,
-    visualizations::AlgoPainter,
+    definition_location,
+    simulation::random_walk_metropolis_hastings::Rwmh,
+    target_distr::{self, NormalDistribution},
+    visualizations::{AlgoPainter, ColorScale, View},
 };
 
-use super::{fullscreen_quad, resolution_uniform::get_resolution_buffer};
+use super::{
+    color_uniform::{color_map_options, get_color_map_buffer},
+    fullscreen_quad,
+    resolution_uniform::{get_resolution_buffer, resolution_info},
+};
 
 use crate::create_shader_module;
 
 create_shader_module!("multimodal_gaussian.fragment");
 
 use shader_bindings::{
-    BindGroupLayout0, BindGroupLayout1, ResolutionInfo,
+    BindGroupLayout0, BindGroupLayout1,
     bind_groups::{BindGroup0, BindGroup1},
 };
 
-pub use shader_bindings::NormalDistribution;
+// The elements are uploaded as is, but their type lives in the GUI-free `sampling` crate.
+const _: () = assert!(
+    size_of::<NormalDistribution>() == size_of::<shader_bindings::NormalDistribution>()
+        && align_of::<NormalDistribution>() == align_of::<shader_bindings::NormalDistribution>()
+);
 
 pub struct PipelineStateHolder {
     pipeline: RenderPipeline,
     bind_group_0: BindGroup0,
     bind_group_1: BindGroup1,
     resolution_buffer: Buffer,
+    color_map_buffer: Buffer,
     target_buffer: Buffer,
 }
 
+/// Colored by [`crate::visualizations::ColorSettings::density`].
 #[cfg_persistence_derive]
 #[derive(Default)]
-pub struct TargetDistribution {
-    // pub color: Color32,
-}
+pub struct TargetDistribution {}
 
 impl AlgoPainter for TargetDistribution {
     fn paint(
@@ -47,12 +57,16 @@ impl AlgoPainter for TargetDistribution {
         rect: egui::Rect,
         _algo: std::sync::Arc<Rwmh>,
         target: &target_distr::Gaussian,
+        view: &View,
+        colors: &ColorScale,
     ) {
         painter.add(eframe::egui_wgpu::Callback::new_paint_callback(
             rect,
             RenderCall {
                 px_size: rect.size().into(),
                 elements: target.gaussians.clone(),
+                view: *view,
+                colors: *colors,
             },
         ));
     }
@@ -115,6 +129,7 @@ impl PipelineStateHolder {
         });
 
         let resolution_buffer = get_resolution_buffer(device);
+        let color_map_buffer = get_color_map_buffer(device);
 
         let normdistr_buffer = get_normaldistr_buffer(device, None);
 
@@ -122,6 +137,7 @@ impl PipelineStateHolder {
             device,
             BindGroupLayout0 {
                 resolution_info: resolution_buffer.as_entire_buffer_binding(),
+                color_map: color_map_buffer.as_entire_buffer_binding(),
             },
         );
 
@@ -136,6 +152,7 @@ impl PipelineStateHolder {
             bind_group_0,
             bind_group_1,
             resolution_buffer,
+            color_map_buffer,
             target_buffer: normdistr_buffer,
         }
     }
@@ -144,6 +161,8 @@ impl PipelineStateHolder {
 struct RenderCall {
     px_size: [f32; 2],
     elements: Vec<NormalDistribution>,
+    view: View,
+    colors: ColorScale,
 }
 
 impl CallbackTrait for RenderCall {
@@ -158,6 +177,7 @@ impl CallbackTrait for RenderCall {
     ) -> Vec<wgpu::CommandBuffer> {
         let &mut PipelineStateHolder {
             ref mut resolution_buffer,
+            ref color_map_buffer,
             ref mut target_buffer,
             ref mut bind_group_1,
             ..
@@ -170,10 +190,12 @@ impl CallbackTrait for RenderCall {
         queue.write_buffer(
             resolution_buffer,
             0,
-            bytemuck::cast_slice(&[ResolutionInfo {
-                resolution: self.px_size,
-                _pad: [0.0; 2],
-            }]),
+            bytemuck::cast_slice(&[resolution_info(self.px_size, &self.view)]),
+        );
+        queue.write_buffer(
+            color_map_buffer,
+            0,
+            bytemuck::cast_slice(&[color_map_options(&self.colors)]),
         );
         queue.write_buffer(
             target_buffer,
@@ -0,0 +1,199 @@
+use egui::{Pos2, Vec2};
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+/// Limits for zooming, in units of the sample space.
+const MIN_HALF_EXTENT: f32 = 1e-4;
+const MAX_HALF_EXTENT: f32 = 1e4;
+
+/// Fitting leaves this much room around what it fits, relative to its size.
+const FIT_MARGIN: f32 = 1.1;
+
+/// [`View::fitted_to_density`] searches this many times the shown area, on a grid with this many cells per side.
+const DENSITY_SEARCH_ZOOM_OUT: f32 = 4.0;
+const DENSITY_SEARCH_CELLS: u16 = 64;
+/// Log of the fraction of the highest density found, above which a cell is shown.
+const DENSITY_SEARCH_THRESHOLD: f64 = -4.6;
+
+/// The part of the sample space shown on the canvas.
+///
+/// The largest square in the middle of the canvas shows [`Self::center`] ± [`Self::half_extent`] in both coordinates,
+/// the default shows the unit square [-1, 1]².
+/// The shaders get this through the resolution uniform, see `canvas_ndc_conversion.wgsl`.
+///
+/// Positions in the sample space are called NDC throughout, as they used to be the normalized device coordinates.
+#[cfg_persistence_derive]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct View {
+    pub center: [f32; 2],
+    pub half_extent: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            center: [0.0; 2],
+            half_extent: 1.0,
+        }
+    }
+}
+
+impl View {
+    pub fn ndc_to_canvas_coord(&self, ndc: Pos2, canvas_size: Vec2) -> Pos2 {
+        let in_view = (ndc - Pos2::from(self.center)) / self.half_extent;
+        let center = (canvas_size - Vec2::splat(canvas_size.min_elem())) / 2.0;
+        ((in_view + Vec2::splat(1.0)) / 2.0 * canvas_size.min_elem() + center).to_pos2()
+    }
+
+    pub fn canvas_coord_to_ndc(&self, canvas_coord: Pos2, canvas_size: Vec2) -> Pos2 {
+        let center = (canvas_size - Vec2::splat(canvas_size.min_elem())) / 2.0;
+        let in_view =
+            (((canvas_coord - center) / canvas_size.min_elem()) * 2.0 - Vec2::splat(1.0)).to_vec2();
+        Pos2::from(self.center) + in_view * self.half_extent
+    }
+
+    /// Canvas points per unit of the sample space.
+    pub fn scale(&self, canvas_size: Vec2) -> f32 {
+        canvas_size.min_elem() / 2.0 / self.half_extent
+    }
+
+    /// Moves the shown part along with a drag of `canvas_delta` on the canvas.
+    pub fn pan(&mut self, canvas_delta: Vec2, canvas_size: Vec2) {
+        let delta = canvas_delta / self.scale(canvas_size);
+        let [ref mut x, ref mut y] = self.center;
+        *x -= delta.x;
+        *y -= delta.y;
+    }
+
+    /// Magnifies by `factor`, keeping what's at `anchor` on the canvas in place.
+    pub fn zoom(&mut self, factor: f32, anchor: Pos2, canvas_size: Vec2) {
+        let fixed = self.canvas_coord_to_ndc(anchor, canvas_size);
+        let half_extent = (self.half_extent / factor).clamp(MIN_HALF_EXTENT, MAX_HALF_EXTENT);
+        let center = fixed + (Pos2::from(self.center) - fixed) * (half_extent / self.half_extent);
+        self.center = center.into();
+        self.half_extent = half_extent;
+    }
+
+    /// Shows all of `points` with some margin.
+    ///
+    /// Keeps the zoom if they are all the same, and the view as is if there are none.
+    pub fn fitted(&self, points: impl IntoIterator<Item = [f32; 2]>) -> Self {
+        let Some(bounds) = points
+            .into_iter()
+            .map(|point| egui::Rect::from_min_max(point.into(), point.into()))
+            .reduce(egui::Rect::union)
+        else {
+            return *self;
+        };
+        let half_extent = bounds.size().max_elem() / 2.0 * FIT_MARGIN;
+        Self {
+            center: bounds.center().into(),
+            half_extent: if half_extent > 0.0 {
+                half_extent.clamp(MIN_HALF_EXTENT, MAX_HALF_EXTENT)
+            } else {
+                self.half_extent
+            },
+        }
+    }
+
+    /// Shows where `log_density` is close to the highest it gets around the shown part.
+    ///
+    /// For densities that aren't known in closed form, the search only covers a few times the shown area.
+    pub fn fitted_to_density(&self, log_density: impl Fn(AlgoVec) -> f64) -> Self {
+        let reach = self.half_extent * DENSITY_SEARCH_ZOOM_OUT;
+        let cell_size = 2.0 * reach / f32::from(DENSITY_SEARCH_CELLS);
+        let [center_x, center_y] = self.center;
+        let at = |idx: u16, center: f32| (f32::from(idx) + 0.5).mul_add(cell_size, center - reach);
+        let cells: Vec<([f32; 2], f64)> = (0..DENSITY_SEARCH_CELLS)
+            .flat_map(|row| {
+                (0..DENSITY_SEARCH_CELLS).map(move |col| [at(col, center_x), at(row, center_y)])
+            })
+            .map(|[x, y]| ([x, y], log_density(AlgoVec::new(x, y))))
+            .filter(|&(_, value)| value.is_finite())
+            .collect();
+        let max = cells
+            .iter()
+            .map(|&(_, value)| value)
+            .fold(f64::NEG_INFINITY, f64::max);
+        self.fitted(
+            cells
+                .into_iter()
+                .filter(|&(_, value)| value - max >= DENSITY_SEARCH_THRESHOLD)
+                .map(|(position, _)| position),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use egui::Pos2;
+
+    use super::View;
+    use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+    fn close_enough(Pos2 { x: x_1, y: y_1 }: Pos2, Pos2 { x: x_2, y: y_2 }: Pos2) -> bool {
+        ((x_1 - x_2).abs() < f32::EPSILON) && ((y_1 - y_2).abs() < f32::EPSILON)
+    }
+
+    #[test]
+    fn invert_each_other() {
+        let rect = egui::Rect {
+            min: [0.0, 0.0].into(),
+            max: [1920.0, 1080.0].into(),
+        };
+
+        let size = rect.size();
+        let view = View::default();
+
+        let start_canvas_coord = egui::Pos2::from([450.0, 670.0]);
+        let start_ndc = [-0.634, 0.232].into();
+
+        assert_eq!(
+            view.ndc_to_canvas_coord(view.canvas_coord_to_ndc(start_canvas_coord, size), size),
+            start_canvas_coord
+        );
+        assert!(close_enough(
+            view.canvas_coord_to_ndc(view.ndc_to_canvas_coord(start_ndc, size), size),
+            start_ndc
+        ));
+    }
+
+    #[test]
+    fn zooming_keeps_the_anchor_in_place() {
+        let size = egui::vec2(800.0, 600.0);
+        let anchor = Pos2::new(200.0, 150.0);
+        let mut view = View {
+            center: [3.0, -2.0],
+            half_extent: 5.0,
+        };
+        let before = view.canvas_coord_to_ndc(anchor, size);
+        view.zoom(4.0, anchor, size);
+        let after = view.canvas_coord_to_ndc(anchor, size);
+        assert!((before - after).length() < 1e-5, "{before:?} {after:?}");
+        assert!((view.half_extent - 1.25).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fitting_shows_all_points() {
+        let size = egui::vec2(800.0, 600.0);
+        let points = [[4.0, 10.0], [-2.0, 12.0], [1.0, 15.0]];
+        let view = View::default().fitted(points);
+        for point in points {
+            let canvas = view.ndc_to_canvas_coord(point.into(), size);
+            assert!(egui::Rect::from_min_size(Pos2::ZERO, size).contains(canvas));
+        }
+        assert_eq!(View::default().fitted([]), View::default());
+    }
+
+    #[test]
+    fn fitting_finds_a_density_off_the_view() {
+        let mode = AlgoVec::new(2.5, -1.5);
+        let view = View::default().fitted_to_density(|position| {
+            -f64::from((position - mode).norm_squared()) / (2.0 * 0.1)
+        });
+        let [x, y] = view.center;
+        assert!((AlgoVec::new(x, y) - mode).norm() < 0.1, "{view:?}");
+        assert!(view.half_extent < 2.0, "{view:?}");
+    }
+}
@@ -0,0 +1,560 @@
+//! Runs the sampler from the command line, without a window or a GPU.
+//!
+//! Meant for parameter studies on servers and in CI, everything the GUI uses a shader for is skipped.
+//! It only depends on the `sampling` crate, so building it doesn't pull in any GUI or GPU dependencies.
+//! See `mcmc-demo-headless --help` for the options.
+
+use std::{
+    fmt::Display,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+#[cfg(feature = "persistence")]
+use sampling::checkpoint::{self, Checkpoint};
+use sampling::{
+    export::{self, Chain, Format, Layout},
+    scenario::{Scenario, ValidationError},
+    simulation::{
+        DEFAULT_SEED, WrappedRngDiscriminants,
+        random_walk_metropolis_hastings::{AlgoParams, OutOfSupport, Rwmh},
+    },
+    target_distr::{CpuTarget, Dataset, Gaussian, HighDimModel, HighDimensional, Posterior},
+};
+
+const USAGE: &str = "\
+Usage: mcmc-demo-headless [OPTIONS]
+
+Options:
+  --scenario <PATH>        TOML scenario file, flags after it override its values
+  --target <NAME>          mixture (default), correlated-gaussian, funnel or posterior
+  --dim <N>                dimensions of correlated-gaussian and funnel [default: 10]
+  --data <PATH>            CSV file for the posterior target
+  --steps <N>              number of steps [default: 10000]
+  --sigma <SIGMA>          standard deviation of the proposal [default: 0.2]
+  --rng <NAME>             RNG for both proposal and acceptance, e.g. Pcg64Mcg
+  --seed <SEED>            seed for both RNGs [default: 42]
+  --out-of-support <MODE>  reject (default), reflect or transform
+  --output <DIR>           where to write the results [default: mcmc-output]
+  --format <FORMAT>        csv (default), jsonl, npy or arviz
+  --layout <LAYOUT>        run-length (default) or expanded, i.e. one row per iteration
+  --checkpoint <PATH>      write a checkpoint of the run there when it ends
+  --checkpoint-every <N>   also write the checkpoint every N steps
+  --resume <PATH>          continue a checkpointed run up to --steps in total,
+                           with its target and sampler instead of the options above
+  --help                   print this
+";
+
+#[derive(Debug)]
+pub enum RunError {
+    UnknownArgument(String),
+    MissingValue(&'static str),
+    InvalidValue {
+        flag: &'static str,
+        value: String,
+    },
+    /// `--target posterior` without `--data`.
+    MissingData,
+    Data(String),
+    Scenario {
+        path: PathBuf,
+        error: ValidationError,
+    },
+    /// Built without the `persistence` feature.
+    CheckpointsUnsupported,
+    Checkpoint {
+        path: PathBuf,
+        error: String,
+    },
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::UnknownArgument(ref arg) => write!(f, "unknown argument `{arg}`"),
+            Self::MissingValue(flag) => write!(f, "`{flag}` requires a value"),
+            Self::InvalidValue { flag, ref value } => {
+                write!(f, "invalid value `{value}` for `{flag}`")
+            }
+            Self::MissingData => write!(f, "the posterior target requires `--data`"),
+            Self::Data(ref error) => write!(f, "{error}"),
+            Self::Scenario {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {error}", path.display()),
+            Self::CheckpointsUnsupported => {
+                write!(f, "checkpoints need the `persistence` feature")
+            }
+            Self::Checkpoint {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {error}", path.display()),
+            Self::Io {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+/// Everything a single run needs, mirrors the target and sampler state of the GUI.
+struct RunConfig {
+    target_distr: Gaussian,
+    cpu_target: Option<CpuTarget>,
+    params: AlgoParams,
+    seed: u64,
+    steps: usize,
+    output: PathBuf,
+    format: Format,
+    layout: Layout,
+    checkpoint: Option<PathBuf>,
+    /// Never zero.
+    checkpoint_every: Option<usize>,
+    /// Continued instead of starting a new chain.
+    resumed: Option<Rwmh>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            target_distr: Default::default(),
+            cpu_target: None,
+            params: Default::default(),
+            seed: DEFAULT_SEED,
+            steps: 10_000,
+            output: "mcmc-output".into(),
+            format: Format::default(),
+            layout: Layout::default(),
+            checkpoint: None,
+            checkpoint_every: None,
+            resumed: None,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut config = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let start = config
+        .resumed
+        .take()
+        .unwrap_or_else(|| Rwmh::new(config.params.clone()));
+    let algo = match run(&config, start) {
+        Ok(algo) => algo,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let chain = Chain::new(&algo, config.cpu_target.as_ref());
+    let summary = Summary::new(&config, &chain);
+    print!("{summary}");
+    match write_outputs(&config, &chain, &summary) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns `None` if only the usage was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<RunConfig>, RunError> {
+    fn parsed<T: std::str::FromStr>(flag: &'static str, value: String) -> Result<T, RunError> {
+        value
+            .parse()
+            .map_err(|_| RunError::InvalidValue { flag, value })
+    }
+
+    let mut config = RunConfig::default();
+    let mut target = "mixture".to_owned();
+    let mut dim = 10;
+    let mut data = None;
+    let mut rng = None;
+    let mut resume = None;
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Ok(None);
+        }
+        let flag = match arg.as_str() {
+            "--scenario" => "--scenario",
+            "--target" => "--target",
+            "--dim" => "--dim",
+            "--data" => "--data",
+            "--steps" => "--steps",
+            "--sigma" => "--sigma",
+            "--rng" => "--rng",
+            "--seed" => "--seed",
+            "--out-of-support" => "--out-of-support",
+            "--output" => "--output",
+            "--format" => "--format",
+            "--layout" => "--layout",
+            "--checkpoint" => "--checkpoint",
+            "--checkpoint-every" => "--checkpoint-every",
+            "--resume" => "--resume",
+            _ => return Err(RunError::UnknownArgument(arg)),
+        };
+        let value = args.next().ok_or(RunError::MissingValue(flag))?;
+        match flag {
+            "--scenario" => {
+                let path = PathBuf::from(value);
+                let text = fs::read_to_string(&path).map_err(|error| RunError::Io {
+                    path: path.clone(),
+                    error,
+                })?;
+                let scenario =
+                    Scenario::parse(&text).map_err(|error| RunError::Scenario { path, error })?;
+                target = "mixture".to_owned();
+                config.target_distr = scenario.target.clone();
+                config.params = scenario.params();
+                config.seed = scenario.seed;
+                config.steps = scenario.steps();
+                rng = Some(scenario.rng);
+            }
+            "--target" => target = value,
+            "--dim" => dim = parsed(flag, value)?,
+            "--data" => data = Some(PathBuf::from(value)),
+            "--steps" => config.steps = parsed(flag, value)?,
+            "--sigma" => {
+                let sigma: f32 = parsed(flag, value.clone())?;
+                if !(sigma.is_finite() && sigma > 0.0) {
+                    return Err(RunError::InvalidValue { flag, value });
+                }
+                config.params.proposal.sigma = sigma;
+            }
+            "--rng" => {
+                rng = Some(
+                    WrappedRngDiscriminants::VARIANTS
+                        .iter()
+                        .copied()
+                        .find(|variant| variant.display_name().eq_ignore_ascii_case(&value))
+                        .ok_or(RunError::InvalidValue { flag, value })?,
+                );
+            }
+            "--seed" => config.seed = parsed(flag, value)?,
+            "--out-of-support" => {
+                config.params.out_of_support = OutOfSupport::VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|variant| variant.display_name().eq_ignore_ascii_case(&value))
+                    .ok_or(RunError::InvalidValue { flag, value })?;
+            }
+            "--output" => config.output = value.into(),
+            "--format" => {
+                config.format = Format::VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|variant| {
+                        variant.extension().eq_ignore_ascii_case(&value)
+                            || variant.display_name().eq_ignore_ascii_case(&value)
+                    })
+                    .ok_or(RunError::InvalidValue { flag, value })?;
+            }
+            "--layout" => {
+                config.layout = Layout::VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|variant| variant.display_name().eq_ignore_ascii_case(&value))
+                    .ok_or(RunError::InvalidValue { flag, value })?;
+            }
+            "--checkpoint" => config.checkpoint = Some(value.into()),
+            "--checkpoint-every" => {
+                let every: usize = parsed(flag, value.clone())?;
+                if every == 0 {
+                    return Err(RunError::InvalidValue { flag, value });
+                }
+                config.checkpoint_every = Some(every);
+            }
+            "--resume" => resume = Some(PathBuf::from(value)),
+            _ => unreachable!("all flags are matched above"),
+        }
+    }
+
+    if !cfg!(feature = "persistence") && (config.checkpoint.is_some() || resume.is_some()) {
+        return Err(RunError::CheckpointsUnsupported);
+    }
+    if let Some(path) = resume {
+        let (algo, target_distr, cpu_target) = load_checkpoint(&path)?;
+        config.params = algo.params.clone();
+        config.seed = algo.params.proposal.rng.seed.unwrap_or(config.seed);
+        config.target_distr = target_distr;
+        config.cpu_target = cpu_target;
+        config.resumed = Some(algo);
+        return Ok(Some(config));
+    }
+
+    let rng = rng.unwrap_or_else(|| WrappedRngDiscriminants::from(&config.params.proposal.rng.rng));
+    config.params.proposal.rng.reseed(rng, config.seed);
+    config.params.accept.reseed(rng, config.seed);
+
+    let high_dimensional = |variant: usize| {
+        if !(2..=HighDimModel::MAX_DIM).contains(&dim) {
+            return Err(RunError::InvalidValue {
+                flag: "--dim",
+                value: dim.to_string(),
+            });
+        }
+        let model = HighDimModel::variants(dim)
+            .into_iter()
+            .nth(variant)
+            .expect("both variants exist");
+        Ok(Some(CpuTarget::HighDimensional(HighDimensional {
+            model,
+            ..Default::default()
+        })))
+    };
+    config.cpu_target = match target.as_str() {
+        "mixture" => None,
+        "correlated-gaussian" => high_dimensional(0)?,
+        "funnel" => high_dimensional(1)?,
+        "posterior" => {
+            let path = data.ok_or(RunError::MissingData)?;
+            let text = fs::read_to_string(&path).map_err(|error| RunError::Io {
+                path: path.clone(),
+                error,
+            })?;
+            let dataset = Dataset::parse_csv(path.display().to_string(), &text)
+                .map_err(|err| RunError::Data(format!("{}: {err}", path.display())))?;
+            Some(CpuTarget::Posterior(Posterior::new(dataset)))
+        }
+        _ => {
+            return Err(RunError::InvalidValue {
+                flag: "--target",
+                value: target,
+            });
+        }
+    };
+    Ok(Some(config))
+}
+
+/// Continues `algo` until it took [`RunConfig::steps`] in total.
+fn run(config: &RunConfig, mut algo: Rwmh) -> Result<Rwmh, RunError> {
+    while (algo.step_count() as usize) < config.steps {
+        match config.cpu_target {
+            Some(CpuTarget::Posterior(ref posterior)) => algo.step(posterior),
+            Some(CpuTarget::HighDimensional(ref target)) => algo.step_high_dimensional(target),
+            None => algo.step(&config.target_distr),
+        }
+        if let Some(ref path) = config.checkpoint
+            && config
+                .checkpoint_every
+                .is_some_and(|every| (algo.step_count() as usize).is_multiple_of(every))
+        {
+            save_checkpoint(path, &algo, config)?;
+        }
+    }
+    if let Some(ref path) = config.checkpoint {
+        save_checkpoint(path, &algo, config)?;
+    }
+    Ok(algo)
+}
+
+#[cfg(feature = "persistence")]
+fn load_checkpoint(path: &Path) -> Result<(Rwmh, Gaussian, Option<CpuTarget>), RunError> {
+    let file = fs::File::open(path).map_err(|error| RunError::Io {
+        path: path.to_owned(),
+        error,
+    })?;
+    let Checkpoint {
+        algo,
+        target_distr,
+        cpu_target,
+    } = Checkpoint::read(std::io::BufReader::new(file)).map_err(|err| RunError::Checkpoint {
+        path: path.to_owned(),
+        error: err.to_string(),
+    })?;
+    Ok((algo, target_distr, cpu_target))
+}
+
+/// Written to a temporary file first, so that an interrupted write doesn't destroy the previous checkpoint.
+#[cfg(feature = "persistence")]
+fn save_checkpoint(path: &Path, algo: &Rwmh, config: &RunConfig) -> Result<(), RunError> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    fs::File::create(&partial)
+        .and_then(|file| {
+            checkpoint::write(
+                algo,
+                &config.target_distr,
+                config.cpu_target.as_ref(),
+                std::io::BufWriter::new(file),
+            )
+        })
+        .and_then(|()| fs::rename(&partial, path))
+        .map_err(|error| RunError::Io {
+            path: path.to_owned(),
+            error,
+        })
+}
+
+#[cfg(not(feature = "persistence"))]
+fn load_checkpoint(_: &Path) -> Result<(Rwmh, Gaussian, Option<CpuTarget>), RunError> {
+    unreachable!("rejected when parsing the arguments")
+}
+
+#[cfg(not(feature = "persistence"))]
+fn save_checkpoint(_: &Path, _: &Rwmh, _: &RunConfig) -> Result<(), RunError> {
+    unreachable!("rejected when parsing the arguments")
+}
+
+struct Summary {
+    steps: usize,
+    accepted: usize,
+    coordinates: Vec<String>,
+    /// Over all steps, i.e. weighted with how long the chain stayed at each position.
+    means: Vec<f64>,
+    sds: Vec<f64>,
+    config_description: Vec<(&'static str, String)>,
+}
+
+impl Summary {
+    fn new(config: &RunConfig, chain: &Chain) -> Self {
+        let coordinates = chain.coordinates.clone();
+        let mut total_weight = 0.0;
+        let mut sums = vec![0.0; coordinates.len()];
+        let mut sums_of_squares = vec![0.0; coordinates.len()];
+        for &(ref position, remain_count) in &chain.accepted {
+            let weight = f64::from(remain_count) + 1.0;
+            total_weight += weight;
+            for ((sum, square_sum), &val) in sums.iter_mut().zip(&mut sums_of_squares).zip(position)
+            {
+                let val = f64::from(val);
+                *sum += weight * val;
+                *square_sum += weight * val * val;
+            }
+        }
+        let means: Vec<f64> = sums.iter().map(|sum| sum / total_weight).collect();
+        let sds = sums_of_squares
+            .iter()
+            .zip(&means)
+            .map(|(square_sum, mean)| (square_sum / total_weight - mean * mean).max(0.0).sqrt())
+            .collect();
+        let target = export::describe_target(config.cpu_target.as_ref(), &config.target_distr);
+        Self {
+            steps: chain.steps(),
+            accepted: chain.accepted.len() - 1,
+            coordinates,
+            means,
+            sds,
+            config_description: vec![
+                ("target", target),
+                ("sigma", config.params.proposal.sigma.to_string()),
+                (
+                    "rng",
+                    WrappedRngDiscriminants::from(&config.params.proposal.rng.rng).to_string(),
+                ),
+                ("seed", config.seed.to_string()),
+                (
+                    "out_of_support",
+                    config.params.out_of_support.display_name().to_owned(),
+                ),
+            ],
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for &(key, ref value) in &self.config_description {
+            writeln!(f, "{key}: {value}")?;
+        }
+        writeln!(f, "steps: {}", self.steps)?;
+        writeln!(f, "accepted: {}", self.accepted)?;
+        if self.steps > 0 {
+            writeln!(
+                f,
+                "acceptance_rate: {:.4}",
+                self.accepted as f64 / self.steps as f64
+            )?;
+        }
+        for ((name, mean), sd) in self.coordinates.iter().zip(&self.means).zip(&self.sds) {
+            writeln!(f, "{name}: mean {mean:.6}, sd {sd:.6}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the exported history, see [`export::export`], and `summary.txt`.
+fn write_outputs(config: &RunConfig, chain: &Chain, summary: &Summary) -> Result<(), RunError> {
+    let io_error = |path: &Path| {
+        let path = path.to_owned();
+        move |error| RunError::Io { path, error }
+    };
+    let dir = &config.output;
+    fs::create_dir_all(dir).map_err(io_error(dir))?;
+    let target = export::describe_target(config.cpu_target.as_ref(), &config.target_distr);
+    for file in export::export(chain, &target, &config.params, config.format, config.layout) {
+        let file_path = dir.join(file.name);
+        fs::write(&file_path, file.bytes).map_err(io_error(&file_path))?;
+    }
+
+    let summary_path = dir.join("summary.txt");
+    fs::File::create(&summary_path)
+        .and_then(|mut file| write!(file, "{summary}"))
+        .map_err(io_error(&summary_path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &str) -> impl Iterator<Item = String> {
+        args.split_whitespace().map(str::to_owned)
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(matches!(
+            parse_args(args("--steps ten")),
+            Err(RunError::InvalidValue {
+                flag: "--steps",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_args(args("--target posterior")),
+            Err(RunError::MissingData)
+        ));
+        assert!(matches!(
+            parse_args(args("--sigma")),
+            Err(RunError::MissingValue("--sigma"))
+        ));
+    }
+
+    #[test]
+    fn every_step_is_accounted_for() {
+        for target in ["mixture", "funnel"] {
+            let config = parse_args(args(&format!("--target {target} --dim 5 --steps 500")))
+                .unwrap()
+                .unwrap();
+            let algo = run(&config, Rwmh::new(config.params.clone())).unwrap();
+            let chain = Chain::new(&algo, config.cpu_target.as_ref());
+            let weights: u32 = chain
+                .accepted
+                .iter()
+                .map(|&(_, remain_count)| remain_count + 1)
+                .sum();
+            // the starting point counts as a sample too.
+            assert_eq!(weights, 501);
+            let summary = Summary::new(&config, &chain);
+            assert_eq!(summary.accepted + chain.rejected.len(), 500);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Complete state of a run, to continue it later exactly where it stopped.
+//!
+//! The RNGs are stored with their internal state, so a resumed run is bit-identical to an uninterrupted one.
+//! The sampler doesn't adapt, so besides the chain and the RNGs there is no state to store.
+//!
+//! The file starts with [`MAGIC`], a little endian `u32` version and a `u64` length,
+//! followed by that many bytes of JSON holding everything but the histories.
+//! The histories follow as raw little endian arrays, their lengths are part of the JSON.
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::{AcceptRecord, AlgoParams, AlgoVec, Rwmh},
+    target_distr::{CpuTarget, Gaussian},
+};
+
+pub const EXTENSION: &str = "ckpt";
+
+const MAGIC: &[u8; 8] = b"MCMCCKPT";
+const VERSION: u32 = 1;
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub algo: Rwmh,
+    pub target_distr: Gaussian,
+    /// If present, this is sampled instead of [`Self::target_distr`].
+    pub cpu_target: Option<CpuTarget>,
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// Doesn't start with the magic bytes.
+    NotACheckpoint,
+    /// Written by a newer version.
+    UnsupportedVersion(u32),
+    Header(String),
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                write!(f, "checkpoint is incomplete")
+            }
+            Self::Io(ref err) => write!(f, "{err}"),
+            Self::NotACheckpoint => write!(f, "not a checkpoint"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "checkpoint has version {version}, only up to {VERSION} is supported"
+            ),
+            Self::Header(ref err) => write!(f, "invalid checkpoint header: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Everything but the histories, which are too long for JSON.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Header {
+    params: AlgoParams,
+    current_loc: AcceptRecord,
+    max_remain_count: u32,
+    total_point_count: u32,
+    current_full: Vec<f32>,
+    target_distr: Gaussian,
+    cpu_target: Option<CpuTarget>,
+    history_len: usize,
+    rejected_len: usize,
+    full_history_len: usize,
+    full_rejected_len: usize,
+}
+
+/// Takes the parts of a [`Checkpoint`] separately, so that the chain doesn't have to be moved or cloned.
+///
+/// Pass a buffered writer, the histories are written value by value.
+///
+/// # Errors
+/// If writing fails.
+pub fn write(
+    algo: &Rwmh,
+    target_distr: &Gaussian,
+    cpu_target: Option<&CpuTarget>,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let header = serde_json::to_vec(&Header {
+        params: algo.params.clone(),
+        current_loc: algo.current_loc,
+        max_remain_count: algo.max_remain_count,
+        total_point_count: algo.total_point_count,
+        current_full: algo.current_full.clone(),
+        target_distr: target_distr.clone(),
+        cpu_target: cpu_target.cloned(),
+        history_len: algo.history.len(),
+        rejected_len: algo.rejected_history.len(),
+        full_history_len: algo.full_history.len(),
+        full_rejected_len: algo.full_rejected_history.len(),
+    })
+    .map_err(io::Error::other)?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for record in &algo.history {
+        write_floats(&mut writer, &record.position)?;
+        writer.write_all(&record.remain_count.to_le_bytes())?;
+    }
+    for rejected in &algo.rejected_history {
+        write_floats(&mut writer, rejected.as_slice())?;
+    }
+    write_floats(&mut writer, &algo.full_history)?;
+    write_floats(&mut writer, &algo.full_rejected_history)?;
+    writer.flush()
+}
+
+impl Checkpoint {
+    /// Pass a buffered reader, the histories are read value by value.
+    ///
+    /// # Errors
+    /// If reading fails, or it's not a checkpoint of a supported version.
+    pub fn read(mut reader: impl Read) -> Result<Self, ReadError> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(ReadError::NotACheckpoint);
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version != VERSION {
+            return Err(ReadError::UnsupportedVersion(version));
+        }
+        let header_len = usize::try_from(u64::from_le_bytes(read_array(&mut reader)?))
+            .map_err(|_| ReadError::Header("too long".to_owned()))?;
+        let mut header = Vec::new();
+        reader
+            .by_ref()
+            .take(header_len as u64)
+            .read_to_end(&mut header)?;
+        if header.len() != header_len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let header: Header =
+            serde_json::from_slice(&header).map_err(|err| ReadError::Header(err.to_string()))?;
+
+        let history = (0..header.history_len)
+            .map(|_| {
+                Ok(AcceptRecord {
+                    position: [read_float(&mut reader)?, read_float(&mut reader)?],
+                    remain_count: u32::from_le_bytes(read_array(&mut reader)?),
+                    _pad: [0],
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        let rejected_history = (0..header.rejected_len)
+            .map(|_| {
+                Ok(AlgoVec::new(
+                    read_float(&mut reader)?,
+                    read_float(&mut reader)?,
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        let full_history = read_floats(&mut reader, header.full_history_len)?;
+        let full_rejected_history = read_floats(&mut reader, header.full_rejected_len)?;
+        Ok(Self {
+            algo: Rwmh {
+                current_loc: header.current_loc,
+                max_remain_count: header.max_remain_count,
+                total_point_count: header.total_point_count,
+                history,
+                rejected_history,
+                params: header.params,
+                current_full: header.current_full,
+                full_history,
+                full_rejected_history,
+            },
+            target_distr: header.target_distr,
+            cpu_target: header.cpu_target,
+        })
+    }
+}
+
+fn write_floats(writer: &mut impl Write, values: &[f32]) -> io::Result<()> {
+    values
+        .iter()
+        .try_for_each(|value| writer.write_all(&value.to_le_bytes()))
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_float(reader: &mut impl Read) -> io::Result<f32> {
+    read_array(reader).map(f32::from_le_bytes)
+}
+
+fn read_floats(reader: &mut impl Read, len: usize) -> io::Result<Vec<f32>> {
+    (0..len).map(|_| read_float(reader)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        simulation::WrappedRngDiscriminants,
+        target_distr::{HighDimModel, HighDimensional},
+    };
+
+    fn bytes(checkpoint: &Checkpoint) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write(
+            &checkpoint.algo,
+            &checkpoint.target_distr,
+            checkpoint.cpu_target.as_ref(),
+            &mut bytes,
+        )
+        .unwrap();
+        bytes
+    }
+
+    fn step(checkpoint: &mut Checkpoint) {
+        match checkpoint.cpu_target {
+            Some(CpuTarget::HighDimensional(ref target)) => {
+                checkpoint.algo.step_high_dimensional(target);
+            }
+            _ => checkpoint.algo.step(&checkpoint.target_distr),
+        }
+    }
+
+    #[test]
+    fn resumed_runs_are_identical() {
+        let mut params = AlgoParams::default();
+        let high_dimensional = CpuTarget::HighDimensional(HighDimensional {
+            model: HighDimModel::variants(4).into_iter().nth(1).unwrap(),
+            ..Default::default()
+        });
+        for (rng, cpu_target) in [
+            (WrappedRngDiscriminants::VARIANTS.first(), None),
+            (
+                WrappedRngDiscriminants::VARIANTS.last(),
+                Some(high_dimensional),
+            ),
+        ] {
+            let rng = *rng.unwrap();
+            params.proposal.rng.reseed(rng, 3);
+            params.accept.reseed(rng, 4);
+            let mut uninterrupted = Checkpoint {
+                algo: Rwmh::new(params.clone()),
+                target_distr: Gaussian::default(),
+                cpu_target,
+            };
+            for _ in 0..200 {
+                step(&mut uninterrupted);
+            }
+            let mut resumed = Checkpoint::read(bytes(&uninterrupted).as_slice()).unwrap();
+            assert_eq!(bytes(&resumed), bytes(&uninterrupted));
+            for _ in 0..300 {
+                step(&mut uninterrupted);
+                step(&mut resumed);
+            }
+            assert_eq!(bytes(&resumed), bytes(&uninterrupted));
+        }
+    }
+
+    #[test]
+    fn damaged_files() {
+        let written = bytes(&Checkpoint {
+            algo: Rwmh::default(),
+            target_distr: Gaussian::default(),
+            cpu_target: None,
+        });
+        let (truncated, _) = written.split_at(written.len() - 1);
+        assert!(matches!(
+            Checkpoint::read(truncated),
+            Err(ReadError::Io(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof
+        ));
+        assert!(matches!(
+            Checkpoint::read(b"x,y\n1,2\n".as_slice()),
+            Err(ReadError::NotACheckpoint)
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! Lines of equal density, traced by marching squares over values on a grid.
+
+use std::collections::HashMap;
+
+use crate::kde::Grid;
+
+/// The densities above which the highest density regions hold the given fractions of the mass.
+///
+/// The mass is that of equally sized cells with the given `densities`, so whatever lies outside of them isn't accounted for.
+/// Fractions of zero and below give infinity, as no region holds less.
+pub fn hdr_levels(densities: &[f64], masses: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = densities
+        .iter()
+        .copied()
+        .filter(|density| density.is_finite() && *density > 0.0)
+        .collect();
+    sorted.sort_unstable_by(|lhs, rhs| rhs.total_cmp(lhs));
+    let total: f64 = sorted.iter().sum();
+    let mut cumulative = Vec::with_capacity(sorted.len());
+    sorted.iter().fold(0.0, |sum, density| {
+        let sum = sum + density / total;
+        cumulative.push(sum);
+        sum
+    });
+    masses
+        .iter()
+        .map(|&mass| {
+            if mass <= 0.0 {
+                return f64::INFINITY;
+            }
+            // rounding might leave the last sum just below 1.
+            let idx = cumulative
+                .partition_point(|&sum| sum < mass)
+                .min(sorted.len().saturating_sub(1));
+            sorted.get(idx).copied().unwrap_or(f64::INFINITY)
+        })
+        .collect()
+}
+
+/// Where `values` at the cell centers of `grid`, row after row, cross `level`.
+///
+/// Each line is a list of positions, closed lines end where they started.
+/// Lines are cut off at the border of the grid.
+pub fn iso_lines(values: &[f64], grid: &Grid, level: f64) -> Vec<Vec<[f32; 2]>> {
+    let [width, height] = [grid.width, grid.height].map(|side| side as usize);
+    if values.len() != width * height || width < 2 || height < 2 {
+        return Vec::new();
+    }
+    let value = |x: usize, y: usize| values.get(y * width + x).copied().unwrap_or(f64::NAN);
+    let position = |x: f64, y: f64| {
+        let [origin_x, origin_y] = grid.origin;
+        let cell_size = f64::from(grid.cell_size);
+        [
+            (f64::from(origin_x) + x * cell_size) as f32,
+            (f64::from(origin_y) + y * cell_size) as f32,
+        ]
+    };
+    // Identifies the edge to the next cell center in positive x (`vertical` false) or y direction,
+    // as neighbouring squares share it, their segments connect there.
+    let edge_key = |x: usize, y: usize, vertical: bool| 2 * (y * width + x) + usize::from(vertical);
+    let crossing = |[x0, y0]: [usize; 2], [x1, y1]: [usize; 2]| {
+        let (from, to) = (value(x0, y0), value(x1, y1));
+        ((from >= level) != (to >= level)).then(|| {
+            let along = (level - from) / (to - from);
+            let point = position(
+                (x1 as f64 - x0 as f64).mul_add(along, x0 as f64),
+                (y1 as f64 - y0 as f64).mul_add(along, y0 as f64),
+            );
+            (edge_key(x0, y0, x0 == x1), point)
+        })
+    };
+
+    let mut segments = Vec::new();
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            // around the square, starting with the top edge.
+            let edges = [
+                crossing([x, y], [x + 1, y]),
+                crossing([x + 1, y], [x + 1, y + 1]),
+                crossing([x, y + 1], [x + 1, y + 1]),
+                crossing([x, y], [x, y + 1]),
+            ];
+            match edges {
+                [Some(top), Some(right), Some(bottom), Some(left)] => {
+                    // A saddle, the center decides which of the opposite corners are connected.
+                    let center =
+                        (value(x, y) + value(x + 1, y) + value(x, y + 1) + value(x + 1, y + 1))
+                            / 4.0;
+                    if (center >= level) == (value(x, y) >= level) {
+                        segments.extend([[top, right], [bottom, left]]);
+                    } else {
+                        segments.extend([[left, top], [right, bottom]]);
+                    }
+                }
+                _ => {
+                    let mut crossed = edges.into_iter().flatten();
+                    if let (Some(from), Some(to)) = (crossed.next(), crossed.next()) {
+                        segments.push([from, to]);
+                    }
+                }
+            }
+        }
+    }
+    join_segments(&segments)
+}
+
+/// Chains the segments that share an edge, in both directions from each unvisited one.
+fn join_segments(segments: &[[(usize, [f32; 2]); 2]]) -> Vec<Vec<[f32; 2]>> {
+    let mut at_edge: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (idx, &[(from, _), (to, _)]) in segments.iter().enumerate() {
+        at_edge.entry(from).or_default().push(idx);
+        at_edge.entry(to).or_default().push(idx);
+    }
+    let mut visited = vec![false; segments.len()];
+    // The other end of the next unvisited segment at `edge`.
+    let follow = |edge: usize, seen: &mut Vec<bool>| {
+        let next = at_edge.get(&edge).and_then(|candidates| {
+            candidates
+                .iter()
+                .copied()
+                .find(|&idx| !seen.get(idx).copied().unwrap_or(true))
+        })?;
+        if let Some(visit) = seen.get_mut(next) {
+            *visit = true;
+        }
+        let &[from, to] = segments.get(next)?;
+        Some(if from.0 == edge { to } else { from })
+    };
+
+    let mut lines = Vec::new();
+    for (idx, &[(start_edge, start), (end_edge, end)]) in segments.iter().enumerate() {
+        if visited.get(idx).copied().unwrap_or(true) {
+            continue;
+        }
+        if let Some(visit) = visited.get_mut(idx) {
+            *visit = true;
+        }
+        let mut forward = vec![start, end];
+        let mut edge = end_edge;
+        while let Some((next_edge, point)) = follow(edge, &mut visited) {
+            forward.push(point);
+            edge = next_edge;
+        }
+        // a closed line came back to the start, otherwise it might continue before it.
+        let mut backward = Vec::new();
+        edge = start_edge;
+        while let Some((next_edge, point)) = follow(edge, &mut visited) {
+            backward.push(point);
+            edge = next_edge;
+        }
+        backward.reverse();
+        backward.extend(forward);
+        lines.push(backward);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Grid, hdr_levels, iso_lines};
+
+    #[test]
+    fn hdr_levels_accumulate_the_highest_densities() {
+        let densities = [1.0, 4.0, 0.0, 3.0, 2.0];
+        assert_eq!(
+            hdr_levels(&densities, &[0.0, 0.4, 0.5, 0.7, 1.0]),
+            [f64::INFINITY, 4.0, 3.0, 3.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn circle_is_one_closed_line() {
+        let grid = Grid {
+            origin: [-1.0, -1.0],
+            cell_size: 0.05,
+            width: 41,
+            height: 41,
+        };
+        let values: Vec<f64> = (0..grid.height)
+            .flat_map(|y| (0..grid.width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let [x, y] = [x, y].map(|idx| f64::from(idx).mul_add(0.05, -1.0));
+                (-(x * x + y * y)).exp()
+            })
+            .collect();
+        let radius: f64 = 0.6;
+        let lines = iso_lines(&values, &grid, (-radius * radius).exp());
+        assert_eq!(lines.len(), 1);
+        let line = lines.first().unwrap();
+        assert_eq!(line.first(), line.last());
+        assert!(line.len() > 20);
+        for &[x, y] in line {
+            let distance = f64::from(x).hypot(f64::from(y));
+            assert!((distance - radius).abs() < 0.01, "{distance}");
+        }
+    }
+
+    #[test]
+    fn lines_end_at_the_border() {
+        let grid = Grid {
+            origin: [0.0, 0.0],
+            cell_size: 1.0,
+            width: 4,
+            height: 3,
+        };
+        // increasing to the right, crossing 1.5 between the second and third column.
+        let values: Vec<f64> = (0..3).flat_map(|_| [0.0, 1.0, 2.0, 3.0]).collect();
+        let lines = iso_lines(&values, &grid, 1.5);
+        assert_eq!(lines.len(), 1);
+        let line = lines.first().unwrap();
+        assert_eq!(line.len(), 3);
+        assert!(line.iter().all(|&[x, _]| (x - 1.5).abs() < 1e-6));
+        let mut ys: Vec<f32> = line.iter().map(|&[_, y]| y).collect();
+        ys.sort_unstable_by(f32::total_cmp);
+        assert_eq!(ys, [0.0, 1.0, 2.0]);
+    }
+}
@@ -0,0 +1,600 @@
+//! Writes the chain history to files that can be read by the usual analysis tools.
+//!
+//! Every tabular export consists of `history.<ext>`, `rejected.<ext>` and a `metadata.json` with everything needed to reproduce the run.
+//! NumPy doesn't allow additional keys in the header of a `.npy` file, so the metadata is kept separate for all of them.
+//! [`Format::ArviZ`] instead writes a single file, with the metadata as attributes.
+
+use std::fmt::Write;
+
+use crate::{
+    simulation::{
+        WrappedRngDiscriminants,
+        random_walk_metropolis_hastings::{AlgoParams, Rwmh},
+    },
+    target_distr::{CpuTarget, Gaussian},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Csv,
+    JsonLines,
+    Npy,
+    /// `InferenceData` as written by `arviz.InferenceData.to_json`, load it with `arviz.from_json`.
+    ArviZ,
+}
+
+impl Format {
+    pub const VARIANTS: &'static [Self] = &[Self::Csv, Self::JsonLines, Self::Npy, Self::ArviZ];
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Csv => "CSV",
+            Self::JsonLines => "JSON Lines",
+            Self::Npy => "NumPy",
+            Self::ArviZ => "ArviZ",
+        }
+    }
+
+    pub const fn extension(&self) -> &'static str {
+        match *self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+            Self::Npy => "npy",
+            Self::ArviZ => "json",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One row per accepted position, with how often the chain stayed there.
+    #[default]
+    RunLength,
+    /// One row per iteration, as most analysis tools expect.
+    Expanded,
+}
+
+impl Layout {
+    pub const VARIANTS: &'static [Self] = &[Self::RunLength, Self::Expanded];
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::RunLength => "run-length",
+            Self::Expanded => "expanded",
+        }
+    }
+
+    pub const fn explanation(&self) -> &'static str {
+        match *self {
+            Self::RunLength => {
+                "One row per accepted position, with a `remain_count` column for how many proposals were rejected there afterwards."
+            }
+            Self::Expanded => {
+                "One row per iteration, a position is repeated for every rejected proposal."
+            }
+        }
+    }
+}
+
+/// The samples of a run, in the coordinates of the target.
+///
+/// Posterior positions are mapped to parameters, high dimensional ones contain all coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain {
+    pub coordinates: Vec<String>,
+    /// Accepted positions with their remain count, including the current one.
+    pub accepted: Vec<(Vec<f32>, u32)>,
+    pub rejected: Vec<Vec<f32>>,
+}
+
+impl Chain {
+    pub fn new(algo: &Rwmh, cpu_target: Option<&CpuTarget>) -> Self {
+        // The first element of the history is a dummy.
+        let records = algo
+            .history
+            .iter()
+            .skip(1)
+            .chain(std::iter::once(&algo.current_loc));
+        match cpu_target {
+            Some(&CpuTarget::HighDimensional(ref target)) => {
+                // the chain never stepped, it's still where the sampler would start it.
+                let current = if algo.current_full.is_empty() {
+                    target.embed(algo.current_loc.position)
+                } else {
+                    algo.current_full.clone()
+                };
+                Self {
+                    coordinates: (0..target.dim())
+                        .map(|coordinate| format!("x{coordinate}"))
+                        .collect(),
+                    accepted: algo
+                        .full_history
+                        .chunks_exact(target.dim())
+                        .map(<[f32]>::to_vec)
+                        .chain(std::iter::once(current))
+                        .zip(records)
+                        .map(|(position, record)| (position, record.remain_count))
+                        .collect(),
+                    rejected: algo
+                        .full_rejected_history
+                        .chunks_exact(target.dim())
+                        .map(<[f32]>::to_vec)
+                        .collect(),
+                }
+            }
+            Some(&CpuTarget::Posterior(ref posterior)) => Self {
+                coordinates: posterior
+                    .model
+                    .parameter_names()
+                    .map(|name| name.replace(' ', "_"))
+                    .into(),
+                accepted: records
+                    .map(|record| {
+                        let parameters = posterior.to_parameters(record.position.into());
+                        (parameters.into(), record.remain_count)
+                    })
+                    .collect(),
+                rejected: algo
+                    .rejected_history
+                    .iter()
+                    .map(|&position| posterior.to_parameters(position).into())
+                    .collect(),
+            },
+            None => Self {
+                coordinates: vec!["x".to_owned(), "y".to_owned()],
+                accepted: records
+                    .map(|record| (record.position.into(), record.remain_count))
+                    .collect(),
+                rejected: algo
+                    .rejected_history
+                    .iter()
+                    .map(|position| vec![position.x, position.y])
+                    .collect(),
+            },
+        }
+    }
+
+    /// The starting point is not a step, but counts as a draw.
+    pub fn steps(&self) -> usize {
+        self.draws().count().saturating_sub(1)
+    }
+
+    /// Every iteration, i.e. accepted positions repeated for each rejection.
+    pub fn draws(&self) -> impl Iterator<Item = &[f32]> {
+        self.accepted
+            .iter()
+            .flat_map(|&(ref position, remain_count)| {
+                std::iter::repeat_n(position.as_slice(), remain_count as usize + 1)
+            })
+    }
+
+    /// For each of [`Self::draws`], whether the chain moved there.
+    pub fn acceptances(&self) -> impl Iterator<Item = bool> {
+        self.accepted.iter().flat_map(|&(_, remain_count)| {
+            std::iter::once(true).chain(std::iter::repeat_n(false, remain_count as usize))
+        })
+    }
+}
+
+/// Human readable description of the target, for the metadata.
+pub fn describe_target(cpu_target: Option<&CpuTarget>, mixture: &Gaussian) -> String {
+    match cpu_target {
+        Some(&CpuTarget::Posterior(ref posterior)) => {
+            format!(
+                "{} posterior of {}",
+                posterior.model.display_name(),
+                posterior.data.name
+            )
+        }
+        Some(&CpuTarget::HighDimensional(ref target)) => {
+            format!(
+                "{} in {} dimensions",
+                target.model.display_name(),
+                target.dim()
+            )
+        }
+        None => format!("mixture of {} Gaussians", mixture.gaussians.len()),
+    }
+}
+
+pub struct OutputFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// `params` are the ones the run was started with. `layout` is ignored for [`Format::ArviZ`].
+pub fn export(
+    chain: &Chain,
+    target: &str,
+    params: &AlgoParams,
+    format: Format,
+    layout: Layout,
+) -> Vec<OutputFile> {
+    if format == Format::ArviZ {
+        return vec![OutputFile {
+            name: "inference_data.json".to_owned(),
+            bytes: inference_data(chain, target, params).into_bytes(),
+        }];
+    }
+    let history_columns: Vec<&str> = match layout {
+        Layout::RunLength => chain
+            .coordinates
+            .iter()
+            .map(String::as_str)
+            .chain(["remain_count"])
+            .collect(),
+        Layout::Expanded => chain.coordinates.iter().map(String::as_str).collect(),
+    };
+    let history_rows: Vec<Row<'_>> = match layout {
+        Layout::RunLength => chain
+            .accepted
+            .iter()
+            .map(|&(ref position, remain_count)| (position.as_slice(), Some(remain_count)))
+            .collect(),
+        Layout::Expanded => chain.draws().map(|position| (position, None)).collect(),
+    };
+    let rejected_rows: Vec<Row<'_>> = chain
+        .rejected
+        .iter()
+        .map(|position| (position.as_slice(), None))
+        .collect();
+    let coordinates: Vec<&str> = chain.coordinates.iter().map(String::as_str).collect();
+    let extension = format.extension();
+    vec![
+        OutputFile {
+            name: format!("history.{extension}"),
+            bytes: encode(format, &history_columns, &history_rows),
+        },
+        OutputFile {
+            name: format!("rejected.{extension}"),
+            bytes: encode(format, &coordinates, &rejected_rows),
+        },
+        OutputFile {
+            name: "metadata.json".to_owned(),
+            bytes: metadata(chain, target, params, layout).into_bytes(),
+        },
+    ]
+}
+
+/// A position, with the remain count in the run-length layout.
+type Row<'a> = (&'a [f32], Option<u32>);
+
+fn encode(format: Format, columns: &[&str], rows: &[Row<'_>]) -> Vec<u8> {
+    let cells = |&(position, remain_count): &Row<'_>, number: fn(f32) -> String| {
+        position
+            .iter()
+            .map(|&val| number(val))
+            .chain(remain_count.map(|count| count.to_string()))
+            .collect::<Vec<_>>()
+    };
+    match format {
+        Format::Csv => {
+            let mut text = columns.join(",");
+            text.push('\n');
+            for row in rows {
+                text.push_str(&cells(row, |val| val.to_string()).join(","));
+                text.push('\n');
+            }
+            text.into_bytes()
+        }
+        Format::JsonLines => {
+            let mut text = String::new();
+            for row in rows {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .zip(cells(row, json_number))
+                    .map(|(&column, val)| format!("{}:{val}", json_string(column)))
+                    .collect();
+                writeln!(text, "{{{}}}", fields.join(",")).expect("writing to a String");
+            }
+            text.into_bytes()
+        }
+        Format::Npy => npy(columns.len(), rows),
+        Format::ArviZ => unreachable!("not tabular"),
+    }
+}
+
+/// Version 1.0 of the format, a C-order `float64` array of shape `(rows, columns)`.
+/// Positions and remain counts are both exact as `float64`.
+fn npy(columns: usize, rows: &[Row<'_>]) -> Vec<u8> {
+    const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {columns}), }}",
+        rows.len()
+    );
+    // magic, header length and header are padded to a multiple of 64 bytes, ending in a newline.
+    let unpadded = MAGIC.len() + 2 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+    let header_len = u16::try_from(header.len()).expect("the header is short");
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 2 + header.len() + rows.len() * columns * 8);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&header_len.to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for &(position, remain_count) in rows {
+        let values = position
+            .iter()
+            .map(|&val| f64::from(val))
+            .chain(remain_count.map(f64::from));
+        for val in values {
+            bytes.extend_from_slice(&val.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Everything needed to reproduce the run, as JSON values.
+fn run_attributes(target: &str, params: &AlgoParams) -> Vec<(&'static str, String)> {
+    let [start_x, start_y] = params.start;
+    let mut attributes = vec![
+        ("target", json_string(target)),
+        ("sigma", params.proposal.sigma.to_string()),
+        (
+            "out_of_support",
+            json_string(params.out_of_support.display_name()),
+        ),
+        ("start", format!("[{start_x}, {start_y}]")),
+        // `null` for continuous runs.
+        (
+            "batch_size",
+            params
+                .progress_mode
+                .batch_size()
+                .map_or_else(|| "null".to_owned(), |size| size.to_string()),
+        ),
+    ];
+    for (name, kind, seed) in [
+        (
+            ["proposal_rng", "proposal_seed"],
+            WrappedRngDiscriminants::from(&params.proposal.rng.rng),
+            params.proposal.rng.seed,
+        ),
+        (
+            ["accept_rng", "accept_seed"],
+            WrappedRngDiscriminants::from(&params.accept.rng),
+            params.accept.seed,
+        ),
+    ] {
+        let [rng_key, seed_key] = name;
+        attributes.push((rng_key, json_string(kind.display_name())));
+        // netCDF has no null, so unknown seeds are left out.
+        if let Some(seed) = seed {
+            attributes.push((seed_key, seed.to_string()));
+        }
+    }
+    attributes
+}
+
+fn json_object(entries: &[(&str, String)], indent: &str) -> String {
+    let fields: Vec<String> = entries
+        .iter()
+        .map(|&(key, ref value)| format!("{indent}  {}: {value}", json_string(key)))
+        .collect();
+    format!("{{\n{}\n{indent}}}", fields.join(",\n"))
+}
+
+/// JSON has no NaN or infinity, these are written as `null`.
+fn json_number(val: f32) -> String {
+    if val.is_finite() {
+        val.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+fn json_array<T: std::fmt::Display>(values: impl Iterator<Item = T>) -> String {
+    let values: Vec<String> = values.map(|val| val.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn metadata(chain: &Chain, target: &str, params: &AlgoParams, layout: Layout) -> String {
+    let mut entries = vec![
+        (
+            "generator",
+            json_string(concat!("mcmc-demo ", env!("CARGO_PKG_VERSION"))),
+        ),
+        (
+            "coordinates",
+            json_array(chain.coordinates.iter().map(|name| json_string(name))),
+        ),
+        ("layout", json_string(layout.display_name())),
+        ("steps", chain.steps().to_string()),
+        (
+            "accepted",
+            chain.accepted.len().saturating_sub(1).to_string(),
+        ),
+    ];
+    entries.extend(run_attributes(target, params));
+    json_object(&entries, "") + "\n"
+}
+
+/// A single chain. The starting point isn't a draw, so there is one draw per step,
+/// each with an `accepted` flag in the `sample_stats` group.
+fn inference_data(chain: &Chain, target: &str, params: &AlgoParams) -> String {
+    let draws: Vec<&[f32]> = chain.draws().skip(1).collect();
+    let posterior: Vec<(&str, String)> = chain
+        .coordinates
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let values = draws
+                .iter()
+                .map(|position| json_number(position.get(idx).copied().unwrap_or_default()));
+            (name.as_str(), format!("[{}]", json_array(values)))
+        })
+        .collect();
+    let sample_stats = [(
+        "accepted",
+        format!("[{}]", json_array(chain.acceptances().skip(1))),
+    )];
+    let mut attributes = vec![
+        ("inference_library", json_string("mcmc-demo")),
+        (
+            "inference_library_version",
+            json_string(env!("CARGO_PKG_VERSION")),
+        ),
+        ("sampler", json_string("random walk Metropolis-Hastings")),
+    ];
+    attributes.extend(run_attributes(target, params));
+    let groups = [
+        ("posterior", json_object(&posterior, "  ")),
+        ("sample_stats", json_object(&sample_stats, "  ")),
+        ("attrs", json_object(&attributes, "  ")),
+    ];
+    json_object(&groups, "") + "\n"
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            control if control.is_control() => {
+                write!(escaped, "\\u{:04x}", u32::from(control)).expect("writing to a String");
+            }
+            other => escaped.push(other),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::target_distr::HighDimensional;
+
+    fn chain() -> Chain {
+        let mut algo = Rwmh::default();
+        for _ in 0..200 {
+            algo.step(&Gaussian::default());
+        }
+        Chain::new(&algo, None)
+    }
+
+    #[test]
+    fn expanded_has_a_row_per_iteration() {
+        let chain = chain();
+        assert_eq!(chain.steps(), 200);
+        let files = export(
+            &chain,
+            "test",
+            &AlgoParams::default(),
+            Format::Csv,
+            Layout::Expanded,
+        );
+        let history = files.first().map(|file| file.bytes.as_slice()).unwrap();
+        let history = std::str::from_utf8(history).unwrap();
+        // header and the starting point.
+        assert_eq!(history.lines().count(), 200 + 2);
+        assert_eq!(history.lines().next(), Some("x,y"));
+    }
+
+    #[test]
+    fn inference_data_has_a_draw_per_step() {
+        let chain = chain();
+        assert_eq!(chain.draws().count(), chain.acceptances().count());
+        let accepted = chain
+            .acceptances()
+            .skip(1)
+            .filter(|&accepted| accepted)
+            .count();
+        assert_eq!(accepted, chain.accepted.len() - 1);
+        let files = export(
+            &chain,
+            "test",
+            &AlgoParams::default(),
+            Format::ArviZ,
+            Layout::RunLength,
+        );
+        let [ref file] = *files else {
+            panic!("a single file");
+        };
+        let text = std::str::from_utf8(&file.bytes).unwrap();
+        let draws = text
+            .lines()
+            .find(|line| line.trim_start().starts_with("\"x\""))
+            .unwrap()
+            .matches(", ")
+            .count()
+            + 1;
+        assert_eq!(draws, chain.steps());
+        assert!(text.contains("\"sample_stats\""));
+    }
+
+    #[test]
+    fn high_dimensional_chains_start_at_the_start_point() {
+        let target = HighDimensional {
+            projection: [2, 0],
+            ..Default::default()
+        };
+        let algo = Rwmh::new(AlgoParams {
+            start: [0.5, -0.25],
+            ..Default::default()
+        });
+        let chain = Chain::new(&algo, Some(&CpuTarget::HighDimensional(target.clone())));
+        let [(ref start, _)] = *chain.accepted else {
+            panic!("only the start");
+        };
+        assert_eq!(*start, target.embed([0.5, -0.25]));
+        assert!((start.get(2).copied().unwrap() - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn json_has_no_non_finite_numbers() {
+        let mut chain = chain();
+        if let Some(&mut (ref mut position, _)) = chain.accepted.last_mut() {
+            *position = vec![f32::NAN, f32::INFINITY];
+        }
+        for format in [Format::ArviZ, Format::JsonLines] {
+            let files = export(
+                &chain,
+                "test",
+                &AlgoParams::default(),
+                format,
+                Layout::RunLength,
+            );
+            let text = std::str::from_utf8(&files.first().unwrap().bytes).unwrap();
+            // `inf` also starts `inference_library`.
+            assert!(
+                !text.contains("NaN")
+                    && !["inf,", "inf]", "inf}"]
+                        .iter()
+                        .any(|inf| text.contains(inf)),
+                "{format:?}"
+            );
+            assert!(
+                text.contains("null, null") || text.contains("\"x\":null,\"y\":null"),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn npy_header_is_aligned() {
+        let chain = chain();
+        let files = export(
+            &chain,
+            "test",
+            &AlgoParams::default(),
+            Format::Npy,
+            Layout::RunLength,
+        );
+        let bytes = files.first().map(|file| file.bytes.as_slice()).unwrap();
+        let (preamble, rest) = bytes.split_at(10);
+        let (magic, header_len) = preamble.split_at(8);
+        assert_eq!(magic, b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes(header_len.try_into().unwrap());
+        let (header, data) = rest.split_at(usize::from(header_len));
+        assert_eq!((10 + header.len()) % 64, 0);
+        let header = std::str::from_utf8(header).unwrap();
+        assert!(header.ends_with('\n'));
+        assert!(header.contains(&format!("'shape': ({}, 3)", chain.accepted.len())));
+        assert_eq!(data.len(), chain.accepted.len() * 3 * 8);
+    }
+}
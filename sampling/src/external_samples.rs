@@ -0,0 +1,117 @@
+//! Draws produced elsewhere, e.g. by Stan or PyMC, turned into a chain so they can be compared with the target.
+//!
+//! Positions are in canvas coordinates, just like the components of the Gaussian mixture.
+
+use std::fmt::Display;
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::{AcceptRecord, Rwmh},
+    target_distr::Dataset,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum ColumnError {
+    /// Less than two columns that could be coordinates.
+    TooFewColumns,
+    /// Line numbers aren't known anymore after parsing, so this is the index of the data row, starting at 1.
+    InvalidRemainCount { row: usize },
+}
+
+impl Display for ColumnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::TooFewColumns => write!(f, "expected at least two coordinate columns"),
+            Self::InvalidRemainCount { row } => {
+                write!(
+                    f,
+                    "row {row}: remain_count has to be a non-negative integer"
+                )
+            }
+        }
+    }
+}
+
+/// Uses the columns named `x` and `y`, otherwise the first two columns that aren't sampler diagnostics like Stan's `lp__`.
+///
+/// A `remain_count` column, as written by the run-length export, is respected.
+///
+/// # Errors
+/// If there are no two coordinate columns, or the remain counts aren't integers.
+pub fn chain_from_dataset(data: &Dataset) -> Result<Rwmh, ColumnError> {
+    let find = |name: &str| {
+        data.headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+    };
+    let remain_count_column = find("remain_count");
+    let (x_column, y_column) = if let (Some(x_column), Some(y_column)) = (find("x"), find("y")) {
+        (x_column, y_column)
+    } else {
+        let mut candidates =
+            data.headers.iter().enumerate().filter(|&(idx, header)| {
+                Some(idx) != remain_count_column && !header.ends_with("__")
+            });
+        match (candidates.next(), candidates.next()) {
+            (Some((x_column, _)), Some((y_column, _))) => (x_column, y_column),
+            _ => return Err(ColumnError::TooFewColumns),
+        }
+    };
+    let remain_counts = remain_count_column.map(|column| data.column(column));
+    let records = data
+        .column(x_column)
+        .iter()
+        .zip(data.column(y_column))
+        .enumerate()
+        .map(|(row, (&x, &y))| {
+            let remain_count = match remain_counts.and_then(|counts| counts.get(row)) {
+                Some(&count) if count >= 0.0 && count.fract() == 0.0 => count as u32,
+                Some(_) => return Err(ColumnError::InvalidRemainCount { row: row + 1 }),
+                None => 0,
+            };
+            Ok(AcceptRecord {
+                position: [x, y],
+                remain_count,
+                _pad: [0],
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // parsing already rejects files without rows.
+    Rwmh::from_records(records).ok_or(ColumnError::TooFewColumns)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "parsed values are exact")]
+    fn stan_output() {
+        let text = "# comment\nlp__,accept_stat__,mu,tau\n-1,0.9,0.1,0.2\n-1,0.1,0.1,0.2\n-2,0.8,0.3,0.4\n";
+        let data = Dataset::parse_csv("stan", text).unwrap();
+        let algo = chain_from_dataset(&data).unwrap();
+        // the repeated draw was a rejection.
+        assert_eq!(algo.history.len(), 2);
+        assert_eq!(
+            algo.history.last().map(|record| record.remain_count),
+            Some(1)
+        );
+        assert_eq!(algo.current_loc.position, [0.3, 0.4]);
+        assert_eq!(algo.total_point_count, 2);
+        assert_eq!(algo.max_remain_count, 1);
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "parsed values are exact")]
+    fn run_length_export() {
+        let fractional =
+            Dataset::parse_csv("export", "y,x,remain_count\n1,2,3\n4,5,0.5\n").unwrap();
+        assert_eq!(
+            chain_from_dataset(&fractional).err(),
+            Some(ColumnError::InvalidRemainCount { row: 2 })
+        );
+        let data = Dataset::parse_csv("export", "y,x,remain_count\n1,2,3\n").unwrap();
+        let algo = chain_from_dataset(&data).unwrap();
+        assert_eq!(algo.current_loc.position, [2.0, 1.0]);
+        assert_eq!(algo.current_loc.remain_count, 3);
+    }
+}
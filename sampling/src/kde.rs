@@ -0,0 +1,380 @@
+//! Kernel density estimates of the draws of a chain.
+//!
+//! The GPU based displays evaluate these for every pixel, this is what they are meant to compute.
+
+use std::f64::consts::PI;
+
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::{AcceptRecord, AlgoVec, Rwmh};
+
+/// Product kernels, the same one dimensional kernel in each coordinate, each integrates to one over the plane.
+///
+/// Being products, they can be applied one coordinate after the other, see [`binned_density`].
+/// The bandwidth is the standard deviation of each coordinate for all of them,
+/// so that a bandwidth smoothes about as much whichever kernel is used.
+#[cfg_persistence_derive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// Uniform on a square.
+    TopHat,
+    /// Proportional to `1 - x²/5` in each coordinate, the most efficient kernel in terms of the mean integrated squared error.
+    Epanechnikov,
+    #[default]
+    Gaussian,
+}
+
+/// The binned estimate cuts the Gaussian off at this many standard deviations.
+const GAUSSIAN_REACH: f64 = 4.0;
+
+impl Kernel {
+    pub const VARIANTS: &'static [Self] = &[Self::TopHat, Self::Epanechnikov, Self::Gaussian];
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::TopHat => "Top-hat",
+            Self::Epanechnikov => "Epanechnikov",
+            Self::Gaussian => "Gaussian",
+        }
+    }
+
+    /// Identifies the kernel in the shaders, see `binary_distance_approx.wgsl`.
+    pub const fn shader_id(&self) -> u32 {
+        match *self {
+            Self::TopHat => 0,
+            Self::Epanechnikov => 1,
+            Self::Gaussian => 2,
+        }
+    }
+
+    /// Half the width of the support in each coordinate for a bandwidth of one, infinite for [`Self::Gaussian`].
+    pub fn radius(&self) -> f64 {
+        match *self {
+            // uniform on [-r, r] has a variance of r²/3.
+            Self::TopHat => 3.0f64.sqrt(),
+            // and this one r²/5.
+            Self::Epanechnikov => 5.0f64.sqrt(),
+            Self::Gaussian => f64::INFINITY,
+        }
+    }
+
+    /// Like [`Self::radius`], but finite for all kernels.
+    pub fn reach(&self) -> f64 {
+        self.radius().min(GAUSSIAN_REACH)
+    }
+
+    /// Value of the one dimensional kernel at `offset`, for a bandwidth of one.
+    pub fn factor(&self, offset: f64) -> f64 {
+        let radius = self.radius();
+        match *self {
+            Self::TopHat if offset.abs() <= radius => 1.0 / (2.0 * radius),
+            Self::Epanechnikov if offset.abs() <= radius => {
+                3.0 / (4.0 * radius) * (1.0 - (offset / radius).powi(2))
+            }
+            Self::TopHat | Self::Epanechnikov => 0.0,
+            Self::Gaussian => (-0.5 * offset * offset).exp() / (2.0 * PI).sqrt(),
+        }
+    }
+
+    /// Value at `offset` from the center, for a bandwidth of one.
+    pub fn value(&self, [x, y]: [f64; 2]) -> f64 {
+        self.factor(x) * self.factor(y)
+    }
+}
+
+/// How the bandwidth is chosen.
+#[cfg_persistence_derive]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Bandwidth {
+    Fixed(f32),
+    /// `σ n^(-1/6)`, optimal for normally distributed draws.
+    Scott,
+    /// `0.9 min(σ, IQR / 1.349) n^(-1/6)`, more robust for multimodal or skewed draws.
+    #[default]
+    Silverman,
+}
+
+impl Bandwidth {
+    /// With a fixed bandwidth, the value is kept when switching to it.
+    pub const VARIANTS: &'static [Self] = &[Self::Fixed(0.1), Self::Scott, Self::Silverman];
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Fixed(_) => "Fixed",
+            Self::Scott => "Scott",
+            Self::Silverman => "Silverman",
+        }
+    }
+
+    /// A chain with less than two distinct positions has no spread, which falls back to `fallback`.
+    pub fn resolve(&self, algo: &Rwmh, fallback: f32) -> f32 {
+        let rule = match *self {
+            Self::Fixed(bandwidth) => return bandwidth,
+            Self::Scott => |spread: Spread| spread.standard_deviation,
+            Self::Silverman => {
+                |spread: Spread| 0.9 * spread.standard_deviation.min(spread.iqr / 1.349)
+            }
+        };
+        let count: f64 = draws(algo)
+            .map(|record| f64::from(record.remain_count) + 1.0)
+            .sum();
+        let spreads = [|[x, _]: [f32; 2]| x, |[_, y]: [f32; 2]| y]
+            .map(|coordinate| Spread::new(algo, coordinate, count));
+        // both coordinates share the bandwidth, so they are averaged.
+        let spread = (spreads
+            .iter()
+            .map(|&spread| rule(spread).powi(2))
+            .sum::<f64>()
+            / 2.0)
+            .sqrt();
+        let bandwidth = (spread * count.powf(-1.0 / 6.0)) as f32;
+        if bandwidth.is_finite() && bandwidth > 0.0 {
+            bandwidth
+        } else {
+            fallback
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Spread {
+    standard_deviation: f64,
+    /// Interquartile range.
+    iqr: f64,
+}
+
+impl Spread {
+    /// Of one coordinate of the draws, weighted by how long the chain stayed.
+    fn new(algo: &Rwmh, coordinate: fn([f32; 2]) -> f32, count: f64) -> Self {
+        let mut values: Vec<(f64, f64)> = draws(algo)
+            .map(|record| {
+                (
+                    f64::from(coordinate(record.position)),
+                    f64::from(record.remain_count) + 1.0,
+                )
+            })
+            .collect();
+        let mean = values
+            .iter()
+            .map(|&(value, weight)| value * weight)
+            .sum::<f64>()
+            / count;
+        let variance = values
+            .iter()
+            .map(|&(value, weight)| weight * (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        values.sort_unstable_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0));
+        let quantile = |fraction: f64| {
+            let mut seen = 0.0;
+            values
+                .iter()
+                .find(|&&(_, weight)| {
+                    seen += weight;
+                    seen >= fraction * count
+                })
+                .map_or(f64::NAN, |&(value, _)| value)
+        };
+        Self {
+            standard_deviation: variance.sqrt(),
+            iqr: quantile(0.75) - quantile(0.25),
+        }
+    }
+}
+
+/// The accepted positions and how long the chain stayed there, including the current one.
+fn draws(algo: &Rwmh) -> impl Iterator<Item = &AcceptRecord> + Clone {
+    algo.history
+        .iter()
+        .skip(1)
+        .chain(std::iter::once(&algo.current_loc))
+}
+
+/// The estimate at `position`, the same as the shaders compute,
+/// which only know the history and not the current position.
+pub fn density(algo: &Rwmh, kernel: Kernel, bandwidth: f32, position: AlgoVec) -> f64 {
+    let bandwidth = f64::from(bandwidth);
+    let weighted: f64 = algo
+        .history
+        .iter()
+        .skip(1)
+        .map(|record| {
+            let offset = AlgoVec::from(record.position) - position;
+            let offset = [offset.x, offset.y].map(|coord| f64::from(coord) / bandwidth);
+            (f64::from(record.remain_count) + 1.0) * kernel.value(offset)
+        })
+        .sum();
+    weighted / (f64::from(algo.total_point_count.max(1)) * bandwidth * bandwidth)
+}
+
+/// Limits how many cells the kernel spans to each side, which bounds the cost of wide kernels.
+///
+/// Points further outside of the grid are left out, and kernels spanning more are cut off.
+pub const MAX_MARGIN: u32 = 512;
+
+/// Equally sized cells along the coordinate axes, [`binned_density`] evaluates the estimate at their centers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    /// Center of the first cell, the others follow in positive direction of each coordinate.
+    pub origin: [f32; 2],
+    pub cell_size: f32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Grid {
+    /// Cells the kernel reaches to each side, the grid is padded by this many while binning.
+    pub fn margin(&self, kernel: Kernel, bandwidth: f32) -> u32 {
+        let cells = (kernel.reach() * f64::from(bandwidth) / f64::from(self.cell_size)).ceil();
+        if cells < f64::from(MAX_MARGIN) {
+            cells.max(0.0) as u32
+        } else {
+            MAX_MARGIN
+        }
+    }
+
+    /// Index into the grid padded by `margin` of the cell closest to `position`.
+    fn padded_cell(&self, position: [f32; 2], margin: u32) -> Option<usize> {
+        let [position_x, position_y] = position;
+        let [origin_x, origin_y] = self.origin;
+        let [x, y] = [position_x - origin_x, position_y - origin_y].map(|offset| {
+            (f64::from(offset) / f64::from(self.cell_size)).round() + f64::from(margin)
+        });
+        let padded_width = f64::from(self.width + 2 * margin);
+        let padded_height = f64::from(self.height + 2 * margin);
+        ((0.0..padded_width).contains(&x) && (0.0..padded_height).contains(&y))
+            .then_some((y * padded_width + x) as usize)
+    }
+}
+
+/// [`density`] at the center of each cell of `grid`, row after row.
+///
+/// Approximated by moving each point to the center of its cell and applying the kernel to one coordinate after the other,
+/// which costs O(points + cells · margin) instead of O(points · cells).
+/// The compute shader does the same, see `binary_distance_approx.compute.wgsl`.
+pub fn binned_density(algo: &Rwmh, kernel: Kernel, bandwidth: f32, grid: &Grid) -> Vec<f64> {
+    if grid.width == 0 || grid.height == 0 {
+        return Vec::new();
+    }
+    let margin = grid.margin(kernel, bandwidth);
+    let width = grid.width as usize;
+    let padded_width = width + 2 * margin as usize;
+    let padded_height = grid.height as usize + 2 * margin as usize;
+
+    let mut bins = vec![0.0; padded_width * padded_height];
+    for record in algo.history.iter().skip(1) {
+        if let Some(bin) = grid
+            .padded_cell(record.position, margin)
+            .and_then(|idx| bins.get_mut(idx))
+        {
+            *bin += f64::from(record.remain_count) + 1.0;
+        }
+    }
+
+    let taps = taps(kernel, bandwidth, grid.cell_size, margin);
+    let dot = |values: &[f64]| {
+        values
+            .iter()
+            .zip(&taps)
+            .map(|(value, tap)| value * tap)
+            .sum()
+    };
+    let rows: Vec<Vec<f64>> = bins
+        .chunks_exact(padded_width)
+        .map(|row| row.windows(taps.len()).map(dot).collect())
+        .collect();
+    let total_point_count = f64::from(algo.total_point_count.max(1));
+    rows.windows(taps.len())
+        .flat_map(|window| {
+            let mut cells = vec![0.0; width];
+            for (row, tap) in window.iter().zip(&taps) {
+                for (cell, value) in cells.iter_mut().zip(row) {
+                    *cell += tap * value;
+                }
+            }
+            cells.into_iter().map(|cell| cell / total_point_count)
+        })
+        .collect()
+}
+
+/// The one dimensional kernel at offsets of `-margin..=margin` cells, scaled to the bandwidth.
+fn taps(kernel: Kernel, bandwidth: f32, cell_size: f32, margin: u32) -> Vec<f64> {
+    let bandwidth = f64::from(bandwidth);
+    let margin = i64::from(margin);
+    (-margin..=margin)
+        .map(|offset| kernel.factor(offset as f64 * f64::from(cell_size) / bandwidth) / bandwidth)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Bandwidth, Grid, Kernel, binned_density, density};
+    use crate::simulation::random_walk_metropolis_hastings::{AcceptRecord, AlgoVec, Rwmh};
+
+    #[test]
+    fn kernels_integrate_to_one() {
+        // being products, they do over the plane if they do along one coordinate.
+        let cell_size = 0.001;
+        for kernel in Kernel::VARIANTS {
+            let integral: f64 = (-8000..8000)
+                .map(|idx| kernel.factor((f64::from(idx) + 0.5) * cell_size) * cell_size)
+                .sum();
+            assert!((integral - 1.0).abs() < 1e-3, "{kernel:?}: {integral}");
+        }
+    }
+
+    #[test]
+    fn rules_scale_with_the_spread() {
+        let records = |scale: f32| {
+            (0u8..100).map(move |idx| AcceptRecord {
+                position: [f32::from(idx % 10) * scale, f32::from(idx / 10) * scale],
+                remain_count: 0,
+                _pad: [0],
+            })
+        };
+        for rule in [Bandwidth::Scott, Bandwidth::Silverman] {
+            let narrow = Rwmh::from_records(records(1.0)).expect("has records");
+            let wide = Rwmh::from_records(records(3.0)).expect("has records");
+            let narrow_bandwidth = rule.resolve(&narrow, 1.0);
+            let wide_bandwidth = rule.resolve(&wide, 1.0);
+            assert!(
+                (wide_bandwidth / narrow_bandwidth - 3.0).abs() < 1e-3,
+                "{rule:?}: {narrow_bandwidth} {wide_bandwidth}"
+            );
+        }
+        let single = Rwmh::from_records(records(0.0)).expect("has records");
+        assert!((Bandwidth::Scott.resolve(&single, 0.5) - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn binning_agrees_with_the_exact_estimate() {
+        // a low discrepancy sequence, spreading the points evenly but irregularly.
+        let records = (0u16..300).map(|idx| AcceptRecord {
+            position: [0.618_034, 0.754_877].map(|step: f32| (f32::from(idx) * step).fract() - 0.5),
+            remain_count: u32::from(idx % 3),
+            _pad: [0],
+        });
+        let algo = Rwmh::from_records(records).expect("has records");
+        let grid = Grid {
+            origin: [-1.0, -1.0],
+            cell_size: 0.01,
+            width: 200,
+            height: 200,
+        };
+        for &kernel in Kernel::VARIANTS {
+            let binned = binned_density(&algo, kernel, 0.2, &grid);
+            assert_eq!(binned.len(), 200 * 200);
+            let cells = (0u16..200).flat_map(|row| (0u16..200).map(move |col| [col, row]));
+            // as both integrate to about one, this is the fraction of the mass that differs.
+            let difference: f64 = cells
+                .zip(&binned)
+                .map(|([col, row], &binned)| {
+                    let position = AlgoVec::new(f32::from(col), f32::from(row)) * 0.01
+                        - AlgoVec::new(1.0, 1.0);
+                    (binned - density(&algo, kernel, 0.2, position)).abs() * 0.01 * 0.01
+                })
+                .sum();
+            assert!(difference < 0.02, "{kernel:?}: {difference}");
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! The samplers and target distributions of the MCMC demo.
+//!
+//! Kept free of any GUI or GPU dependency, so that it can be used for analysis without compiling the whole GUI stack.
+//! The UI for all of this lives in the executable.
+#[cfg(feature = "persistence")]
+pub mod checkpoint;
+pub mod contours;
+pub mod export;
+pub mod external_samples;
+pub mod kde;
+pub mod metrics;
+pub mod scenario;
+pub mod share_link;
+pub mod simulation;
+pub mod target_distr;
+
+#[cfg(not(any(feature = "rng_pcg", feature = "rng_xorshift", feature = "rng_xoshiro")))]
+compile_error!("no rng compiled in.");
@@ -0,0 +1,230 @@
+//! How well the draws of a chain match the target, evaluated on a grid over the shown part of the sample space,
+//! and how many independent draws they are worth.
+
+use crate::{
+    simulation::random_walk_metropolis_hastings::{AlgoVec, Rwmh},
+    target_distr::Density,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Discrepancy {
+    /// Accepted positions weighted with how long the chain stayed there, including the current one.
+    pub draws: u64,
+    /// Fraction of the draws outside of the grid, these aren't part of [`Self::total_variation`].
+    pub outside: f64,
+    /// Half the L1 distance between the histogram of the draws and the target, between 0 and 1.
+    pub total_variation: f64,
+    /// Euclidean distance between the mean of the draws and the mean of the target, both restricted to the grid.
+    pub mean_error: f64,
+}
+
+impl Discrepancy {
+    /// Over the square of `half_extent` around `center`, as the view shows it,
+    /// in `resolution` x `resolution` cells, coarser grids need less draws for a low total variation.
+    pub fn new<T: Density + ?Sized>(
+        algo: &Rwmh,
+        target: &T,
+        [center_x, center_y]: [f32; 2],
+        half_extent: f32,
+        resolution: usize,
+    ) -> Self {
+        let half_extent = f64::from(half_extent);
+        let [origin_x, origin_y] =
+            [center_x, center_y].map(|center| f64::from(center) - half_extent);
+        let cell_size = 2.0 * half_extent / resolution as f64;
+        let center = |idx: usize, start: f64| (idx as f64 + 0.5).mul_add(cell_size, start);
+
+        let mut target_mass = vec![0.0; resolution * resolution];
+        let mut target_mean = [0.0; 2];
+        for (idx, mass) in target_mass.iter_mut().enumerate() {
+            let position = [
+                center(idx % resolution, origin_x),
+                center(idx / resolution, origin_y),
+            ];
+            let density = f64::from(target.calculate_probability_density(AlgoVec::new(
+                position[0] as f32,
+                position[1] as f32,
+            )));
+            *mass = if density.is_finite() { density } else { 0.0 };
+            target_mean[0] += *mass * position[0];
+            target_mean[1] += *mass * position[1];
+        }
+        let total_target: f64 = target_mass.iter().sum();
+
+        let mut draw_mass = vec![0.0; resolution * resolution];
+        let mut draw_mean = [0.0; 2];
+        let mut draws = 0;
+        let mut inside = 0.0;
+        for record in algo
+            .history
+            .iter()
+            .skip(1)
+            .chain(std::iter::once(&algo.current_loc))
+        {
+            let weight = f64::from(record.remain_count) + 1.0;
+            draws += u64::from(record.remain_count) + 1;
+            let [x, y] = record.position.map(f64::from);
+            let cell = |coordinate: f64, start: f64| {
+                let idx = ((coordinate - start) / cell_size).floor();
+                (0.0..resolution as f64)
+                    .contains(&idx)
+                    .then_some(idx as usize)
+            };
+            if let (Some(column), Some(row)) = (cell(x, origin_x), cell(y, origin_y))
+                && let Some(mass) = draw_mass.get_mut(row * resolution + column)
+            {
+                *mass += weight;
+                inside += weight;
+                draw_mean[0] += weight * x;
+                draw_mean[1] += weight * y;
+            }
+        }
+        let draw_count = draws as f64;
+
+        let total_variation = if total_target > 0.0 && inside > 0.0 {
+            0.5 * target_mass
+                .iter()
+                .zip(&draw_mass)
+                .map(|(cell_target, cell_draws)| {
+                    (cell_target / total_target - cell_draws / inside).abs()
+                })
+                .sum::<f64>()
+        } else {
+            1.0
+        };
+        let mean_error = if total_target > 0.0 && inside > 0.0 {
+            let [draw_x, draw_y] = draw_mean.map(|sum| sum / inside);
+            let [target_x, target_y] = target_mean.map(|sum| sum / total_target);
+            (draw_x - target_x).hypot(draw_y - target_y)
+        } else {
+            f64::NAN
+        };
+        Self {
+            draws,
+            outside: 1.0 - inside / draw_count,
+            total_variation,
+            mean_error,
+        }
+    }
+}
+
+/// Effective sample size of the draws, the minimum over both coordinates.
+///
+/// Estimated with batch means over about `√n` batches, which needs a single pass over the draws.
+/// With less than 4 draws it's just their number.
+pub fn effective_sample_size(algo: &Rwmh) -> f64 {
+    let records = || {
+        algo.history
+            .iter()
+            .skip(1)
+            .chain(std::iter::once(&algo.current_loc))
+    };
+    let draws: usize = records()
+        .map(|record| record.remain_count as usize + 1)
+        .sum();
+    if draws < 4 {
+        return draws as f64;
+    }
+    let batch_len = draws.isqrt();
+    let batch_count = draws / batch_len;
+    // the incomplete last batch is left out.
+    let used = batch_len * batch_count;
+
+    [|[x, _]: [f32; 2]| x, |[_, y]: [f32; 2]| y]
+        .into_iter()
+        .map(|coordinate| {
+            let values = records()
+                .flat_map(|record| {
+                    std::iter::repeat_n(
+                        f64::from(coordinate(record.position)),
+                        record.remain_count as usize + 1,
+                    )
+                })
+                .take(used);
+            let mut sum = 0.0;
+            let mut sum_squares = 0.0;
+            let mut batch_sums = Vec::with_capacity(batch_count);
+            for (idx, value) in values.enumerate() {
+                if idx % batch_len == 0 {
+                    batch_sums.push(0.0);
+                }
+                if let Some(batch_sum) = batch_sums.last_mut() {
+                    *batch_sum += value;
+                }
+                sum += value;
+                sum_squares += value * value;
+            }
+            let mean = sum / used as f64;
+            let variance = sum_squares / used as f64 - mean * mean;
+            let batch_variance = batch_sums
+                .iter()
+                .map(|batch_sum| (batch_sum / batch_len as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (batch_count - 1) as f64;
+            if variance <= 0.0 || batch_variance <= 0.0 {
+                // a chain that never moved is worth a single draw.
+                return 1.0;
+            }
+            (used as f64 * variance / (batch_len as f64 * batch_variance)).min(used as f64)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::target_distr::Gaussian;
+
+    #[test]
+    fn longer_chains_get_closer() {
+        let target = Gaussian::default();
+        let mut algo = Rwmh::default();
+        for _ in 0..100 {
+            algo.step(&target);
+        }
+        let short = Discrepancy::new(&algo, &target, [0.0; 2], 1.0, 16);
+        for _ in 0..50_000 {
+            algo.step(&target);
+        }
+        let long = Discrepancy::new(&algo, &target, [0.0; 2], 1.0, 16);
+        assert_eq!(long.draws, 50_101);
+        assert!(long.total_variation < short.total_variation);
+        assert!(long.total_variation < 0.2, "{long:?}");
+        assert!(long.mean_error < 0.1, "{long:?}");
+    }
+
+    #[test]
+    fn follows_the_view() {
+        let target = Gaussian::default();
+        let mut algo = Rwmh::default();
+        for _ in 0..1_000 {
+            algo.step(&target);
+        }
+        let elsewhere = Discrepancy::new(&algo, &target, [10.0, -10.0], 1.0, 16);
+        assert!(
+            (elsewhere.outside - 1.0).abs() < f64::EPSILON,
+            "{elsewhere:?}"
+        );
+        let zoomed_out = Discrepancy::new(&algo, &target, [0.0; 2], 100.0, 16);
+        assert!(zoomed_out.outside.abs() < f64::EPSILON, "{zoomed_out:?}");
+    }
+
+    #[test]
+    fn effective_sample_size_grows_with_the_chain() {
+        let target = Gaussian::default();
+        let mut algo = Rwmh::default();
+        assert!((effective_sample_size(&algo) - 1.0).abs() < f64::EPSILON);
+        for _ in 0..2_000 {
+            algo.step(&target);
+        }
+        let short = effective_sample_size(&algo);
+        for _ in 0..48_000 {
+            algo.step(&target);
+        }
+        let long = effective_sample_size(&algo);
+        assert!(short > 1.0 && short < 2_001.0, "{short}");
+        assert!(long > 2.0 * short, "{short} {long}");
+        // a random walk with a small step size is strongly autocorrelated.
+        assert!(long < 25_000.0, "{long}");
+    }
+}
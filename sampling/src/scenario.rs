@@ -0,0 +1,630 @@
+//! Declarative description of a run: the target, the sampler and the run plan, stored as TOML.
+//!
+//! ```toml
+//! [target]
+//! support = { kind = "box", min = [-0.8, -0.8], max = [0.8, 0.8] }
+//!
+//! [[target.components]]
+//! position = [-0.6, -0.8]
+//! variance = 0.14
+//! weight = 0.5
+//!
+//! [sampler]
+//! sigma = 0.2
+//! out_of_support = "reflect"
+//! start = [0.0, 0.0]
+//!
+//! [rng]
+//! kind = "Pcg64Mcg"
+//! seed = 42
+//!
+//! [run]
+//! batch_size = 500
+//! batches = 10
+//! ```
+//!
+//! Every key is optional and falls back to the defaults of the GUI, except the fields of a component.
+//! Both the proposal and the acceptance RNG are seeded with the given seed.
+
+use std::{fmt::Display, ops::Range};
+
+use toml_edit::{Item, TableLike, Value};
+
+use crate::{
+    simulation::{
+        DEFAULT_SEED, WrappedRngDiscriminants,
+        random_walk_metropolis_hastings::{
+            AlgoParams, DEFAULT_BATCH_SIZE, IPromiseThisIsNonZeroUsize, OutOfSupport, ProgressMode,
+        },
+    },
+    target_distr::{Gaussian, NormalDistribution, Support},
+};
+
+#[derive(Clone, PartialEq)]
+pub struct Scenario {
+    pub target: Gaussian,
+    pub sigma: f32,
+    pub out_of_support: OutOfSupport,
+    pub start: [f32; 2],
+    pub rng: WrappedRngDiscriminants,
+    pub seed: u64,
+    /// Never zero.
+    pub batch_size: usize,
+    pub batches: usize,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        let AlgoParams {
+            proposal,
+            out_of_support,
+            start,
+            ..
+        } = AlgoParams::default();
+        Self {
+            target: Default::default(),
+            sigma: proposal.sigma,
+            out_of_support,
+            start,
+            rng: WrappedRngDiscriminants::from(&proposal.rng.rng),
+            seed: DEFAULT_SEED,
+            batch_size: DEFAULT_BATCH_SIZE,
+            batches: 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// Not valid TOML at all.
+    Syntax(String),
+    Missing,
+    UnknownKey,
+    WrongType {
+        expected: &'static str,
+    },
+    Invalid(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ValidationError {
+    /// Dotted path of the offending key, e.g. `target.components[1].variance`.
+    /// Empty for syntax errors.
+    pub key: String,
+    /// Line numbers start at 1, like in any text editor.
+    pub line: Option<usize>,
+    pub problem: Problem,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}")?;
+            if !self.key.is_empty() {
+                write!(f, ", ")?;
+            }
+        }
+        if !self.key.is_empty() {
+            write!(f, "`{}`", self.key)?;
+        }
+        if self.line.is_some() || !self.key.is_empty() {
+            write!(f, ": ")?;
+        }
+        match self.problem {
+            Problem::Syntax(ref message) => write!(f, "{}", message.trim_end()),
+            Problem::Missing => write!(f, "missing"),
+            Problem::UnknownKey => write!(f, "unknown key"),
+            Problem::WrongType { expected } => write!(f, "expected {expected}"),
+            Problem::Invalid(ref reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Scenario {
+    /// # Errors
+    /// If the source isn't valid TOML, or contains unknown keys, is missing required ones or has invalid values.
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let document = toml_edit::Document::parse(source).map_err(|err| ValidationError {
+            key: String::new(),
+            line: err.span().map(|span| line_of(source, &span)),
+            problem: Problem::Syntax(err.message().to_owned()),
+        })?;
+        let mut scenario = Self::default();
+        let mut root = Reader {
+            source,
+            table: document.as_table(),
+            path: String::new(),
+            span: None,
+            used: Vec::new(),
+        };
+        if let Some(mut target) = root.table("target")? {
+            if let Some(components) = target.tables("components")? {
+                let gaussians: Vec<NormalDistribution> = components
+                    .into_iter()
+                    .map(|mut component| {
+                        let position = component.required("position", Reader::point)?;
+                        let variance = component.required("variance", Reader::float)?;
+                        if variance <= 0.0 {
+                            return Err(component.invalid("variance", "has to be positive"));
+                        }
+                        let scale = component.required("weight", Reader::float)?;
+                        if scale < 0.0 {
+                            return Err(component.invalid("weight", "can't be negative"));
+                        }
+                        component.finish()?;
+                        Ok(NormalDistribution {
+                            position,
+                            variance,
+                            scale,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                // the density would be 0 / 0 everywhere otherwise.
+                if gaussians.is_empty() {
+                    return Err(target.invalid("components", "needs at least one component"));
+                }
+                if gaussians.iter().all(|gaussian| gaussian.scale <= 0.0) {
+                    return Err(
+                        target.invalid("components", "needs a component with a positive weight")
+                    );
+                }
+                scenario.target.gaussians = gaussians;
+            }
+            if let Some(support) = target.table("support")? {
+                scenario.target.support = parse_support(support)?;
+            }
+            target.finish()?;
+        }
+        if let Some(mut sampler) = root.table("sampler")? {
+            if let Some(sigma) = sampler.float("sigma")? {
+                if sigma <= 0.0 {
+                    return Err(sampler.invalid("sigma", "has to be positive"));
+                }
+                scenario.sigma = sigma;
+            }
+            if let Some(out_of_support) = sampler.string("out_of_support")? {
+                scenario.out_of_support = OutOfSupport::VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|variant| variant.display_name().eq_ignore_ascii_case(out_of_support))
+                    .ok_or_else(|| {
+                        sampler.invalid(
+                            "out_of_support",
+                            "has to be one of \"reject\", \"reflect\" or \"transform\"",
+                        )
+                    })?;
+            }
+            if let Some(start) = sampler.point("start")? {
+                scenario.start = start;
+            }
+            sampler.finish()?;
+        }
+        if let Some(mut rng) = root.table("rng")? {
+            if let Some(kind) = rng.string("kind")? {
+                scenario.rng = WrappedRngDiscriminants::VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|variant| variant.display_name().eq_ignore_ascii_case(kind))
+                    .ok_or_else(|| {
+                        let available: Vec<_> = WrappedRngDiscriminants::VARIANTS
+                            .iter()
+                            .map(WrappedRngDiscriminants::display_name)
+                            .collect();
+                        rng.invalid(
+                            "kind",
+                            &format!("unknown RNG, available are {}", available.join(", ")),
+                        )
+                    })?;
+            }
+            if let Some(seed) = rng.integer("seed")? {
+                scenario.seed = seed;
+            }
+            rng.finish()?;
+        }
+        if let Some(mut run) = root.table("run")? {
+            if let Some(batch_size) = run.integer("batch_size")? {
+                scenario.batch_size = usize::try_from(batch_size)
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| run.invalid("batch_size", "has to be positive"))?;
+            }
+            if let Some(batches) = run.integer("batches")? {
+                scenario.batches =
+                    usize::try_from(batches).map_err(|_| run.invalid("batches", "too large"))?;
+            }
+            run.finish()?;
+        }
+        root.finish()?;
+        Ok(scenario)
+    }
+
+    pub fn params(&self) -> AlgoParams {
+        let mut params = AlgoParams {
+            progress_mode: ProgressMode::Batched {
+                size: IPromiseThisIsNonZeroUsize::new(self.batch_size),
+            },
+            out_of_support: self.out_of_support,
+            start: self.start,
+            ..Default::default()
+        };
+        params.proposal.sigma = self.sigma;
+        params.proposal.rng.reseed(self.rng, self.seed);
+        params.accept.reseed(self.rng, self.seed);
+        params
+    }
+
+    pub const fn steps(&self) -> usize {
+        self.batch_size.saturating_mul(self.batches)
+    }
+}
+
+fn parse_support(mut support: Reader<'_>) -> Result<Support, ValidationError> {
+    let kind = support.required("kind", Reader::string)?;
+    let parsed = match kind {
+        "unbounded" => Support::Unbounded,
+        "box" => {
+            let min = support.required("min", Reader::point)?;
+            let max = support.required("max", Reader::point)?;
+            if min.iter().zip(max).any(|(&min, max)| min >= max) {
+                return Err(support.invalid("max", "has to be larger than `min`"));
+            }
+            Support::Box { min, max }
+        }
+        "half-plane" => {
+            let normal = support.required("normal", Reader::point)?;
+            if normal.iter().all(|&component| component == 0.0) {
+                return Err(support.invalid("normal", "can't be zero"));
+            }
+            Support::HalfPlane {
+                normal,
+                offset: support.float("offset")?.unwrap_or_default(),
+            }
+        }
+        "polygon" => {
+            let vertices = support.required("vertices", Reader::points)?;
+            if vertices.len() < 3 {
+                return Err(support.invalid("vertices", "needs at least 3 vertices"));
+            }
+            Support::Polygon { vertices }
+        }
+        _ => {
+            return Err(support.invalid(
+                "kind",
+                "has to be one of \"unbounded\", \"box\", \"half-plane\" or \"polygon\"",
+            ));
+        }
+    };
+    support.finish()?;
+    Ok(parsed)
+}
+
+fn line_of(source: &str, span: &Range<usize>) -> usize {
+    source
+        .get(..span.start)
+        .map_or(0, |before| before.matches('\n').count())
+        + 1
+}
+
+/// Reads the keys of a single table, and remembers which ones were read to find unknown ones.
+struct Reader<'a> {
+    source: &'a str,
+    table: &'a dyn TableLike,
+    path: String,
+    span: Option<Range<usize>>,
+    used: Vec<&'static str>,
+}
+
+impl<'a> Reader<'a> {
+    fn key_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn error(&self, key: &str, problem: Problem) -> ValidationError {
+        let span = self
+            .table
+            .get(key)
+            .and_then(Item::span)
+            .or_else(|| self.span.clone());
+        ValidationError {
+            key: self.key_path(key),
+            line: span.map(|span| line_of(self.source, &span)),
+            problem,
+        }
+    }
+
+    fn invalid(&self, key: &str, reason: &str) -> ValidationError {
+        self.error(key, Problem::Invalid(reason.to_owned()))
+    }
+
+    fn get(&mut self, key: &'static str) -> Option<&'a Item> {
+        self.used.push(key);
+        self.table.get(key)
+    }
+
+    fn required<T>(
+        &mut self,
+        key: &'static str,
+        read: impl FnOnce(&mut Self, &'static str) -> Result<Option<T>, ValidationError>,
+    ) -> Result<T, ValidationError> {
+        read(self, key)?.ok_or_else(|| self.error(key, Problem::Missing))
+    }
+
+    fn value(&mut self, key: &'static str) -> Result<Option<&'a Value>, ValidationError> {
+        self.get(key)
+            .map(|item| {
+                item.as_value().ok_or_else(|| {
+                    self.error(
+                        key,
+                        Problem::WrongType {
+                            expected: "a value",
+                        },
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    fn float(&mut self, key: &'static str) -> Result<Option<f32>, ValidationError> {
+        self.value(key)?
+            .map(|value| {
+                value_to_float(value).ok_or_else(|| {
+                    self.error(
+                        key,
+                        Problem::WrongType {
+                            expected: "a finite number",
+                        },
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    fn integer(&mut self, key: &'static str) -> Result<Option<u64>, ValidationError> {
+        self.value(key)?
+            .map(|value| {
+                value
+                    .as_integer()
+                    .and_then(|val| u64::try_from(val).ok())
+                    .ok_or_else(|| {
+                        self.error(
+                            key,
+                            Problem::WrongType {
+                                expected: "a non-negative integer",
+                            },
+                        )
+                    })
+            })
+            .transpose()
+    }
+
+    fn string(&mut self, key: &'static str) -> Result<Option<&'a str>, ValidationError> {
+        self.value(key)?
+            .map(|value| {
+                value.as_str().ok_or_else(|| {
+                    self.error(
+                        key,
+                        Problem::WrongType {
+                            expected: "a string",
+                        },
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    fn point(&mut self, key: &'static str) -> Result<Option<[f32; 2]>, ValidationError> {
+        self.value(key)?
+            .map(|value| {
+                value_to_point(value).ok_or_else(|| {
+                    self.error(
+                        key,
+                        Problem::WrongType {
+                            expected: "an array of 2 numbers",
+                        },
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    fn points(&mut self, key: &'static str) -> Result<Option<Vec<[f32; 2]>>, ValidationError> {
+        self.value(key)?
+            .map(|value| {
+                value
+                    .as_array()
+                    .and_then(|array| array.iter().map(value_to_point).collect())
+                    .ok_or_else(|| {
+                        self.error(
+                            key,
+                            Problem::WrongType {
+                                expected: "an array of points",
+                            },
+                        )
+                    })
+            })
+            .transpose()
+    }
+
+    fn table(&mut self, key: &'static str) -> Result<Option<Self>, ValidationError> {
+        let Some(item) = self.get(key) else {
+            return Ok(None);
+        };
+        let table = item.as_table_like().ok_or_else(|| {
+            self.error(
+                key,
+                Problem::WrongType {
+                    expected: "a table",
+                },
+            )
+        })?;
+        Ok(Some(Self {
+            source: self.source,
+            table,
+            path: self.key_path(key),
+            span: item.span(),
+            used: Vec::new(),
+        }))
+    }
+
+    /// Either an array of tables, or an array of inline tables.
+    fn tables(&mut self, key: &'static str) -> Result<Option<Vec<Self>>, ValidationError> {
+        let Some(item) = self.get(key) else {
+            return Ok(None);
+        };
+        let wrong_type = || {
+            self.error(
+                key,
+                Problem::WrongType {
+                    expected: "an array of tables",
+                },
+            )
+        };
+        let tables: Vec<(&'a dyn TableLike, Option<Range<usize>>)> =
+            if let Some(array) = item.as_array_of_tables() {
+                array
+                    .iter()
+                    .map(|table| (table as &dyn TableLike, table.span()))
+                    .collect()
+            } else if let Some(array) = item.as_array() {
+                array
+                    .iter()
+                    .map(|value| {
+                        value
+                            .as_inline_table()
+                            .map(|table| (table as &dyn TableLike, table.span()))
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(wrong_type)?
+            } else {
+                return Err(wrong_type());
+            };
+        let path = self.key_path(key);
+        Ok(Some(
+            tables
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (table, span))| Self {
+                    source: self.source,
+                    table,
+                    path: format!("{path}[{idx}]"),
+                    span,
+                    used: Vec::new(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Checks that there are no keys that weren't read.
+    fn finish(self) -> Result<(), ValidationError> {
+        let Some((unknown, _)) = self
+            .table
+            .iter()
+            .find(|&(key, _)| !self.used.contains(&key))
+        else {
+            return Ok(());
+        };
+        let span = self
+            .table
+            .key(unknown)
+            .and_then(toml_edit::Key::span)
+            .or_else(|| self.span.clone());
+        Err(ValidationError {
+            key: self.key_path(unknown),
+            line: span.map(|span| line_of(self.source, &span)),
+            problem: Problem::UnknownKey,
+        })
+    }
+}
+
+fn value_to_float(value: &Value) -> Option<f32> {
+    let val = value
+        .as_float()
+        .or_else(|| value.as_integer().map(|val| val as f64))? as f32;
+    val.is_finite().then_some(val)
+}
+
+fn value_to_point(value: &Value) -> Option<[f32; 2]> {
+    let array = value.as_array()?;
+    if array.len() != 2 {
+        return None;
+    }
+    Some([
+        value_to_float(array.get(0)?)?,
+        value_to_float(array.get(1)?)?,
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "parsed values are exact")]
+    fn parses_example() {
+        let source = r#"
+            [target]
+            support = { kind = "box", min = [-0.8, -0.8], max = [0.8, 0.8] }
+
+            [[target.components]]
+            position = [-0.6, -0.8]
+            variance = 0.14
+            weight = 1
+
+            [sampler]
+            out_of_support = "reflect"
+            start = [0.1, 0.2]
+
+            [run]
+            batches = 3
+        "#;
+        let scenario = Scenario::parse(source).unwrap();
+        assert_eq!(scenario.target.gaussians.len(), 1);
+        assert!(scenario.out_of_support == OutOfSupport::Reflect);
+        assert_eq!(scenario.start, [0.1, 0.2]);
+        assert_eq!(scenario.steps(), 3 * Scenario::default().batch_size);
+    }
+
+    #[test]
+    fn errors_point_to_key() {
+        let source = "[sampler]\nsigma = 0.2\n\n[[target.components]]\nposition = [0, 0]\nvariance = -1\nweight = 1\n";
+        assert_eq!(
+            Scenario::parse(source).err(),
+            Some(ValidationError {
+                key: "target.components[0].variance".to_owned(),
+                line: Some(6),
+                problem: Problem::Invalid("has to be positive".to_owned()),
+            })
+        );
+        let error = Scenario::parse("[rng]\nseeed = 3\n").err().unwrap();
+        assert_eq!(error.key, "rng.seeed");
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.problem, Problem::UnknownKey);
+    }
+
+    #[test]
+    fn targets_need_a_component() {
+        assert_eq!(
+            Scenario::parse("[target]\ncomponents = []\n").err(),
+            Some(ValidationError {
+                key: "target.components".to_owned(),
+                line: Some(2),
+                problem: Problem::Invalid("needs at least one component".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn targets_need_a_weighted_component() {
+        let source = "[[target.components]]\nposition = [0, 0]\nvariance = 1\nweight = 0\n\n[[target.components]]\nposition = [1, 0]\nvariance = 1\nweight = 0\n";
+        let error = Scenario::parse(source).err().unwrap();
+        assert_eq!(error.key, "target.components");
+        assert_eq!(
+            error.problem,
+            Problem::Invalid("needs a component with a positive weight".to_owned())
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Compact encoding of a run for the fragment of a URL, so that a setup can be shared as a link.
+//!
+//! The run is written as little endian binary and then base64url encoded without padding.
+//! Floats are stored exactly, so a restored scenario is identical to the shared one.
+//! The first byte is a version, to be able to still read old links after the format changed.
+//! RNGs are stored by name, as which of them exist depends on the features of the build.
+
+use std::fmt::Display;
+
+use crate::{
+    scenario::Scenario,
+    simulation::{
+        WrappedRngDiscriminants,
+        random_walk_metropolis_hastings::{AlgoParams, OutOfSupport},
+    },
+    target_distr::{NormalDistribution, Support},
+};
+
+const VERSION: u8 = 1;
+
+/// A [`Scenario`], but the GUI lets users choose the acceptance RNG independently of the proposal one.
+#[derive(Clone, PartialEq)]
+pub struct SharedRun {
+    /// [`Scenario::rng`] and [`Scenario::seed`] are used for the proposals only.
+    pub scenario: Scenario,
+    pub accept_rng: WrappedRngDiscriminants,
+    pub accept_seed: u64,
+}
+
+impl SharedRun {
+    pub fn params(&self) -> AlgoParams {
+        let mut params = self.scenario.params();
+        params.accept.reseed(self.accept_rng, self.accept_seed);
+        params
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    /// A character outside of the base64url alphabet.
+    NotBase64,
+    /// Written by a newer version of the demo.
+    UnknownVersion(u8),
+    /// The link ended before the scenario was complete, it was probably cut off while copying.
+    Truncated,
+    /// Bytes after the scenario.
+    TrailingData,
+    Invalid(&'static str),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::NotBase64 => write!(f, "not a valid share link"),
+            Self::UnknownVersion(version) => {
+                write!(
+                    f,
+                    "share link has version {version}, only up to {VERSION} is supported"
+                )
+            }
+            Self::Truncated => write!(f, "share link is incomplete"),
+            Self::TrailingData => write!(f, "share link is longer than expected"),
+            Self::Invalid(reason) => write!(f, "share link is invalid: {reason}"),
+        }
+    }
+}
+
+pub fn encode(run: &SharedRun) -> String {
+    let scenario = &run.scenario;
+    let mut writer = Writer(vec![VERSION]);
+    writer.name(scenario.rng.display_name());
+    writer.name(run.accept_rng.display_name());
+    writer.0.push(variant_index(
+        OutOfSupport::VARIANTS,
+        &scenario.out_of_support,
+    ));
+    writer.varint(scenario.seed);
+    writer.varint(run.accept_seed);
+    writer.float(scenario.sigma);
+    writer.point(scenario.start);
+    writer.varint(scenario.batch_size as u64);
+    writer.varint(scenario.batches as u64);
+    writer.varint(scenario.target.gaussians.len() as u64);
+    for component in &scenario.target.gaussians {
+        writer.point(component.position);
+        writer.float(component.variance);
+        writer.float(component.scale);
+    }
+    match scenario.target.support {
+        Support::Unbounded => writer.0.push(0),
+        Support::Box { min, max } => {
+            writer.0.push(1);
+            writer.point(min);
+            writer.point(max);
+        }
+        Support::HalfPlane { normal, offset } => {
+            writer.0.push(2);
+            writer.point(normal);
+            writer.float(offset);
+        }
+        Support::Polygon { ref vertices } => {
+            writer.0.push(3);
+            writer.varint(vertices.len() as u64);
+            for &vertex in vertices {
+                writer.point(vertex);
+            }
+        }
+    }
+    base64_encode(&writer.0)
+}
+
+/// Checks the same constraints as [`Scenario::parse`].
+///
+/// # Errors
+/// If the fragment wasn't written by [`encode`], or was modified or cut off afterwards.
+pub fn decode(fragment: &str) -> Result<SharedRun, DecodeError> {
+    let bytes = base64_decode(fragment).ok_or(DecodeError::NotBase64)?;
+    let mut reader = Reader(&bytes);
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(DecodeError::UnknownVersion(version));
+    }
+    let mut rng = || {
+        let name = reader.name()?;
+        WrappedRngDiscriminants::VARIANTS
+            .iter()
+            .copied()
+            .find(|variant| variant.display_name().as_bytes() == name)
+            .ok_or(DecodeError::Invalid("RNG not available in this build"))
+    };
+    let proposal_rng = rng()?;
+    let accept_rng = rng()?;
+    let out_of_support = *OutOfSupport::VARIANTS
+        .get(usize::from(reader.byte()?))
+        .ok_or(DecodeError::Invalid("unknown out of support handling"))?;
+    let seed = reader.varint()?;
+    let accept_seed = reader.varint()?;
+    let sigma = reader.float()?;
+    if sigma <= 0.0 {
+        return Err(DecodeError::Invalid("sigma has to be positive"));
+    }
+    let start = reader.point()?;
+    let batch_size = reader.length()?;
+    if batch_size == 0 {
+        return Err(DecodeError::Invalid("batch size has to be positive"));
+    }
+    let batches = reader.length()?;
+    let gaussians = (0..reader.length()?)
+        .map(|_| {
+            let position = reader.point()?;
+            let variance = reader.float()?;
+            let scale = reader.float()?;
+            if variance <= 0.0 || scale < 0.0 {
+                return Err(DecodeError::Invalid(
+                    "component with non-positive variance or negative weight",
+                ));
+            }
+            Ok(NormalDistribution {
+                position,
+                variance,
+                scale,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    let support = match reader.byte()? {
+        0 => Support::Unbounded,
+        1 => {
+            let min = reader.point()?;
+            let max = reader.point()?;
+            if min.iter().zip(max).any(|(&min, max)| min >= max) {
+                return Err(DecodeError::Invalid("empty box support"));
+            }
+            Support::Box { min, max }
+        }
+        2 => {
+            let normal = reader.point()?;
+            if normal.iter().all(|&component| component == 0.0) {
+                return Err(DecodeError::Invalid("half-plane with zero normal"));
+            }
+            Support::HalfPlane {
+                normal,
+                offset: reader.float()?,
+            }
+        }
+        3 => {
+            let vertices = (0..reader.length()?)
+                .map(|_| reader.point())
+                .collect::<Result<Vec<_>, _>>()?;
+            if vertices.len() < 3 {
+                return Err(DecodeError::Invalid("polygon with less than 3 vertices"));
+            }
+            Support::Polygon { vertices }
+        }
+        _ => return Err(DecodeError::Invalid("unknown support")),
+    };
+    if !reader.0.is_empty() {
+        return Err(DecodeError::TrailingData);
+    }
+    let mut scenario = Scenario {
+        sigma,
+        out_of_support,
+        start,
+        rng: proposal_rng,
+        seed,
+        batch_size,
+        batches,
+        ..Default::default()
+    };
+    scenario.target.gaussians = gaussians;
+    scenario.target.support = support;
+    Ok(SharedRun {
+        scenario,
+        accept_rng,
+        accept_seed,
+    })
+}
+
+fn variant_index<T: PartialEq>(variants: &[T], variant: &T) -> u8 {
+    variants
+        .iter()
+        .position(|candidate| candidate == variant)
+        .and_then(|idx| u8::try_from(idx).ok())
+        .expect("variant lists are short and complete")
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    /// LEB128, so that small numbers like batch counts take a single byte.
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn float(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn point(&mut self, [x, y]: [f32; 2]) {
+        self.float(x);
+        self.float(y);
+    }
+
+    fn name(&mut self, name: &str) {
+        self.varint(name.len() as u64);
+        self.0.extend_from_slice(name.as_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let (taken, rest) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or(DecodeError::Truncated)?;
+        self.0 = rest;
+        Ok(*taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Invalid("number too large"))
+    }
+
+    fn length(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.varint()?).map_err(|_| DecodeError::Invalid("number too large"))
+    }
+
+    /// Only finite values, infinities and NaNs can't have been shared on purpose.
+    fn float(&mut self) -> Result<f32, DecodeError> {
+        Some(f32::from_le_bytes(self.take()?))
+            .filter(|value| value.is_finite())
+            .ok_or(DecodeError::Invalid("number not finite"))
+    }
+
+    fn point(&mut self) -> Result<[f32; 2], DecodeError> {
+        Ok([self.float()?, self.float()?])
+    }
+
+    fn name(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.length()?;
+        let (name, rest) = self.0.split_at_checked(len).ok_or(DecodeError::Truncated)?;
+        self.0 = rest;
+        Ok(name)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 4];
+        group
+            .iter_mut()
+            .skip(1)
+            .zip(chunk)
+            .for_each(|(slot, &byte)| *slot = byte);
+        let bits = u32::from_be_bytes(group);
+        // n bytes need n + 1 characters without padding.
+        encoded.extend(
+            (0..=chunk.len())
+                .filter_map(|idx| BASE64_ALPHABET.get(((bits >> (18 - 6 * idx)) & 0x3f) as usize))
+                .map(|&character| char::from(character)),
+        );
+    }
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let sextets = encoded
+        .bytes()
+        .map(|character| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&candidate| candidate == character)
+                .map(|idx| idx as u32)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let mut bytes = Vec::with_capacity(sextets.len() * 3 / 4);
+    for chunk in sextets.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let bits = chunk
+            .iter()
+            .chain(std::iter::repeat(&0))
+            .take(4)
+            .fold(0, |bits, &sextet| (bits << 6) | sextet);
+        let [_, group @ ..] = bits.to_be_bytes();
+        bytes.extend(group.iter().take(chunk.len() - 1));
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut scenario = Scenario {
+            sigma: 0.3,
+            out_of_support: OutOfSupport::Transform,
+            start: [0.25, -0.5],
+            seed: u64::MAX,
+            batch_size: 1000,
+            batches: 7,
+            ..Default::default()
+        };
+        scenario.target.support = Support::Polygon {
+            vertices: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+        };
+        let run = SharedRun {
+            scenario,
+            accept_rng: WrappedRngDiscriminants::VARIANTS.last().copied().unwrap(),
+            accept_seed: 7,
+        };
+        let encoded = encode(&run);
+        assert!(
+            encoded
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character)),
+            "{encoded}"
+        );
+        assert!(decode(&encoded) == Ok(run));
+    }
+
+    #[test]
+    fn damaged_links() {
+        let encoded = encode(&SharedRun {
+            scenario: Scenario::default(),
+            accept_rng: Scenario::default().rng,
+            accept_seed: 0,
+        });
+        assert_eq!(decode("a+b").err(), Some(DecodeError::NotBase64));
+        assert_eq!(
+            decode(encoded.get(..encoded.len() - 4).unwrap()).err(),
+            Some(DecodeError::Truncated)
+        );
+        assert_eq!(
+            decode(&format!("{encoded}AAAA")).err(),
+            Some(DecodeError::TrailingData)
+        );
+        assert_eq!(decode("Ag").err(), Some(DecodeError::UnknownVersion(2)));
+    }
+
+    #[test]
+    fn unknown_rngs_are_rejected() {
+        let mut writer = Writer(vec![VERSION]);
+        writer.name("NoSuchRng");
+        assert_eq!(
+            decode(&base64_encode(&writer.0)).err(),
+            Some(DecodeError::Invalid("RNG not available in this build"))
+        );
+    }
+
+    #[test]
+    fn base64_lengths() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|idx| 250 - idx * 31).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+        }
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE");
+    }
+}
@@ -0,0 +1,640 @@
+#![cfg_attr(
+    feature = "more_debug_impls",
+    expect(
+        clippy::used_underscore_binding,
+        reason = "Educe is somewhat broken, and I dont feel like putting that derive etc into a separate module. So for now I do this at the top level of this module"
+    )
+)]
+use macros::{cfg_educe_debug, cfg_persistence_derive};
+
+use crate::target_distr::{Density, HighDimensional};
+
+use super::{Percentage, RngIter, StandardNormal};
+
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+#[cfg_educe_debug]
+pub struct GaussianProposal {
+    pub sigma: f32,
+    pub rng: RngIter<StandardNormal>,
+}
+
+impl Default for GaussianProposal {
+    fn default() -> Self {
+        Self {
+            sigma: 0.2,
+            rng: Default::default(),
+        }
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+#[cfg_educe_debug]
+pub struct IPromiseThisIsNonZeroUsize(usize);
+
+impl IPromiseThisIsNonZeroUsize {
+    /// # Panics
+    /// If `val` is zero.
+    pub const fn new(val: usize) -> Self {
+        if val == 0 {
+            panic!("nonzero")
+        } else {
+            Self(val)
+        }
+    }
+
+    /// # Safety
+    /// The value must not be set to zero.
+    pub const unsafe fn get_inner_mut(&mut self) -> &mut usize {
+        &mut self.0
+    }
+
+    pub const fn get_inner(&self) -> usize {
+        self.0
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+#[cfg_educe_debug]
+// // TODO: properly fix this
+// // See https://github.com/magiclen/educe/issues/34
+// #[expect(
+//     clippy::used_underscore_binding,
+//     reason = "Should be educe causing this"
+// )]
+pub enum ProgressMode {
+    Batched {
+        size: IPromiseThisIsNonZeroUsize,
+    },
+    /// Keeps stepping until stopped or one of the [`StopConditions`] is met.
+    Continuous {
+        /// Seconds between the snapshots of the chain that are shown while it runs.
+        snapshot_interval: f32,
+        stop: StopConditions,
+    },
+    /// Single steps, each shown as proposal, acceptance test and move, see [`Rwmh::step_traced`].
+    Animated {
+        /// Seconds each step is shown for.
+        step_duration: f32,
+    },
+}
+
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+impl Default for ProgressMode {
+    fn default() -> Self {
+        Self::Batched {
+            size: const { IPromiseThisIsNonZeroUsize::new(DEFAULT_BATCH_SIZE) },
+        }
+    }
+}
+
+impl ProgressMode {
+    pub fn continuous() -> Self {
+        Self::Continuous {
+            snapshot_interval: 0.1,
+            stop: StopConditions::default(),
+        }
+    }
+
+    pub const fn animated() -> Self {
+        Self::Animated { step_duration: 1.5 }
+    }
+
+    pub const fn batch_size(&self) -> Option<usize> {
+        match *self {
+            Self::Batched { ref size } => Some(size.get_inner()),
+            Self::Continuous { .. } | Self::Animated { .. } => None,
+        }
+    }
+}
+
+/// Ends a continuous run once any of the set conditions is met.
+#[cfg_persistence_derive]
+#[derive(Default, Clone, PartialEq)]
+#[cfg_educe_debug]
+pub struct StopConditions {
+    /// Steps since the run was started.
+    pub max_steps: Option<u32>,
+    /// Of the whole chain, see [`crate::metrics::effective_sample_size`].
+    pub target_ess: Option<f64>,
+    /// Seconds spent running since the run was started, pauses excluded.
+    pub time_limit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    MaxSteps,
+    TargetEss,
+    TimeLimit,
+}
+
+impl StopReason {
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::MaxSteps => "reached the maximum number of steps",
+            Self::TargetEss => "reached the target effective sample size",
+            Self::TimeLimit => "reached the time limit",
+        }
+    }
+}
+
+/// How to deal with proposals that leave the support of the target distribution.
+#[cfg_persistence_derive]
+#[derive(Default, Clone, Copy, PartialEq)]
+#[cfg_educe_debug]
+pub enum OutOfSupport {
+    /// The target density is zero there, so these proposals are always rejected.
+    #[default]
+    Reject,
+    /// Mirror the proposal on the boundary of the support.
+    Reflect,
+    /// Do the random walk in an unconstrained space, and map into the support.
+    /// The acceptance ratio is corrected with the jacobian of that mapping.
+    ///
+    /// Falls back to [`Self::Reject`] if the support has no such mapping.
+    Transform,
+}
+
+impl OutOfSupport {
+    pub const VARIANTS: &'static [Self] = &[Self::Reject, Self::Reflect, Self::Transform];
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Reject => "Reject",
+            Self::Reflect => "Reflect",
+            Self::Transform => "Transform",
+        }
+    }
+
+    pub const fn explanation(&self) -> &'static str {
+        match *self {
+            Self::Reject => {
+                "Proposals outside of the support are rejected, the chain stays where it is. Lots of rejections close to the boundary."
+            }
+            Self::Reflect => {
+                "Proposals outside of the support are mirrored on its boundary. The proposal stays symmetric, so no correction is required."
+            }
+            Self::Transform => {
+                "The random walk happens in an unconstrained space (e.g. log of the distance to a half-plane boundary), with a jacobian correction. Not available for polygons."
+            }
+        }
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Default, Clone, PartialEq)]
+#[cfg_educe_debug]
+pub struct AlgoParams {
+    pub proposal: GaussianProposal,
+    pub accept: RngIter<Percentage>,
+    pub progress_mode: ProgressMode,
+    #[serde(default)]
+    pub out_of_support: OutOfSupport,
+    /// Where the chain starts, see [`Rwmh::new`].
+    #[serde(default)]
+    pub start: [f32; 2],
+}
+
+// horrible name but I cant think of something better RN.
+pub type AlgoVec = nalgebra::Vector2<f32>;
+
+impl AlgoParams {
+    fn propose(&mut self, start_loc: AlgoVec) -> AlgoVec {
+        let GaussianProposal {
+            sigma,
+            rng: ref mut prop_rng,
+        } = self.proposal;
+
+        let normal_x = start_loc.x + prop_rng.unwrapped_next() * sigma;
+        let normal_y = start_loc.y + prop_rng.unwrapped_next() * sigma;
+        AlgoVec::new(normal_x, normal_y)
+    }
+
+    fn propose_high_dimensional(&mut self, start_loc: &[f32]) -> Vec<f32> {
+        let GaussianProposal {
+            sigma,
+            rng: ref mut prop_rng,
+        } = self.proposal;
+
+        start_loc
+            .iter()
+            .map(|&val| val + prop_rng.unwrapped_next() * sigma)
+            .collect()
+    }
+}
+
+/// What happened in a single step, to show it step by step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepTrace {
+    /// Position before the step, projected for high dimensional targets.
+    pub from: AlgoVec,
+    /// After reflection or the mapping into the support, where that applies.
+    pub proposal: AlgoVec,
+    /// Ratio of the target densities at the proposal and the current position,
+    /// including the jacobian correction of [`OutOfSupport::Transform`].
+    pub acceptance_ratio: f32,
+    /// Uniform draw in `[0, 1)`, the proposal is accepted if it's at most [`Self::acceptance_ratio`].
+    pub uniform: f32,
+    pub accepted: bool,
+}
+
+/// Layout matches `RWMHAcceptRecord` in the shaders, so the history can be uploaded to the GPU as is.
+#[cfg_persistence_derive]
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct AcceptRecord {
+    pub position: [f32; 2],
+    pub remain_count: u32,
+    pub _pad: [u32; 1],
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone)]
+#[cfg_educe_debug]
+pub struct Rwmh {
+    pub current_loc: AcceptRecord,
+    pub max_remain_count: u32,
+    pub total_point_count: u32,
+    // should be HashMap<AlgoVec, i32> or similar,
+    // but this is an issue as the f32 in AlgoVec isnt Eq.
+    // So IDK how to do this right.
+    #[educe(Debug(method(debug_fmt_vec_as_len)))]
+    pub history: Vec<AcceptRecord>,
+    #[educe(Debug(method(debug_fmt_vec_as_len)))]
+    pub rejected_history: Vec<AlgoVec>,
+    pub params: AlgoParams,
+    /// Only used for targets with more than 2 dimensions, [`Self::current_loc`] holds the projection of it.
+    #[serde(default)]
+    pub current_full: Vec<f32>,
+    /// Full positions of [`Self::history`] (without its first dummy element), flattened.
+    /// Empty for 2 dimensional targets.
+    #[serde(default)]
+    #[educe(Debug(method(debug_fmt_vec_as_len)))]
+    pub full_history: Vec<f32>,
+    /// Full positions of [`Self::rejected_history`], flattened.
+    #[serde(default)]
+    #[educe(Debug(method(debug_fmt_vec_as_len)))]
+    pub full_rejected_history: Vec<f32>,
+}
+
+/// Prefix sums over the run-length encoded [`Rwmh::history`],
+/// to cut the chain at any iteration with a binary search, see [`Rwmh::truncated`].
+#[derive(Debug, Clone, Default)]
+pub struct HistoryIndex {
+    /// Step at which each record of the history, without the first dummy element, was left.
+    left_at: Vec<u32>,
+    /// Maximum `remain_count` among the records up to each one.
+    max_remain: Vec<u32>,
+}
+
+impl HistoryIndex {
+    pub fn new(algo: &Rwmh) -> Self {
+        let records = algo.history.iter().skip(1);
+        let left_at = records
+            .clone()
+            .scan(0, |steps, record| {
+                *steps += record.remain_count + 1;
+                Some(*steps)
+            })
+            .collect();
+        let max_remain = records
+            .scan(0, |max, record| {
+                *max = record.remain_count.max(*max);
+                Some(*max)
+            })
+            .collect();
+        Self {
+            left_at,
+            max_remain,
+        }
+    }
+}
+
+#[cfg(feature = "more_debug_impls")]
+fn debug_fmt_vec_as_len<T>(s: &[T], f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "Vec<len={len}>", len = s.len())
+}
+
+impl Default for Rwmh {
+    fn default() -> Self {
+        Self {
+            current_loc: AcceptRecord {
+                position: [0.0; 2],
+                ..Default::default()
+            },
+            max_remain_count: 0,
+            total_point_count: 0,
+            // ugly hack around forbidden buffersize zero
+            history: vec![AcceptRecord {
+                _pad: [0; 1],
+                position: [0.0; 2],
+                remain_count: 0,
+            }],
+            rejected_history: vec![],
+            params: Default::default(),
+            current_full: vec![],
+            full_history: vec![],
+            full_rejected_history: vec![],
+        }
+    }
+}
+
+impl Rwmh {
+    /// Steps taken since the start, both accepted and rejected ones.
+    pub const fn step_count(&self) -> u32 {
+        self.total_point_count + self.current_loc.remain_count
+    }
+
+    /// The chain as it was after `steps` steps, at most [`Self::step_count`], for replaying it.
+    ///
+    /// `index` has to be built from this chain.
+    /// Besides the binary search this only copies the part of the chain that is kept.
+    /// The RNG states aren't rewound, so the result is meant for displaying, not for continuing it.
+    pub fn truncated(&self, index: &HistoryIndex, steps: u32) -> Self {
+        let steps = steps.min(self.step_count());
+        // accepted moves in the first `steps` steps.
+        let moves = index.left_at.partition_point(|&left_at| left_at <= steps);
+        let before = moves
+            .checked_sub(1)
+            .and_then(|last| index.left_at.get(last))
+            .copied()
+            .unwrap_or(0);
+        let mut current_loc = self
+            .history
+            .get(moves + 1)
+            .copied()
+            .unwrap_or(self.current_loc);
+        current_loc.remain_count = steps - before;
+        let max_before = moves
+            .checked_sub(1)
+            .and_then(|last| index.max_remain.get(last))
+            .copied()
+            .unwrap_or(0);
+
+        let dim = self.current_full.len();
+        let rejected = (steps as usize - moves).min(self.rejected_history.len());
+        let prefix = |values: &[f32], len: usize| values.get(..len).unwrap_or(values).to_vec();
+        let current_full = self
+            .full_history
+            .get(moves * dim..(moves + 1) * dim)
+            .unwrap_or(&self.current_full)
+            .to_vec();
+        Self {
+            current_loc,
+            max_remain_count: max_before.max(current_loc.remain_count),
+            total_point_count: before,
+            history: self.history.get(..=moves).unwrap_or(&self.history).to_vec(),
+            rejected_history: self
+                .rejected_history
+                .get(..rejected)
+                .unwrap_or(&self.rejected_history)
+                .to_vec(),
+            params: self.params.clone(),
+            current_full,
+            full_history: prefix(&self.full_history, moves * dim),
+            full_rejected_history: prefix(&self.full_rejected_history, rejected * dim),
+        }
+    }
+
+    /// A fresh chain at [`AlgoParams::start`].
+    /// On targets with more than 2 dimensions, that's where the projection starts, all other coordinates start at 0.
+    pub fn new(params: AlgoParams) -> Self {
+        Self {
+            current_loc: AcceptRecord {
+                position: params.start,
+                ..Default::default()
+            },
+            params,
+            ..Default::default()
+        }
+    }
+
+    /// A chain that wasn't sampled here, e.g. the draws of another sampler.
+    ///
+    /// Consecutive records at the same position are merged, just like rejections would have been.
+    /// Returns `None` without any records.
+    #[expect(
+        clippy::float_cmp,
+        reason = "only exact repetitions can come from rejections"
+    )]
+    pub fn from_records(records: impl IntoIterator<Item = AcceptRecord>) -> Option<Self> {
+        let mut records = records.into_iter();
+        let mut algo = Self {
+            current_loc: records.next()?,
+            ..Default::default()
+        };
+        for record in records {
+            if record.position == algo.current_loc.position {
+                algo.current_loc.remain_count += record.remain_count + 1;
+            } else {
+                algo.total_point_count += algo.current_loc.remain_count + 1;
+                algo.history.push(algo.current_loc);
+                algo.current_loc = record;
+            }
+            algo.max_remain_count = algo.max_remain_count.max(algo.current_loc.remain_count);
+        }
+        algo.max_remain_count = algo.max_remain_count.max(algo.current_loc.remain_count);
+        Some(algo)
+    }
+
+    pub fn step<T: Density + ?Sized>(&mut self, target_distr: &T) {
+        self.step_traced(target_distr);
+    }
+
+    /// Like [`Self::step`], also returning what happened.
+    pub fn step_traced<T: Density + ?Sized>(&mut self, target_distr: &T) -> StepTrace {
+        let current = &mut self.current_loc;
+        let current_pos = AlgoVec::from(current.position);
+        let support = target_distr.support();
+        let unconstrained_current = match self.params.out_of_support {
+            OutOfSupport::Transform => support.to_unconstrained(current_pos),
+            OutOfSupport::Reject | OutOfSupport::Reflect => None,
+        };
+        let (proposal, acceptance_ratio) = if let Some(unconstrained_current) =
+            unconstrained_current
+        {
+            let unconstrained_proposal = self.params.propose(unconstrained_current);
+            let proposal = support.to_constrained(unconstrained_proposal);
+            let jacobian_ratio = support.unconstrained_jacobian(unconstrained_proposal)
+                / support.unconstrained_jacobian(unconstrained_current);
+            (
+                proposal,
+                target_distr.compute_acceptance_ratio(proposal, current_pos) * jacobian_ratio,
+            )
+        } else {
+            let proposal = self.params.propose(current_pos);
+            let proposal = match self.params.out_of_support {
+                OutOfSupport::Reflect => support.reflect(current_pos, proposal).unwrap_or(proposal),
+                OutOfSupport::Reject | OutOfSupport::Transform => proposal,
+            };
+            (
+                proposal,
+                target_distr.compute_acceptance_ratio(proposal, current_pos),
+            )
+        };
+        let uniform = self.params.accept.unwrapped_next();
+        let accept = uniform <= acceptance_ratio;
+        // self.current_loc = if accept { proposal } else { current };
+        if accept {
+            self.total_point_count += self.current_loc.remain_count + 1;
+            self.history.push(self.current_loc);
+            self.current_loc = AcceptRecord {
+                position: [proposal.x, proposal.y],
+                remain_count: 0,
+                _pad: [0; 1],
+            };
+        } else {
+            current.remain_count += 1;
+            self.max_remain_count = self.max_remain_count.max(current.remain_count);
+            self.rejected_history.push(proposal);
+        };
+        StepTrace {
+            from: current_pos,
+            proposal,
+            acceptance_ratio,
+            uniform,
+            accepted: accept,
+        }
+    }
+
+    /// Like [`Self::step`], for targets with more than 2 dimensions.
+    ///
+    /// Bounded supports don't exist for these, so there is no handling of proposals outside of it.
+    pub fn step_high_dimensional(&mut self, target: &HighDimensional) {
+        self.step_high_dimensional_traced(target);
+    }
+
+    /// Like [`Self::step_high_dimensional`], also returning what happened in the projection.
+    pub fn step_high_dimensional_traced(&mut self, target: &HighDimensional) -> StepTrace {
+        let from = AlgoVec::from(self.current_loc.position);
+        if self.current_full.is_empty() {
+            // a fresh chain, which starts where the 2D one does.
+            self.current_full = target.embed(self.current_loc.position);
+        }
+        self.current_full.resize(target.dim(), 0.0);
+        let proposal = self.params.propose_high_dimensional(&self.current_full);
+        let acceptance_ratio =
+            (target.log_density(&proposal) - target.log_density(&self.current_full)).exp() as f32;
+        let uniform = self.params.accept.unwrapped_next();
+        let accept = uniform <= acceptance_ratio;
+        let projected = target.project(&proposal);
+        if accept {
+            self.total_point_count += self.current_loc.remain_count + 1;
+            self.history.push(self.current_loc);
+            self.full_history.extend_from_slice(&self.current_full);
+            self.current_loc = AcceptRecord {
+                position: [projected.x, projected.y],
+                remain_count: 0,
+                _pad: [0; 1],
+            };
+            self.current_full = proposal;
+        } else {
+            self.current_loc.remain_count += 1;
+            self.max_remain_count = self.max_remain_count.max(self.current_loc.remain_count);
+            self.rejected_history.push(projected);
+            self.full_rejected_history.extend_from_slice(&proposal);
+        }
+        StepTrace {
+            from,
+            proposal: projected,
+            acceptance_ratio,
+            uniform,
+            accepted: accept,
+        }
+    }
+
+    /// Recomputes the 2D positions, after the projection of a high dimensional target changed.
+    pub fn reproject(&mut self, target: &HighDimensional) {
+        let dim = target.dim();
+        let current = target.project(&self.current_full);
+        self.current_loc.position = [current.x, current.y];
+        for (record, full) in self
+            .history
+            .iter_mut()
+            .skip(1)
+            .zip(self.full_history.chunks_exact(dim))
+        {
+            let projected = target.project(full);
+            record.position = [projected.x, projected.y];
+        }
+        self.rejected_history = self
+            .full_rejected_history
+            .chunks_exact(dim)
+            .map(|full| target.project(full))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::target_distr::Gaussian;
+
+    #[test]
+    fn traces_match_the_chain() {
+        let target = Gaussian::default();
+        let mut traced = Rwmh::default();
+        let mut plain = Rwmh::default();
+        let mut accepted = 0;
+        for _ in 0..1_000 {
+            let before = AlgoVec::from(traced.current_loc.position);
+            let trace = traced.step_traced(&target);
+            plain.step(&target);
+            assert_eq!(trace.from, before);
+            assert_eq!(trace.accepted, trace.uniform <= trace.acceptance_ratio);
+            if trace.accepted {
+                accepted += 1;
+                assert_eq!(AlgoVec::from(traced.current_loc.position), trace.proposal);
+            } else {
+                assert_eq!(traced.rejected_history.last(), Some(&trace.proposal));
+            }
+        }
+        assert_eq!(traced.history, plain.history);
+        assert_eq!(traced.current_loc, plain.current_loc);
+        assert!(0 < accepted && accepted < 1_000, "{accepted}");
+    }
+
+    #[test]
+    fn high_dimensional_chains_start_at_the_start_point() {
+        let start = [0.5, -0.5];
+        let target = HighDimensional {
+            projection: [2, 3],
+            ..Default::default()
+        };
+        let mut algo = Rwmh::new(AlgoParams {
+            start,
+            ..Default::default()
+        });
+        let trace = algo.step_high_dimensional_traced(&target);
+        assert_eq!(trace.from, AlgoVec::from(start));
+        let initial = if trace.accepted {
+            algo.full_history.get(..target.dim())
+        } else {
+            Some(algo.current_full.as_slice())
+        };
+        assert_eq!(initial, Some(target.embed(start).as_slice()));
+    }
+
+    #[test]
+    fn truncated_chains_match_earlier_states() {
+        let target = Gaussian::default();
+        let mut algo = Rwmh::default();
+        let mut states = vec![algo.clone()];
+        for _ in 0..300 {
+            algo.step(&target);
+            states.push(algo.clone());
+        }
+        let index = HistoryIndex::new(&algo);
+        for (steps, state) in (0..).zip(&states) {
+            let truncated = algo.truncated(&index, steps);
+            assert_eq!(truncated.step_count(), steps);
+            assert_eq!(truncated.history, state.history);
+            assert_eq!(truncated.current_loc, state.current_loc);
+            assert_eq!(truncated.rejected_history, state.rejected_history);
+            assert_eq!(truncated.max_remain_count, state.max_remain_count);
+        }
+    }
+}
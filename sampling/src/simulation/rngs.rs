@@ -1,12 +1,9 @@
-use std::{borrow::Borrow, fmt::Display};
+use std::fmt::Display;
 
-use egui::Slider;
 use macros::{cfg_educe_debug, cfg_persistence_derive};
 use rand::Rng;
 use rand_distr::{Distribution, Uniform};
 
-use crate::helpers::TempStateDataAccess;
-
 macro_rules! declare_rng_wrapper_macro {
     ($macro_name: ident, mod $path: tt) => {
         #[macro_export]
@@ -42,7 +39,7 @@ macro_rules! declare_rng_wrappers {
         )+
 
         #[cfg_persistence_derive]
-        #[derive(Clone)]
+        #[derive(Clone, PartialEq)]
         #[cfg_educe_debug]
         pub enum WrappedRng {
             $(
@@ -226,7 +223,7 @@ mod rng_wrappers {
     }
 }
 
-use rng_wrappers::*;
+pub use rng_wrappers::*;
 
 impl WrappedRngDiscriminants {
     pub const fn explanation(&self) -> &'static str {
@@ -275,25 +272,49 @@ impl Display for WrappedRngDiscriminants {
     }
 }
 
+/// Seed of the default RNGs, in the GUI and the headless runner alike.
+pub const DEFAULT_SEED: u64 = 42;
+
 // TODO: actually remove the enum in here and use the raw RNG. Should be possible.
 // But I've spend enough time on this for now, so I'll get to it whenever I do.
 #[cfg_persistence_derive]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 #[cfg_educe_debug]
 pub struct RngIter<Distr: Distribution<f32>> {
     pub rng: WrappedRng,
     distr: Distr,
+    /// What [`Self::rng`] was last seeded with, recorded so that runs can be reproduced.
+    /// `None` if unknown.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl<T: Default + Distribution<f32>> Default for RngIter<T> {
     fn default() -> Self {
         Self {
-            rng: WrappedRngDiscriminants::Pcg64Mcg.seed_from_u64(42),
+            rng: WrappedRngDiscriminants::Pcg64Mcg.seed_from_u64(DEFAULT_SEED),
             distr: Default::default(),
+            seed: Some(DEFAULT_SEED),
         }
     }
 }
 
+impl<Distr: Distribution<f32>> RngIter<Distr> {
+    pub fn reseed(&mut self, kind: WrappedRngDiscriminants, seed: u64) {
+        self.rng = kind.seed_from_u64(seed);
+        self.seed = Some(seed);
+    }
+
+    /// Back to the state right after seeding, `false` if the seed is unknown.
+    pub fn rewind(&mut self) -> bool {
+        let Some(seed) = self.seed else {
+            return false;
+        };
+        self.reseed(WrappedRngDiscriminants::from(&self.rng), seed);
+        true
+    }
+}
+
 impl<Distr: Distribution<f32>> Iterator for RngIter<Distr> {
     type Item = f32;
     #[inline(always)]
@@ -311,13 +332,15 @@ impl<Distr: Distribution<f32>> RngIter<Distr> {
     //     Self { rng, distr }
     // }
 
+    /// # Panics
+    /// Never, the iterator is infinite.
     pub fn unwrapped_next(&mut self) -> f32 {
         self.next().expect("infinite iterator")
     }
 }
 
 #[cfg_persistence_derive]
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq)]
 #[cfg_educe_debug]
 pub struct Percentage;
 
@@ -329,7 +352,7 @@ impl Distribution<f32> for Percentage {
 
 /// Recreated just to implement default
 #[cfg_persistence_derive]
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq)]
 #[cfg_educe_debug]
 pub struct StandardNormal;
 
@@ -339,51 +362,6 @@ impl Distribution<f32> for StandardNormal {
     }
 }
 
-impl WrappedRngDiscriminants {
-    pub fn selection_ui(&mut self, ui: &mut egui::Ui) {
-        for ele in Self::VARIANTS.iter() {
-            ui.selectable_value(self, *ele, ele.display_name())
-                .on_hover_text(ele.explanation());
-        }
-    }
-}
-
-impl WrappedRng {
-    pub fn settings_ui(&mut self, ui: &mut egui::Ui, id: egui::Id) {
-        #[derive(Clone, Copy)]
-        struct Settings {
-            discr: WrappedRngDiscriminants,
-            seed: u64,
-        }
-
-        let current_settings = ui.temp_ui_state::<Settings>().with_id(id).get();
-
-        if let Some(mut current_settings) = current_settings {
-            current_settings.discr.selection_ui(ui);
-            // If I set this to u64::MAX to provide all options, its not realistically possible to select many values.
-            ui.add(Slider::new(&mut current_settings.seed, 0..=300).text("Seed"));
-
-            if ui.button("apply").clicked() {
-                *self = current_settings.discr.seed_from_u64(current_settings.seed);
-                ui.temp_ui_state::<Settings>().with_id(id).remove();
-            } else {
-                ui.temp_ui_state::<Settings>()
-                    .with_id(id)
-                    .set_or_create(current_settings);
-            }
-        } else {
-            let current_rng_setting = WrappedRngDiscriminants::from(self.borrow());
-            ui.label("Current RNG:");
-            if ui.button(format!("{current_rng_setting}")).clicked() {
-                ui.temp_ui_state::<Settings>().with_id(id).create(Settings {
-                    discr: WrappedRngDiscriminants::from(self.borrow()),
-                    seed: 42,
-                });
-            }
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
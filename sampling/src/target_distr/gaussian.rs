@@ -4,12 +4,25 @@ use macros::cfg_persistence_derive;
 
 use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
 
-pub use crate::visualizations::NormalDistribution;
+use super::{Density, Support};
 
+/// Layout matches `NormalDistribution` in the shaders, so the elements can be uploaded to the GPU as is.
 #[cfg_persistence_derive]
-#[derive(Clone)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct NormalDistribution {
+    pub position: [f32; 2],
+    pub variance: f32,
+    /// Weight in relation to the other elements.
+    pub scale: f32,
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
 pub struct Distr {
     pub gaussians: Vec<NormalDistribution>,
+    #[serde(default)]
+    pub support: Support,
 }
 
 impl Default for Distr {
@@ -43,12 +56,17 @@ impl Default for Distr {
                 },
             ]
             .into(),
+            support: Default::default(),
         }
     }
 }
 
-impl Distr {
-    pub fn calculate_probability_density(&self, position: AlgoVec) -> f32 {
+impl Density for Distr {
+    fn calculate_probability_density(&self, position: AlgoVec) -> f32 {
+        if !self.support.contains(position) {
+            return 0.0;
+        }
+
         let mut total_weighted_density = 0.0;
 
         let mut scaling_factor = 0.0;
@@ -72,8 +90,7 @@ impl Distr {
         total_weighted_density
     }
 
-    /// this is NOT limited to legal range, cause its really not required.
-    pub fn compute_acceptance_ratio(&self, proposal: AlgoVec, current: AlgoVec) -> f32 {
-        self.calculate_probability_density(proposal) / self.calculate_probability_density(current)
+    fn support(&self) -> &Support {
+        &self.support
     }
 }
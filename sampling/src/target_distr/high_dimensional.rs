@@ -0,0 +1,226 @@
+use std::f64::consts::TAU;
+
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+#[cfg_persistence_derive]
+#[derive(Clone, Copy, PartialEq)]
+pub enum HighDimModel {
+    /// Zero mean, all coordinates with the same standard deviation and the same correlation between every pair of them.
+    CorrelatedGaussian {
+        dim: usize,
+        sd: f32,
+        correlation: f32,
+    },
+    /// Neal's funnel, the simplest hierarchical model.
+    ///
+    /// The first coordinate `v ~ N(0, log_scale_sd²)` sets the scale of all others, `x_i ~ N(0, (base_sd * exp(v))²)`.
+    Funnel {
+        dim: usize,
+        log_scale_sd: f32,
+        base_sd: f32,
+    },
+}
+
+impl HighDimModel {
+    pub const MAX_DIM: usize = 50;
+
+    pub const fn variants(dim: usize) -> [Self; 2] {
+        [
+            Self::CorrelatedGaussian {
+                dim,
+                sd: 0.3,
+                correlation: 0.9,
+            },
+            Self::Funnel {
+                dim,
+                log_scale_sd: 0.5,
+                base_sd: 0.15,
+            },
+        ]
+    }
+
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::CorrelatedGaussian { .. } => "Correlated Gaussian",
+            Self::Funnel { .. } => "Hierarchical (funnel)",
+        }
+    }
+
+    pub const fn dim(&self) -> usize {
+        match *self {
+            Self::CorrelatedGaussian { dim, .. } | Self::Funnel { dim, .. } => dim,
+        }
+    }
+
+    /// The correlation has to stay above this for the covariance matrix to be positive definite.
+    pub fn min_correlation(dim: usize) -> f32 {
+        -1.0 / (dim.max(2) - 1) as f32
+    }
+}
+
+/// A target with more than 2 dimensions, of which the canvas shows the marginal of a pair of coordinates.
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+pub struct HighDimensional {
+    pub model: HighDimModel,
+    /// Coordinates shown along the x and y axis of the canvas.
+    pub projection: [usize; 2],
+}
+
+impl Default for HighDimensional {
+    fn default() -> Self {
+        let [model, _] = HighDimModel::variants(10);
+        Self {
+            model,
+            projection: [0, 1],
+        }
+    }
+}
+
+fn normal_log_density(val: f64, sd: f64) -> f64 {
+    -0.5 * (val / sd).powi(2) - sd.ln() - 0.5 * TAU.ln()
+}
+
+impl HighDimensional {
+    pub const fn dim(&self) -> usize {
+        self.model.dim()
+    }
+
+    pub fn project(&self, position: &[f32]) -> AlgoVec {
+        let [x_axis, y_axis] = self.projection;
+        AlgoVec::new(
+            position.get(x_axis).copied().unwrap_or_default(),
+            position.get(y_axis).copied().unwrap_or_default(),
+        )
+    }
+
+    /// The position that [`Self::project`]s to `position`, with all other coordinates at 0.
+    pub fn embed(&self, position: [f32; 2]) -> Vec<f32> {
+        let mut full = vec![0.0; self.dim()];
+        for (axis, coord) in self.projection.into_iter().zip(position) {
+            if let Some(full_coord) = full.get_mut(axis) {
+                *full_coord = coord;
+            }
+        }
+        full
+    }
+
+    /// Up to an additive constant.
+    pub fn log_density(&self, position: &[f32]) -> f64 {
+        match self.model {
+            HighDimModel::CorrelatedGaussian {
+                dim,
+                sd,
+                correlation,
+            } => {
+                let (sum, sum_of_squares) = position
+                    .iter()
+                    .map(|&val| f64::from(val))
+                    .fold((0.0, 0.0), |(sum, squares), val| {
+                        (sum + val, squares + val * val)
+                    });
+                let correlation = f64::from(correlation);
+                // Closed form of the inverse of an equicorrelation matrix.
+                let quadratic_form = (sum_of_squares
+                    - correlation / (1.0 + (dim as f64 - 1.0) * correlation) * sum * sum)
+                    / (f64::from(sd).powi(2) * (1.0 - correlation));
+                -0.5 * quadratic_form
+            }
+            HighDimModel::Funnel {
+                log_scale_sd,
+                base_sd,
+                ..
+            } => {
+                let Some((&log_scale, rest)) = position.split_first() else {
+                    return 0.0;
+                };
+                let log_scale = f64::from(log_scale);
+                let scale = f64::from(base_sd) * log_scale.exp();
+                normal_log_density(log_scale, f64::from(log_scale_sd))
+                    + rest
+                        .iter()
+                        .map(|&val| normal_log_density(f64::from(val), scale))
+                        .sum::<f64>()
+            }
+        }
+    }
+
+    /// Log density of the marginal distribution of the projected pair of coordinates.
+    pub fn projected_log_density(&self, projected: AlgoVec) -> f64 {
+        let [x_axis, y_axis] = self.projection;
+        let (x, y) = (f64::from(projected.x), f64::from(projected.y));
+        match self.model {
+            HighDimModel::CorrelatedGaussian {
+                sd, correlation, ..
+            } => {
+                // Any pair of coordinates is a 2D Gaussian with the same correlation.
+                let correlation = f64::from(correlation);
+                let variance = f64::from(sd).powi(2);
+                -0.5 * (x * x - 2.0 * correlation * x * y + y * y)
+                    / (variance * (1.0 - correlation * correlation))
+            }
+            HighDimModel::Funnel {
+                log_scale_sd,
+                base_sd,
+                ..
+            } => {
+                let log_scale_sd = f64::from(log_scale_sd);
+                let base_sd = f64::from(base_sd);
+                match (x_axis == 0, y_axis == 0) {
+                    (true, false) => {
+                        normal_log_density(x, log_scale_sd)
+                            + normal_log_density(y, base_sd * x.exp())
+                    }
+                    (false, true) => {
+                        normal_log_density(y, log_scale_sd)
+                            + normal_log_density(x, base_sd * y.exp())
+                    }
+                    _ => {
+                        // Both depend on the scale, which has to be integrated out numerically.
+                        const NODES: usize = 64;
+                        let range = 5.0 * log_scale_sd;
+                        let step = 2.0 * range / NODES as f64;
+                        let integral: f64 = (0..NODES)
+                            .map(|node| {
+                                let log_scale = -range + (node as f64 + 0.5) * step;
+                                let scale = base_sd * log_scale.exp();
+                                (normal_log_density(log_scale, log_scale_sd)
+                                    + normal_log_density(x, scale)
+                                    + normal_log_density(y, scale))
+                                .exp()
+                                    * step
+                            })
+                            .sum();
+                        integral.ln()
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn two_dimensional_marginal_is_the_joint() {
+        let target = HighDimensional {
+            model: HighDimModel::CorrelatedGaussian {
+                dim: 2,
+                sd: 0.4,
+                correlation: 0.7,
+            },
+            projection: [0, 1],
+        };
+        let offset = |position: [f32; 2]| {
+            target.log_density(&position) - target.projected_log_density(position.into())
+        };
+        let reference = offset([0.0, 0.0]);
+        for position in [[0.3, -0.2], [-0.5, -0.6], [0.9, 0.1]] {
+            assert!((offset(position) - reference).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+mod gaussian;
+mod high_dimensional;
+mod posterior;
+mod support;
+
+pub use gaussian::{Distr as Gaussian, NormalDistribution};
+pub use high_dimensional::{HighDimModel, HighDimensional};
+pub use posterior::{Dataset, Model, Posterior, Prior};
+pub use support::Support;
+
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+/// What the sampler needs to know about a target distribution.
+pub trait Density {
+    /// Does not need to be normalized, the sampler only ever looks at ratios.
+    fn calculate_probability_density(&self, position: AlgoVec) -> f32;
+
+    /// this is NOT limited to legal range, cause its really not required.
+    ///
+    /// If the current position is outside of the support this will be infinite (or NaN if both are outside),
+    /// so the chain moves into the support as soon as it can.
+    fn compute_acceptance_ratio(&self, proposal: AlgoVec, current: AlgoVec) -> f32 {
+        self.calculate_probability_density(proposal) / self.calculate_probability_density(current)
+    }
+
+    fn support(&self) -> &Support;
+}
+
+/// Targets that are only evaluated on the CPU, the shader based displays only know about [`Gaussian`].
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+pub enum CpuTarget {
+    Posterior(Posterior),
+    HighDimensional(HighDimensional),
+}
+
+impl CpuTarget {
+    /// Log density of what the canvas shows, up to an additive constant.
+    pub fn canvas_log_density(&self, position: AlgoVec) -> f64 {
+        match *self {
+            Self::Posterior(ref posterior) => posterior.log_density(position),
+            Self::HighDimensional(ref target) => target.projected_log_density(position),
+        }
+    }
+}
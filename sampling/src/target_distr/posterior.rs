@@ -0,0 +1,384 @@
+use std::fmt::Display;
+
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+use super::{Density, Support};
+
+/// Numeric columns of a CSV file.
+#[cfg_persistence_derive]
+#[derive(Clone, Default, PartialEq)]
+pub struct Dataset {
+    /// Where the data came from, only for display.
+    pub name: String,
+    pub headers: Vec<String>,
+    /// Column major, all columns have the same length.
+    pub columns: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CsvError {
+    /// No data rows at all.
+    Empty,
+    /// Line numbers start at 1, like in any text editor.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    NotANumber {
+        line: usize,
+        column: usize,
+    },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Empty => write!(f, "the file contains no data rows"),
+            Self::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} fields, found {found}"),
+            Self::NotANumber { line, column } => {
+                write!(f, "line {line}, column {column}: not a number")
+            }
+        }
+    }
+}
+
+impl Dataset {
+    /// Parses comma, semicolon, tab or whitespace separated values.
+    ///
+    /// The first row is taken as header if any of its fields isn't a number.
+    /// Empty lines and lines starting with `#` are skipped.
+    ///
+    /// # Errors
+    /// If there are no data rows, rows of different length or fields that aren't numbers.
+    pub fn parse_csv(name: impl Into<String>, text: &str) -> Result<Self, CsvError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|&(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .peekable();
+        let Some(&(_, first_line)) = lines.peek() else {
+            return Err(CsvError::Empty);
+        };
+        let delimiter = [',', ';', '\t']
+            .into_iter()
+            .find(|&delimiter| first_line.contains(delimiter));
+        let split = |line: &'_ str| -> Vec<String> {
+            match delimiter {
+                Some(delimiter) => line
+                    .split(delimiter)
+                    .map(|field| field.trim().to_owned())
+                    .collect(),
+                None => line.split_whitespace().map(str::to_owned).collect(),
+            }
+        };
+
+        let first_fields = split(first_line);
+        let headers = if first_fields
+            .iter()
+            .any(|field| field.parse::<f32>().is_err())
+        {
+            lines.next();
+            first_fields
+        } else {
+            (1..=first_fields.len())
+                .map(|column| format!("column {column}"))
+                .collect()
+        };
+
+        let mut columns = vec![Vec::new(); headers.len()];
+        for (line, content) in lines {
+            let fields = split(content);
+            if fields.len() != headers.len() {
+                return Err(CsvError::RaggedRow {
+                    line,
+                    expected: headers.len(),
+                    found: fields.len(),
+                });
+            }
+            for (column_idx, (field, column)) in fields.iter().zip(columns.iter_mut()).enumerate() {
+                let val = field.parse().map_err(|_| CsvError::NotANumber {
+                    line,
+                    column: column_idx + 1,
+                })?;
+                column.push(val);
+            }
+        }
+        if columns.first().is_none_or(Vec::is_empty) {
+            return Err(CsvError::Empty);
+        }
+        Ok(Self {
+            name: name.into(),
+            headers,
+            columns,
+        })
+    }
+
+    pub fn column(&self, idx: usize) -> &[f32] {
+        self.columns.get(idx).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Prior {
+    /// Improper, every value is equally likely.
+    Flat,
+    Normal {
+        mean: f32,
+        sd: f32,
+    },
+}
+
+impl Prior {
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Flat => "Flat",
+            Self::Normal { .. } => "Normal",
+        }
+    }
+
+    /// Up to an additive constant.
+    fn log_density(&self, val: f64) -> f64 {
+        match *self {
+            Self::Flat => 0.0,
+            Self::Normal { mean, sd } => -0.5 * ((val - f64::from(mean)) / f64::from(sd)).powi(2),
+        }
+    }
+}
+
+#[cfg_persistence_derive]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Model {
+    /// `y = intercept + slope * x + noise` with normally distributed noise of known standard deviation.
+    ///
+    /// Parameters are (intercept, slope).
+    LinearRegression {
+        x_column: usize,
+        y_column: usize,
+        noise_sd: f32,
+    },
+    /// The data is normally distributed with unknown mean and standard deviation.
+    ///
+    /// Parameters are (mean, log of standard deviation), the log makes the parameter space unconstrained.
+    Normal { column: usize },
+}
+
+impl Model {
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::LinearRegression { .. } => "Linear regression",
+            Self::Normal { .. } => "Normal",
+        }
+    }
+
+    pub const fn parameter_names(&self) -> [&'static str; 2] {
+        match *self {
+            Self::LinearRegression { .. } => ["intercept", "slope"],
+            Self::Normal { .. } => ["mean", "log sd"],
+        }
+    }
+}
+
+/// Posterior of a 2 parameter model given a [`Dataset`].
+///
+/// The canvas shows a window of the parameter space, positions of the sampler are mapped linearly onto parameters.
+/// As that mapping has a constant jacobian, the acceptance ratios are the same in both spaces.
+#[cfg_persistence_derive]
+#[derive(Clone, PartialEq)]
+pub struct Posterior {
+    pub data: Dataset,
+    pub model: Model,
+    pub priors: [Prior; 2],
+    /// Parameters at the center of the canvas.
+    pub center: [f32; 2],
+    /// Distance in parameter space from the center to the edge of the canvas.
+    /// Note that the canvas y axis points down, the second parameter grows upwards.
+    pub half_extent: [f32; 2],
+}
+
+impl Posterior {
+    pub fn new(data: Dataset) -> Self {
+        let model = if data.columns.len() >= 2 {
+            Model::LinearRegression {
+                x_column: 0,
+                y_column: 1,
+                noise_sd: 1.0,
+            }
+        } else {
+            Model::Normal { column: 0 }
+        };
+        let mut posterior = Self {
+            data,
+            model,
+            priors: [Prior::Flat; 2],
+            center: [0.0; 2],
+            half_extent: [1.0; 2],
+        };
+        posterior.fit_view();
+        posterior
+    }
+
+    pub fn to_parameters(&self, position: AlgoVec) -> [f32; 2] {
+        let [center_x, center_y] = self.center;
+        let [extent_x, extent_y] = self.half_extent;
+        [
+            center_x + extent_x * position.x,
+            center_y - extent_y * position.y,
+        ]
+    }
+
+    /// Up to an additive constant.
+    pub fn log_density(&self, position: AlgoVec) -> f64 {
+        let params = self.to_parameters(position).map(f64::from);
+        let log_prior: f64 = self
+            .priors
+            .iter()
+            .zip(params)
+            .map(|(prior, param)| prior.log_density(param))
+            .sum();
+        let [first, second] = params;
+        let log_likelihood: f64 = match self.model {
+            Model::LinearRegression {
+                x_column,
+                y_column,
+                noise_sd,
+            } => {
+                let noise_sd = f64::from(noise_sd);
+                self.data
+                    .column(x_column)
+                    .iter()
+                    .zip(self.data.column(y_column))
+                    .map(|(&x, &y)| {
+                        let residual = f64::from(y) - first - second * f64::from(x);
+                        -0.5 * (residual / noise_sd).powi(2)
+                    })
+                    .sum()
+            }
+            Model::Normal { column } => {
+                let sd = second.exp();
+                self.data
+                    .column(column)
+                    .iter()
+                    .map(|&val| -0.5 * ((f64::from(val) - first) / sd).powi(2) - second)
+                    .sum()
+            }
+        };
+        log_prior + log_likelihood
+    }
+
+    /// Centers the view on a point estimate and zooms to a few standard errors around it.
+    pub fn fit_view(&mut self) {
+        const STANDARD_ERRORS_TO_EDGE: f64 = 5.0;
+        let Some((estimate, standard_errors)) = self.point_estimate() else {
+            return;
+        };
+        self.center = estimate.map(|val| val as f32);
+        self.half_extent = standard_errors.map(|se| {
+            let extent = (STANDARD_ERRORS_TO_EDGE * se) as f32;
+            if extent.is_normal() { extent } else { 1.0 }
+        });
+    }
+
+    /// Least squares or sample moments, together with their standard errors.
+    fn point_estimate(&self) -> Option<([f64; 2], [f64; 2])> {
+        match self.model {
+            Model::LinearRegression {
+                x_column,
+                y_column,
+                noise_sd,
+            } => {
+                let xs = self.data.column(x_column);
+                let ys = self.data.column(y_column);
+                let count = xs.len().min(ys.len()) as f64;
+                let mean_x = mean(xs)?;
+                let mean_y = mean(ys)?;
+                let (sxx, sxy) = xs.iter().zip(ys).fold((0.0, 0.0), |(sxx, sxy), (&x, &y)| {
+                    let dx = f64::from(x) - mean_x;
+                    (sxx + dx * dx, sxy + dx * (f64::from(y) - mean_y))
+                });
+                let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+                let intercept = mean_y - slope * mean_x;
+                let noise_sd = f64::from(noise_sd);
+                let slope_se = noise_sd / sxx.sqrt();
+                let intercept_se = noise_sd * (1.0 / count + mean_x * mean_x / sxx).sqrt();
+                Some(([intercept, slope], [intercept_se, slope_se]))
+            }
+            Model::Normal { column } => {
+                let vals = self.data.column(column);
+                let count = vals.len() as f64;
+                let sample_mean = mean(vals)?;
+                let variance = vals
+                    .iter()
+                    .map(|&val| (f64::from(val) - sample_mean).powi(2))
+                    .sum::<f64>()
+                    / count;
+                let sd = variance.sqrt();
+                Some((
+                    [sample_mean, sd.ln()],
+                    [sd / count.sqrt(), (2.0 * count).sqrt().recip()],
+                ))
+            }
+        }
+    }
+}
+
+fn mean(vals: &[f32]) -> Option<f64> {
+    (!vals.is_empty())
+        .then(|| vals.iter().map(|&val| f64::from(val)).sum::<f64>() / vals.len() as f64)
+}
+
+/// The log parametrization of the standard deviation means no constraints are required.
+const UNBOUNDED: &Support = &Support::Unbounded;
+
+impl Density for Posterior {
+    fn calculate_probability_density(&self, position: AlgoVec) -> f32 {
+        self.log_density(position).exp() as f32
+    }
+
+    /// Computed on the log scale, the densities themselves easily underflow with a few hundred data points.
+    fn compute_acceptance_ratio(&self, proposal: AlgoVec, current: AlgoVec) -> f32 {
+        (self.log_density(proposal) - self.log_density(current)).exp() as f32
+    }
+
+    fn support(&self) -> &Support {
+        UNBOUNDED
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_header_and_reports_line() {
+        let data = Dataset::parse_csv("test", "x, y\n1, 2\n\n# comment\n3, 4.5\n").unwrap();
+        assert_eq!(data.headers, ["x", "y"]);
+        assert_eq!(data.column(1), [2.0, 4.5]);
+        assert_eq!(
+            Dataset::parse_csv("test", "x,y\n1,2\n3,oops\n").err(),
+            Some(CsvError::NotANumber { line: 3, column: 2 })
+        );
+    }
+
+    #[test]
+    fn view_is_centered_on_mode() {
+        let data = Dataset::parse_csv("test", "1 3.1\n2 4.9\n3 7.2\n4 8.8\n").unwrap();
+        let posterior = Posterior::new(data);
+        let at_center = posterior.log_density(AlgoVec::zeros());
+        for offset in [AlgoVec::new(0.1, 0.0), AlgoVec::new(0.0, -0.1)] {
+            assert!(posterior.log_density(offset) < at_center);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use macros::cfg_persistence_derive;
+
+use crate::simulation::random_walk_metropolis_hastings::AlgoVec;
+
+/// Region the target distribution is restricted to.
+/// The density of the target is zero outside of it.
+#[cfg_persistence_derive]
+#[derive(Clone, Default, PartialEq)]
+pub enum Support {
+    #[default]
+    Unbounded,
+    /// Axis aligned box, limits are inclusive.
+    Box { min: [f32; 2], max: [f32; 2] },
+    /// All points `x` with `normal · x >= offset`.
+    HalfPlane { normal: [f32; 2], offset: f32 },
+    /// Closed polygon, the last vertex connects back to the first one.
+    ///
+    /// Polygons with less than 3 vertices are treated as unbounded, so that the support doesn't collapse while the user is still drawing.
+    Polygon { vertices: Vec<[f32; 2]> },
+}
+
+/// Upper limit of boundary reflections of a single proposal in a polygon.
+/// Proposals that need more are rejected instead.
+const MAX_POLYGON_REFLECTIONS: usize = 16;
+
+impl Support {
+    pub const fn display_name(&self) -> &'static str {
+        match *self {
+            Self::Unbounded => "Unbounded",
+            Self::Box { .. } => "Box",
+            Self::HalfPlane { .. } => "Half-plane",
+            Self::Polygon { .. } => "Polygon",
+        }
+    }
+
+    /// A default instance of every kind, to select from.
+    pub fn variants() -> [Self; 4] {
+        [
+            Self::Unbounded,
+            Self::Box {
+                min: [-0.8, -0.8],
+                max: [0.8, 0.8],
+            },
+            // x > 0, the classic positivity constraint.
+            Self::HalfPlane {
+                normal: [1.0, 0.0],
+                offset: 0.0,
+            },
+            Self::Polygon {
+                vertices: vec![[-0.6, -0.6], [0.6, -0.6], [0.0, 0.7]],
+            },
+        ]
+    }
+
+    pub fn contains(&self, point: AlgoVec) -> bool {
+        match *self {
+            Self::Unbounded => true,
+            Self::Box { min, max } => {
+                (min[0]..=max[0]).contains(&point.x) && (min[1]..=max[1]).contains(&point.y)
+            }
+            Self::HalfPlane { normal, offset } => AlgoVec::from(normal).dot(&point) >= offset,
+            Self::Polygon { ref vertices } => {
+                vertices.len() < 3 || polygon_contains(vertices, point)
+            }
+        }
+    }
+
+    /// Mirror a proposal that left the support back into it.
+    ///
+    /// Returns `None` if that is not possible, the proposal is to be rejected then.
+    pub fn reflect(&self, from: AlgoVec, proposal: AlgoVec) -> Option<AlgoVec> {
+        if self.contains(proposal) {
+            return Some(proposal);
+        }
+        match *self {
+            Self::Unbounded => Some(proposal),
+            Self::Box { min, max } => Some(AlgoVec::new(
+                fold_into(proposal.x, min[0], max[0]),
+                fold_into(proposal.y, min[1], max[1]),
+            )),
+            Self::HalfPlane { normal, offset } => {
+                let (unit_normal, threshold) = normalized_half_plane(normal, offset)?;
+                let distance = unit_normal.dot(&proposal) - threshold;
+                Some(proposal - 2.0 * distance * unit_normal)
+            }
+            Self::Polygon { ref vertices } => {
+                if !polygon_contains(vertices, from) {
+                    // Reflecting requires a path inside the polygon, which we don't have.
+                    return None;
+                }
+                reflect_in_polygon(vertices, from, proposal)
+            }
+        }
+    }
+
+    /// Map a point of the support into an unconstrained space, where proposals can't leave the support.
+    ///
+    /// Returns `None` if no such transformation is implemented for this kind of support, or the point is outside of the support.
+    pub fn to_unconstrained(&self, point: AlgoVec) -> Option<AlgoVec> {
+        match *self {
+            Self::Unbounded => Some(point),
+            Self::Box { min, max } => Some(AlgoVec::new(
+                logit((point.x - min[0]) / (max[0] - min[0]))?,
+                logit((point.y - min[1]) / (max[1] - min[1]))?,
+            )),
+            Self::HalfPlane { normal, offset } => {
+                let (unit_normal, threshold) = normalized_half_plane(normal, offset)?;
+                let distance = unit_normal.dot(&point) - threshold;
+                (distance > 0.0).then(|| {
+                    let tangent = AlgoVec::new(-unit_normal.y, unit_normal.x);
+                    AlgoVec::new(distance.ln(), tangent.dot(&point))
+                })
+            }
+            Self::Polygon { .. } => None,
+        }
+    }
+
+    /// Inverse of [`Self::to_unconstrained`].
+    ///
+    /// Only valid for kinds of support where [`Self::to_unconstrained`] is implemented.
+    pub fn to_constrained(&self, unconstrained: AlgoVec) -> AlgoVec {
+        match *self {
+            Self::Unbounded | Self::Polygon { .. } => unconstrained,
+            Self::Box { min, max } => AlgoVec::new(
+                min[0] + (max[0] - min[0]) * sigmoid(unconstrained.x),
+                min[1] + (max[1] - min[1]) * sigmoid(unconstrained.y),
+            ),
+            Self::HalfPlane { normal, offset } => {
+                let Some((unit_normal, threshold)) = normalized_half_plane(normal, offset) else {
+                    return unconstrained;
+                };
+                let tangent = AlgoVec::new(-unit_normal.y, unit_normal.x);
+                unit_normal * (threshold + unconstrained.x.exp()) + tangent * unconstrained.y
+            }
+        }
+    }
+
+    /// Absolute value of the jacobian determinant of [`Self::to_constrained`] at the given point.
+    ///
+    /// This is the correction required for the density, as the random walk happens in the unconstrained space.
+    pub fn unconstrained_jacobian(&self, unconstrained: AlgoVec) -> f32 {
+        match *self {
+            Self::Unbounded | Self::Polygon { .. } => 1.0,
+            Self::Box { min, max } => {
+                let sig_x = sigmoid(unconstrained.x);
+                let sig_y = sigmoid(unconstrained.y);
+                (max[0] - min[0])
+                    * sig_x
+                    * (1.0 - sig_x)
+                    * (max[1] - min[1])
+                    * sig_y
+                    * (1.0 - sig_y)
+            }
+            Self::HalfPlane { .. } => unconstrained.x.exp(),
+        }
+    }
+}
+
+fn sigmoid(val: f32) -> f32 {
+    1.0 / (1.0 + (-val).exp())
+}
+
+fn logit(fraction: f32) -> Option<f32> {
+    (fraction > 0.0 && fraction < 1.0).then(|| (fraction / (1.0 - fraction)).ln())
+}
+
+/// Returns the unit normal and the offset in the direction of that unit normal.
+fn normalized_half_plane(normal: [f32; 2], offset: f32) -> Option<(AlgoVec, f32)> {
+    let normal = AlgoVec::from(normal);
+    let norm = normal.norm();
+    (norm > 0.0).then(|| (normal / norm, offset / norm))
+}
+
+/// Reflect the value at the limits until it ends up between them.
+fn fold_into(val: f32, lower: f32, upper: f32) -> f32 {
+    let width = upper - lower;
+    if width <= 0.0 {
+        return lower;
+    }
+    let periodic = (val - lower).rem_euclid(2.0 * width);
+    lower
+        + if periodic > width {
+            2.0 * width - periodic
+        } else {
+            periodic
+        }
+}
+
+fn polygon_edges(vertices: &[[f32; 2]]) -> impl Iterator<Item = (AlgoVec, AlgoVec)> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(&start, &end)| (AlgoVec::from(start), AlgoVec::from(end)))
+}
+
+/// Even-odd rule.
+fn polygon_contains(vertices: &[[f32; 2]], point: AlgoVec) -> bool {
+    let mut inside = false;
+    for (start, end) in polygon_edges(vertices) {
+        if (start.y > point.y) != (end.y > point.y) {
+            let crossing_x = start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x);
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Follows the straight path from `from` to `proposal`, mirroring the remaining path on every edge it hits.
+fn reflect_in_polygon(vertices: &[[f32; 2]], from: AlgoVec, proposal: AlgoVec) -> Option<AlgoVec> {
+    let mut from = from;
+    let mut proposal = proposal;
+    for _ in 0..MAX_POLYGON_REFLECTIONS {
+        let path = proposal - from;
+        let first_hit = polygon_edges(vertices)
+            .filter_map(|(start, end)| {
+                let edge = end - start;
+                let denominator = path.perp(&edge);
+                if denominator == 0.0 {
+                    return None;
+                }
+                let to_start = start - from;
+                let along_path = to_start.perp(&edge) / denominator;
+                let along_edge = to_start.perp(&path) / denominator;
+                // the lower limit avoids hitting the edge we just reflected on again.
+                ((1e-6..=1.0).contains(&along_path) && (0.0..=1.0).contains(&along_edge))
+                    .then_some((along_path, start, edge))
+            })
+            .min_by(|&(lhs, ..), &(rhs, ..)| lhs.total_cmp(&rhs));
+        let Some((along_path, start, edge)) = first_hit else {
+            return polygon_contains(vertices, proposal).then_some(proposal);
+        };
+        let hit = from + path * along_path;
+        let edge_dir = edge.normalize();
+        let rel = proposal - start;
+        proposal = start + 2.0 * rel.dot(&edge_dir) * edge_dir - rel;
+        from = hit;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close_enough(lhs: AlgoVec, rhs: AlgoVec) -> bool {
+        (lhs - rhs).norm() < 1e-4
+    }
+
+    #[test]
+    fn reflections_end_up_inside() {
+        let from = AlgoVec::new(0.0, 0.0);
+        let proposal = AlgoVec::new(1.7, -2.3);
+        for support in Support::variants() {
+            let reflected = support.reflect(from, proposal).unwrap();
+            assert!(support.contains(reflected), "{}", support.display_name());
+        }
+    }
+
+    #[test]
+    fn box_reflection_mirrors_on_limit() {
+        let support = Support::Box {
+            min: [-1.0, -1.0],
+            max: [1.0, 1.0],
+        };
+        let reflected = support
+            .reflect(AlgoVec::zeros(), AlgoVec::new(1.25, 0.5))
+            .unwrap();
+        assert!(close_enough(reflected, AlgoVec::new(0.75, 0.5)));
+    }
+
+    #[test]
+    fn transforms_invert_each_other() {
+        let point = AlgoVec::new(0.3, 0.1);
+        for support in Support::variants() {
+            let Some(unconstrained) = support.to_unconstrained(point) else {
+                continue;
+            };
+            assert!(
+                close_enough(support.to_constrained(unconstrained), point),
+                "{}",
+                support.display_name()
+            );
+        }
+    }
+}